@@ -1,16 +1,17 @@
 use super::parse::CallDef;
-use quote::quote;
+use quote::{format_ident, quote, ToTokens};
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
-	let CallDef { pallet_struct, methods } = def;
+	let CallDef { pallet_struct, methods, attr: def_attr } = def;
 
 	// This is a vector of all the callable function names.
 	let fn_name = methods.iter().map(|method| &method.name).collect::<Vec<_>>();
 
 	// This is a nested vector of all the arguments for each of the functions in `fn_name`. It does
-	// not include the `self` or `caller: T::AccountId` parameter, which we always assume are the
-	// first two parameters to these calls.
+	// not include the `self` or `runtime` parameter, or the `caller: T::AccountId` parameter, or the
+	// `context` parameter which can replace it, which we always assume are the first two parameters
+	// to these calls.
 	let args_name = methods
 		.iter()
 		.map(|method| method.args.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>())
@@ -20,9 +21,183 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 	// `fn_name`. It has the same assumptions as `args_name`.
 	let args_type = methods
 		.iter()
-		.map(|method| method.args.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>())
+		.map(|method| method.args.iter().map(|(_, type_)| (**type_).clone()).collect::<Vec<_>>())
 		.collect::<Vec<_>>();
 
+	// This is a vector of the weight of each of the functions in `fn_name`. Calls without a
+	// `#[weight(..)]` attribute have the default weight. The expression of the attribute can use the
+	// arguments of the call by reference, so the weight can depend on the size of its input.
+	let fn_weight = methods
+		.iter()
+		.map(|method| match &method.weight {
+			Some(weight) => quote! { #weight },
+			None => quote! { crate::support::DEFAULT_WEIGHT },
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of what to pass before the arguments of each of the functions in `fn_name`:
+	// the whole `context` for functions which ask for it, and nothing for privileged functions,
+	// which the runtime checks the origin of. Every other function needs a signed origin, and gets
+	// the account which signed the call as its `caller`.
+	let fn_caller = methods
+		.iter()
+		.map(|method| match (method.takes_context, method.privileged) {
+			(true, _) => quote! { context, },
+			(false, true) => quote! {},
+			(false, false) => quote! { context.origin.ensure_signed()?, },
+		})
+		.collect::<Vec<_>>();
+
+	// This is a vector of the names of the functions in `fn_name` which only privileged origins
+	// can call.
+	let privileged_fn_name = methods
+		.iter()
+		.filter(|method| method.privileged)
+		.map(|method| &method.name)
+		.collect::<Vec<_>>();
+
+	// The extra where-predicates of the runtime, from the `call` attribute. Predicates which do not
+	// bound the runtime `R` itself, like `T::RuntimeCall: GetWeight`, also apply to the weight of
+	// the calls, so the weight of a call can depend on the weight of an inner call.
+	let predicates = &def_attr.predicates;
+	let weight_predicates = predicates
+		.iter()
+		.filter(|predicate| match predicate {
+			syn::WherePredicate::Type(pt) => {
+				!matches!(&pt.bounded_ty, syn::Type::Path(tp) if tp.path.is_ident("R"))
+			},
+			_ => true,
+		})
+		.collect::<Vec<_>>();
+
+	// If some of the calls are privileged, the runtime must also decide who can make them.
+	let privileged_bound = if privileged_fn_name.is_empty() {
+		quote! {}
+	} else {
+		quote! { + crate::support::EnsurePrivileged<T::AccountId> }
+	};
+	let ensure_privileged = if privileged_fn_name.is_empty() {
+		quote! {}
+	} else {
+		quote! {
+			if matches!(call, #( Call::#privileged_fn_name { .. } )|*) {
+				crate::support::EnsurePrivileged::ensure_privileged(self, &context.origin)?;
+			}
+		}
+	};
+
+	// Calls which take the whole `runtime` cannot be dispatched by the pallet on its own, so when
+	// there are any, the runtime dispatches every call itself. Otherwise the runtime simply asks
+	// the pallet to dispatch the call.
+	let takes_runtime = methods.iter().any(|method| method.takes_runtime);
+	let dispatch_call_body = if takes_runtime {
+		let fn_call = methods
+			.iter()
+			.zip(&fn_caller)
+			.zip(&args_name)
+			.map(|((method, caller), args_name)| {
+				let name = &method.name;
+				if method.takes_runtime {
+					quote! { #pallet_struct::<T>::#name(self, #caller #( #args_name ),*) }
+				} else {
+					quote! { AsMut::<#pallet_struct<T>>::as_mut(self).#name(#caller #( #args_name ),*) }
+				}
+			})
+			.collect::<Vec<_>>();
+		quote! {
+			match call {
+				#(
+					Call::#fn_name { #( #args_name ),* } => {
+						#fn_call?;
+					},
+				)*
+			}
+			Ok(())
+		}
+	} else {
+		quote! {
+			crate::support::Dispatch::dispatch(AsMut::<#pallet_struct<T>>::as_mut(self), context, call)
+		}
+	};
+
+	// Dispatch logic at the runtime level. Most calls only need access to this pallet, so any
+	// runtime which can give us mutable access to this pallet can dispatch them, but calls which
+	// take the whole runtime can require more of it with the `call` attribute.
+	let dispatch_call_impl = quote! {
+		impl<T: Config, R> crate::support::DispatchCall<T::AccountId, T::BlockNumber, Call<T>> for R
+		where
+			R: AsMut<#pallet_struct<T>> #privileged_bound,
+			#( #predicates, )*
+		{
+			fn dispatch_call(
+				&mut self,
+				context: crate::support::DispatchContext<T::AccountId, T::BlockNumber>,
+				call: Call<T>,
+			) -> crate::support::DispatchResult {
+				#ensure_privileged
+				#dispatch_call_body
+			}
+		}
+	};
+
+	// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
+	// appropriate function call with all arguments, including the `caller`. This is only possible
+	// when none of the calls take the whole runtime.
+	let pallet_dispatch_impl = if takes_runtime {
+		quote! {}
+	} else {
+		quote! {
+			impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
+				type Caller = T::AccountId;
+				type BlockNumber = T::BlockNumber;
+				type Call = Call<T>;
+
+				fn dispatch(
+					&mut self,
+					context: crate::support::DispatchContext<Self::Caller, Self::BlockNumber>,
+					call: Self::Call,
+				) -> crate::support::DispatchResult {
+					match call {
+						#(
+							Call::#fn_name { #( #args_name ),* } => {
+								self.#fn_name(
+									// Note that we assume the first argument of every call is the
+									// `caller` or the whole `context`, unless it is privileged.
+									#fn_caller
+									#( #args_name ),*
+								)?;
+							},
+						)*
+					}
+					Ok(())
+				}
+			}
+		}
+	};
+
+	// Hooks at the runtime level, which simply run the `Hooks` of this pallet. Pallets whose hooks
+	// need access to other pallets implement this by hand instead.
+	let pallet_hooks_impl = if def_attr.custom_hooks {
+		quote! {}
+	} else {
+		quote! {
+			impl<T: Config, R: AsMut<#pallet_struct<T>>> crate::support::PalletHooks<#pallet_struct<T>, T::BlockNumber>
+				for R
+			{
+				fn on_initialize(&mut self, block_number: T::BlockNumber) {
+					crate::support::Hooks::on_initialize(self.as_mut(), block_number)
+				}
+
+				fn on_finalize(&mut self, block_number: T::BlockNumber) {
+					crate::support::Hooks::on_finalize(self.as_mut(), block_number)
+				}
+			}
+		}
+	};
+
+	// The `serde` attributes of the `Call` enum.
+	let serde_attrs = expand_serde_attrs(&args_type);
+
 	// This quote block creates an `enum Call` which contains all the calls exposed by our pallet,
 	// and the `Dispatch` trait logic to route a `caller` to access those functions.
 	let dispatch_impl = quote! {
@@ -30,35 +205,213 @@ pub fn expand_call(def: CallDef) -> proc_macro2::TokenStream {
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
+		#serde_attrs
 		pub enum Call<T: Config> {
 			#(
 				#fn_name { #( #args_name: #args_type),* },
 			)*
 		}
 
-		// Dispatch logic at the pallet level, mapping each of the items in the `Call` enum to the
-		// appropriate function call with all arguments, including the `caller`.
-		impl<T: Config> crate::support::Dispatch for #pallet_struct<T> {
-			type Caller = T::AccountId;
-			type Call = Call<T>;
+		#pallet_dispatch_impl
+
+		#dispatch_call_impl
+
+		// The weight of each call, which may depend on its arguments.
+		impl<T: Config> crate::support::GetWeight for Call<T>
+		where
+			#( #weight_predicates, )*
+		{
+			#[allow(unused_variables)]
+			fn weight(&self) -> crate::support::Weight {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => #fn_weight,
+					)*
+				}
+			}
+		}
+
+		#pallet_hooks_impl
+	};
+
+	// The traits which are implemented for every `Call` enum.
+	let traits_impl = expand_call_traits(&fn_name, &args_name, &args_type);
+
+	// Return the generated code.
+	quote! {
+		#dispatch_impl
+		#traits_impl
+	}
+	.into()
+}
+
+/// Derive `serde` serialization for a `Call` enum, when the `serde` feature of the runtime crate is
+/// enabled, where the variants of the enum have arguments of types `args_type`.
+///
+pub fn expand_serde_attrs(args_type: &[Vec<syn::Type>]) -> proc_macro2::TokenStream {
+	// Serde cannot always figure out the bounds it needs for our generic `Call` enum, for example
+	// for `Box<T::RuntimeCall>` or `Vec<(T::AccountId, T::Balance)>`, so we tell it to require that
+	// each of the argument types can be serialized.
+	let bounds = |bound: &str| {
+		args_type
+			.iter()
+			.flatten()
+			.map(|ty| format!("{}: {}", ty.to_token_stream(), bound))
+			.collect::<Vec<_>>()
+			.join(", ")
+	};
+	let serialize_bound = bounds("serde::Serialize");
+	let deserialize_bound = bounds("serde::Deserialize<'de>");
+
+	quote! {
+		#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+		#[cfg_attr(
+			feature = "serde",
+			serde(bound(serialize = #serialize_bound, deserialize = #deserialize_bound))
+		)]
+	}
+}
+
+/// Implement `Clone`, `Debug`, `PartialEq`, `Eq`, `Hash`, `support::Encode` and
+/// `support::GetCallName` for a `Call` enum, where each of the variants `fn_name` has the arguments
+/// `args_name` of types `args_type`.
+pub fn expand_call_traits(
+	fn_name: &[&syn::Ident],
+	args_name: &[Vec<syn::Ident>],
+	args_type: &[Vec<syn::Type>],
+) -> proc_macro2::TokenStream {
+	// The same names as `args_name`, used to destructure a second `Call` when comparing two calls.
+	// Leading underscores of unused arguments are dropped, to keep the names in snake case.
+	let other_args_name = args_name
+		.iter()
+		.map(|names| {
+			names
+				.iter()
+				.map(|name| format_ident!("other_{}", name.to_string().trim_start_matches('_')))
+				.collect::<Vec<_>>()
+		})
+		.collect::<Vec<_>>();
+
+	// The quote blocks below implement some common traits for the `Call` enum. We cannot simply
+	// derive them, since that would require `T` itself to implement them, so instead we require that
+	// each of the argument types implement them.
+	let clone_impl = quote! {
+		impl<T: Config> Clone for Call<T>
+		where
+			#( #( #args_type: Clone, )* )*
+		{
+			fn clone(&self) -> Self {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							Call::#fn_name { #( #args_name: #args_name.clone() ),* }
+						},
+					)*
+				}
+			}
+		}
+	};
+
+	let debug_impl = quote! {
+		impl<T: Config> core::fmt::Debug for Call<T>
+		where
+			#( #( #args_type: core::fmt::Debug, )* )*
+		{
+			fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							f.debug_struct(stringify!(#fn_name))
+								#( .field(stringify!(#args_name), #args_name) )*
+								.finish()
+						},
+					)*
+				}
+			}
+		}
+	};
+
+	let eq_impl = quote! {
+		impl<T: Config> PartialEq for Call<T>
+		where
+			#( #( #args_type: PartialEq, )* )*
+		{
+			fn eq(&self, other: &Self) -> bool {
+				#[allow(unreachable_patterns)]
+				match (self, other) {
+					#(
+						(
+							Call::#fn_name { #( #args_name ),* },
+							Call::#fn_name { #( #args_name: #other_args_name ),* },
+						) => true #( && #args_name == #other_args_name )*,
+					)*
+					_ => false,
+				}
+			}
+		}
 
-			fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> crate::support::DispatchResult {
-				match call {
+		impl<T: Config> Eq for Call<T>
+		where
+			#( #( #args_type: Eq, )* )*
+		{}
+	};
+
+	let hash_impl = quote! {
+		impl<T: Config> core::hash::Hash for Call<T>
+		where
+			#( #( #args_type: core::hash::Hash, )* )*
+		{
+			fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+				core::hash::Hash::hash(&core::mem::discriminant(self), state);
+				match self {
 					#(
 						Call::#fn_name { #( #args_name ),* } => {
-							self.#fn_name(
-								// Note that we assume the first argument of every call is the `caller`.
-								caller,
-								#( #args_name ),*
-							)?;
+							#( core::hash::Hash::hash(#args_name, state); )*
 						},
 					)*
 				}
-				Ok(())
 			}
 		}
 	};
 
-	// Return the generated code.
-	dispatch_impl.into()
+	// Like `macros::Encode`, a call is encoded as the index of its variant, followed by its arguments.
+	let fn_index = (0..fn_name.len()).map(|index| index as u8).collect::<Vec<_>>();
+	let encode_impl = quote! {
+		impl<T: Config> crate::support::Encode for Call<T>
+		where
+			#( #( #args_type: crate::support::Encode, )* )*
+		{
+			fn encode_to(&self, encoded: &mut Vec<u8>) {
+				match self {
+					#(
+						Call::#fn_name { #( #args_name ),* } => {
+							encoded.push(#fn_index);
+							#( crate::support::Encode::encode_to(#args_name, encoded); )*
+						},
+					)*
+				}
+			}
+		}
+	};
+
+	let call_name_impl = quote! {
+		impl<T: Config> crate::support::GetCallName for Call<T> {
+			fn call_name(&self) -> &'static str {
+				match self {
+					#(
+						Call::#fn_name { .. } => stringify!(#fn_name),
+					)*
+				}
+			}
+		}
+	};
+
+	quote! {
+		#clone_impl
+		#debug_impl
+		#eq_impl
+		#hash_impl
+		#encode_impl
+		#call_name_impl
+	}
 }
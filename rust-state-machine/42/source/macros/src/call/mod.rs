@@ -3,22 +3,36 @@ pub mod parse;
 
 /// See the `fn call` docs at the `lib.rs` of this crate for a high level definition.
 pub fn call(
-	_attr: proc_macro::TokenStream,
+	attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	// The final expanded code will be placed here.
-	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
-	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	let attr = syn::parse_macro_input!(attr as parse::CallAttr);
+	let mut item_mod = syn::parse_macro_input!(item as syn::Item);
 
 	// First we parse the call functions implemented for the pallet...
-	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(item_mod.clone()) {
+	let generated: proc_macro::TokenStream = match parse::CallDef::try_from(attr, item_mod.clone()) {
 		// ..then we generate our new code.
 		Ok(def) => expand::expand_call(def).into(),
 		Err(e) => e.to_compile_error().into(),
 	};
 
+	// The final expanded code will be placed here.
+	// Since our macro only adds new code, our final product will contain all of our old code too,
+	// except for the `#[weight(..)]` and `#[privileged]` attributes, which are not real attributes.
+	// The arguments of a call are stored in the `Call` enum, so an argument which contains a whole
+	// `RuntimeCall` must be boxed, even if the function itself does not need the box.
+	if let syn::Item::Impl(item_impl) = &mut item_mod {
+		for item in &mut item_impl.items {
+			if let syn::ImplItem::Fn(method) = item {
+				method
+					.attrs
+					.retain(|attr| !parse::is_weight_attr(attr) && !parse::is_privileged_attr(attr));
+				method.attrs.push(syn::parse_quote! { #[allow(clippy::boxed_local)] });
+			}
+		}
+	}
+	let mut finished: proc_macro::TokenStream = quote::quote!(#item_mod).into();
+
 	// Add our generated code to the end, and return the final result.
 	finished.extend(generated);
 	return finished;
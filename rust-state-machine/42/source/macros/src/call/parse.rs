@@ -5,6 +5,39 @@ use syn::spanned::Spanned;
 mod keyword {
 	syn::custom_keyword!(T);
 	syn::custom_keyword!(AccountId);
+	syn::custom_keyword!(custom_hooks);
+}
+
+/// The arguments of the `call` attribute: `custom_hooks`, and the extra where-predicates of the
+/// runtime `R`, like `#[macros::call(custom_hooks, R: AsMut<balances::Pallet<T>>)]`.
+#[derive(Debug, Default)]
+pub struct CallAttr {
+	/// Whether the pallet implements `support::PalletHooks` by hand, so we must not generate it.
+	pub custom_hooks: bool,
+	/// The where-predicates the runtime must satisfy for the calls to be dispatched.
+	pub predicates: Vec<syn::WherePredicate>,
+}
+
+impl syn::parse::Parse for CallAttr {
+	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+		let mut attr = Self::default();
+		while !input.is_empty() {
+			// `custom_hooks` on its own is a flag, anything else is a where-predicate.
+			let fork = input.fork();
+			if fork.parse::<keyword::custom_hooks>().is_ok() &&
+				(fork.is_empty() || fork.peek(syn::Token![,]))
+			{
+				input.parse::<keyword::custom_hooks>()?;
+				attr.custom_hooks = true;
+			} else {
+				attr.predicates.push(input.parse()?);
+			}
+			if !input.is_empty() {
+				input.parse::<syn::Token![,]>()?;
+			}
+		}
+		Ok(attr)
+	}
 }
 
 /// This object will collect all the information we need to keep while parsing the callable
@@ -16,6 +49,8 @@ pub struct CallDef {
 	pub pallet_struct: syn::Ident,
 	/// This is a list of the callable functions exposed by this pallet. See `CallVariantDef`.
 	pub methods: Vec<CallVariantDef>,
+	/// The arguments of the `call` attribute. See `CallAttr`.
+	pub attr: CallAttr,
 }
 
 /// This is the metadata we keep about each callable function in our pallet.
@@ -25,10 +60,21 @@ pub struct CallVariantDef {
 	pub name: syn::Ident,
 	/// Information on args of the function: `(name, type)`.
 	pub args: Vec<(syn::Ident, Box<syn::Type>)>,
+	/// The weight of the call, from its `#[weight(..)]` attribute, if any.
+	pub weight: Option<syn::Expr>,
+	/// Whether the call has the `#[privileged]` attribute, so only privileged origins can make it.
+	/// Unless they take the whole `context`, privileged calls take no `caller` argument.
+	pub privileged: bool,
+	/// Whether the call takes the whole `context: DispatchContext<..>` as its second argument,
+	/// instead of just `caller: T::AccountId`.
+	pub takes_context: bool,
+	/// Whether the call is an associated function which takes the whole `runtime: &mut R` as its
+	/// first argument, instead of a method of the pallet, so it can access other pallets.
+	pub takes_runtime: bool,
 }
 
 impl CallDef {
-	pub fn try_from(item: syn::Item) -> syn::Result<Self> {
+	pub fn try_from(attr: CallAttr, item: syn::Item) -> syn::Result<Self> {
 		// First we check that we are parsing an `impl`.
 		let item_impl = if let syn::Item::Impl(item) = item {
 			item
@@ -50,32 +96,55 @@ impl CallDef {
 				// Here is where we will store all the args for each callable functions.
 				let mut args = vec![];
 
-				// First argument should be some variant of `self`.
-				match method.sig.inputs.first() {
-					Some(syn::FnArg::Receiver(_)) => {},
+				// First argument should be some variant of `self`, or `runtime: &mut R` for calls
+				// which need more than this pallet.
+				let takes_runtime = match method.sig.inputs.first() {
+					Some(syn::FnArg::Receiver(_)) => false,
+					Some(syn::FnArg::Typed(arg)) if is_runtime_arg(arg) => true,
 					_ => {
-						let msg = "Invalid call, first argument must be a variant of self";
+						let msg = "Invalid call, first argument must be a variant of self, or \
+							`runtime: &mut R`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
+				};
+
+				let fn_name = method.sig.ident.clone();
+
+				// The weight of the call can be given with a `#[weight(..)]` attribute.
+				let mut weight = None;
+				for attr in method.attrs.iter().filter(|attr| is_weight_attr(attr)) {
+					if weight.is_some() {
+						let msg = "Invalid call, expected at most one `#[weight(..)]` attribute";
+						return Err(syn::Error::new(attr.span(), msg))
+					}
+					weight = Some(attr.parse_args::<syn::Expr>()?);
 				}
 
-				// The second argument should be the `caller: T::AccountId` argument.
-				match method.sig.inputs.iter().skip(1).next() {
+				// Calls which only privileged origins can make have a `#[privileged]` attribute.
+				let privileged = method.attrs.iter().any(is_privileged_attr);
+
+				// The second argument should be the `caller: T::AccountId` argument, or the
+				// `context: DispatchContext<..>` argument for calls which need more than that.
+				// Privileged calls may not be signed at all, so they have no `caller` argument.
+				let takes_context = match method.sig.inputs.iter().skip(1).next() {
+					Some(syn::FnArg::Typed(arg)) if is_context_arg(arg) => true,
+					_ if privileged => false,
 					Some(syn::FnArg::Typed(arg)) => {
 						// Here we specifically check that this argument is as we expect for
 						// `caller: T::AccountId`.
 						check_caller_arg(arg)?;
+						false
 					},
 					_ => {
 						let msg = "Invalid call, second argument should be `caller: T::AccountId`";
 						return Err(syn::Error::new(method.sig.span(), msg))
 					},
-				}
-
-				let fn_name = method.sig.ident.clone();
+				};
+				let takes_caller = takes_context || !privileged;
 
-				// Parsing the rest of the args. Skipping 2 for `self` and `caller`.
-				for arg in method.sig.inputs.iter().skip(2) {
+				// Parsing the rest of the args. Skipping `self` or `runtime`, and `caller` or
+				// `context` if the call takes one.
+				for arg in method.sig.inputs.iter().skip(1 + takes_caller as usize) {
 					// All arguments should be typed.
 					let arg = if let syn::FnArg::Typed(arg) = arg {
 						arg
@@ -96,15 +165,62 @@ impl CallDef {
 				}
 
 				// Store all the function name and the arg data for the function.
-				methods.push(CallVariantDef { name: fn_name, args });
+				methods.push(CallVariantDef {
+					name: fn_name,
+					args,
+					weight,
+					privileged,
+					takes_context,
+					takes_runtime,
+				});
 			}
 		}
 
 		// Return all callable functions for this pallet.
-		Ok(Self { pallet_struct, methods })
+		Ok(Self { pallet_struct, methods, attr })
 	}
 }
 
+/// Check if `attr` is the `#[weight(..)]` attribute of a call.
+pub fn is_weight_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("weight")
+}
+
+/// Check if `attr` is the `#[privileged]` attribute of a call.
+pub fn is_privileged_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("privileged")
+}
+
+/// Check if `arg` is the context of the call: `context: DispatchContext<..>`, where the type can be
+/// given with any path, like `crate::support::DispatchContext<T::AccountId, T::BlockNumber>`.
+pub fn is_context_arg(arg: &syn::PatType) -> bool {
+	let is_context_name = match &*arg.pat {
+		syn::Pat::Ident(ident) => ident.ident == "context" || ident.ident == "_context",
+		_ => false,
+	};
+	let is_context_type = match &*arg.ty {
+		syn::Type::Path(tp) => tp.path.segments.last().is_some_and(|s| s.ident == "DispatchContext"),
+		_ => false,
+	};
+	is_context_name && is_context_type
+}
+
+/// Check if `arg` is the runtime of the call: `runtime: &mut R`.
+pub fn is_runtime_arg(arg: &syn::PatType) -> bool {
+	let is_runtime_name = match &*arg.pat {
+		syn::Pat::Ident(ident) => ident.ident == "runtime" || ident.ident == "_runtime",
+		_ => false,
+	};
+	let is_runtime_type = match &*arg.ty {
+		syn::Type::Reference(reference) => match &*reference.elem {
+			syn::Type::Path(tp) => reference.mutability.is_some() && tp.path.is_ident("R"),
+			_ => false,
+		},
+		_ => false,
+	};
+	is_runtime_name && is_runtime_type
+}
+
 /// Check caller arg is exactly: `caller: T::AccountId`.
 ///
 /// This is kept strict to keep the code simple.
@@ -0,0 +1,125 @@
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+/// See the `fn encode` docs at the `lib.rs` of this crate for a high level definition.
+pub fn encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let input = syn::parse_macro_input!(item as syn::DeriveInput);
+
+	match expand_encode(input) {
+		Ok(expanded) => expanded.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+/// Check if `tokens` mention any of the generic type parameters `params`.
+pub fn mentions_any(tokens: proc_macro2::TokenStream, params: &[syn::Ident]) -> bool {
+	tokens.into_iter().any(|token| match token {
+		proc_macro2::TokenTree::Ident(ident) => params.contains(&ident),
+		proc_macro2::TokenTree::Group(group) => mentions_any(group.stream(), params),
+		_ => false,
+	})
+}
+
+/// The code which encodes the fields bound to the names in `bindings`, in order.
+fn encode_fields(bindings: &[syn::Ident]) -> proc_macro2::TokenStream {
+	quote! { #( crate::support::Encode::encode_to(#bindings, encoded); )* }
+}
+
+/// The names the fields are bound to when destructuring them, and the pattern which does so.
+fn bind_fields(fields: &syn::Fields) -> (Vec<syn::Ident>, proc_macro2::TokenStream) {
+	match fields {
+		syn::Fields::Named(named) => {
+			let names = named.named.iter().filter_map(|f| f.ident.clone()).collect::<Vec<_>>();
+			let pattern = quote! { { #( #names ),* } };
+			(names, pattern)
+		},
+		syn::Fields::Unnamed(unnamed) => {
+			let names = (0..unnamed.unnamed.len())
+				.map(|i| format_ident!("field_{}", i))
+				.collect::<Vec<_>>();
+			let pattern = quote! { ( #( #names ),* ) };
+			(names, pattern)
+		},
+		syn::Fields::Unit => (vec![], quote! {}),
+	}
+}
+
+fn expand_encode(mut input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let name = &input.ident;
+
+	// Like `call`, we require that each of the field types which depends on a generic parameter
+	// can be encoded, rather than the parameters themselves, so `T::AccountId` works. Fields which do
+	// not depend on a parameter need no bound, which also allows recursive types like `RuntimeCall`.
+	let params = input
+		.generics
+		.type_params()
+		.map(|param| param.ident.clone())
+		.collect::<Vec<_>>();
+	let field_types = match &input.data {
+		syn::Data::Struct(data) => data.fields.iter().map(|f| f.ty.clone()).collect::<Vec<_>>(),
+		syn::Data::Enum(data) => data
+			.variants
+			.iter()
+			.flat_map(|v| v.fields.iter().map(|f| f.ty.clone()))
+			.collect(),
+		syn::Data::Union(_) => {
+			let msg = "Invalid encode, unions cannot be encoded";
+			return Err(syn::Error::new(input.span(), msg))
+		},
+	};
+	let bounds = field_types
+		.into_iter()
+		.filter(|ty| mentions_any(quote! { #ty }, &params))
+		.collect::<Vec<_>>();
+	let where_clause = input.generics.make_where_clause();
+	for ty in bounds {
+		where_clause.predicates.push(syn::parse_quote! { #ty: crate::support::Encode });
+	}
+
+	// A struct is encoded as its fields in order. An enum is encoded as the index of its variant,
+	// as a single byte, followed by the fields of that variant.
+	let body = match &input.data {
+		syn::Data::Struct(data) => {
+			let (bindings, pattern) = bind_fields(&data.fields);
+			let encode = encode_fields(&bindings);
+			quote! {
+				let #name #pattern = self;
+				#encode
+			}
+		},
+		syn::Data::Enum(data) => {
+			if data.variants.len() > 256 {
+				let msg = "Invalid encode, an enum can have at most 256 variants";
+				return Err(syn::Error::new(input.span(), msg))
+			}
+			let arms = data.variants.iter().enumerate().map(|(index, variant)| {
+				let variant_name = &variant.ident;
+				let index = index as u8;
+				let (bindings, pattern) = bind_fields(&variant.fields);
+				let encode = encode_fields(&bindings);
+				quote! {
+					#name::#variant_name #pattern => {
+						encoded.push(#index);
+						#encode
+					},
+				}
+			});
+			quote! {
+				match self {
+					#( #arms )*
+				}
+			}
+		},
+		syn::Data::Union(_) => unreachable!("unions are rejected above"),
+	};
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics crate::support::Encode for #name #ty_generics #where_clause {
+			#[allow(unused_variables)]
+			fn encode_to(&self, encoded: &mut Vec<u8>) {
+				#body
+			}
+		}
+	})
+}
@@ -1,6 +1,32 @@
 mod call;
+mod encode;
 mod runtime;
+mod storage;
 
+/// Expand the callable functions of a pallet, which are placed in an `impl` block of the `Pallet`.
+///
+/// Each function becomes a variant of a `Call` enum, with the same name and arguments. Its first
+/// argument is `&mut self`, or `runtime: &mut R` for an associated function which needs access to
+/// other pallets. The second is `caller: T::AccountId`, or `context: DispatchContext<..>` for a
+/// call which needs more than that. Functions can have these attributes:
+/// - `#[weight(..)]` - the weight of the call, which may use the arguments of the call by
+///   reference, instead of `support::DEFAULT_WEIGHT`.
+/// - `#[privileged]` - only origins which the runtime allows with `support::EnsurePrivileged` can
+///   make the call. Unless it takes the whole `context`, the call takes no `caller` argument.
+///
+/// This generates the `Call` enum, with the same traits as `Clone`, `Debug`, `PartialEq`, `Eq`,
+/// `Hash` and `support::Encode` which require each of the argument types to implement them,
+/// `support::GetCallName`, and `serde` serialization when the `serde` feature of the runtime crate
+/// is enabled. It also implements `support::GetWeight` for the `Call` enum, `support::DispatchCall`
+/// for any runtime `R` which implements `AsMut` for the pallet, and `support::PalletHooks`, which
+/// runs the `support::Hooks` of the pallet. Unless some calls take the whole runtime, the pallet
+/// itself implements `support::Dispatch` too.
+///
+/// The attribute can take extra where-predicates for the runtime, like
+/// `#[macros::call(R: AsMut<balances::Pallet<T>>)]`, which are required when dispatching the calls.
+/// Predicates which do not bound `R` itself, like `T::RuntimeCall: GetWeight`, are also required
+/// for the weight of the calls. A pallet whose hooks need access to other pallets also passes
+/// `custom_hooks`, and implements `support::PalletHooks` by hand.
 #[proc_macro_attribute]
 pub fn call(
 	attr: proc_macro::TokenStream,
@@ -9,22 +35,121 @@ pub fn call(
 	call::call(attr, item)
 }
 
+/// Derive `support::Encode` for a struct or an enum, which is the canonical encoding of its value
+/// that the state root and block hashes are computed from.
+///
+/// A struct is encoded as each of its fields in order, and an enum as the index of its variant,
+/// as a single byte, followed by the fields of that variant. Each of the field types which
+/// depends on a generic parameter is required to implement `support::Encode`.
+#[proc_macro_derive(Encode)]
+pub fn encode(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	encode::encode(item)
+}
+
+/// Derive `serde` serialization for the storage of a pallet, when the `serde` feature of the
+/// runtime crate is enabled, so the whole state of a runtime can be saved and loaded, and implement
+/// `support::Encode` for it, which the runtime uses to compute its state root.
+///
+/// This is placed on the `Pallet` struct, whose fields are its storage. Fields marked with
+/// `#[transient]`, like the events which have not yet been collected by the runtime, are not part
+/// of the state, so they are skipped, and take their default value when the storage is loaded.
+/// Each of the other field types is required to implement `serde::Serialize` and
+/// `serde::Deserialize`.
+///
+/// The encoding is every field which is part of the state, in order, except for the fields marked
+/// with `#[not_in_state_root]`, which are still saved and loaded, but do not change the state root.
+/// Each of the encoded field types which depends on a generic parameter is required to implement
+/// `support::Encode`.
+#[proc_macro_attribute]
+pub fn storage(
+	attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	storage::storage(attr, item)
+}
+
 /// Expand the `Runtime` definition.
 ///
+/// The first field of the `Runtime` must be the `system` pallet, and every other field must be a
+/// pallet with the same name as its module, like `balances: balances::Pallet<Self>`, since the
+/// name of the field is used to find the `Call` and `Event` types of the pallet. When the runtime
+/// cannot dispatch the calls of a pallet, for example because they need another pallet which is
+/// not in the runtime, the compiler error points at that pallet.
+///
 /// This generates function implementations on `Runtime`:
 /// - `fn new()` - which generates a new instance of the runtime, by instantiating all the pallets
 ///   included in the runtime.
-/// - `fn execute_block()` - which handles basic logic for executing a block of extrinsics. It does
-///   basic actions like incrementing the block number and checking the block to be executed has a
-///   valid block number.
+/// - `fn execute_block()` - which handles basic logic for executing a borrowed block of extrinsics. It does
+///   basic actions like incrementing the block number, checking the block to be executed has a
+///   valid block number and parent hash, verifying the signature and checking the nonce of each extrinsic,
+///   rejecting the extrinsics of frozen accounts, checking the state root, and recording the block hash. It returns the events emitted
+///   during the block, so callers can see which extrinsics succeeded and what they did. The events
+///   are also left in the system pallet, like with `author_block`, which returns the result of
+///   each extrinsic instead. An extrinsic which fails with a
+///   fatal `support::DispatchError`, like an invalid signature or an extrinsic which appears twice
+///   in the block, aborts the whole block instead. A block which is rejected changes no state,
+///   since the state from before the block is restored with `support::CloneState`, which this
+///   implements for the runtime, so every pallet must implement `Clone`.
+/// - `fn validate_block()` - which checks the header, signatures, nonces and weight of a block, and
+///   that it has no duplicate extrinsics, without executing it, so a block can be pre-checked
+///   before `execute_block`.
+/// - `fn validate_header()` - which checks the block number and parent hash of a header, returning
+///   a `support::HeaderError` for the first field which is invalid. The state root is checked by
+///   `execute_block`, since it is only known after executing the block.
+/// - `fn author_block()` - which builds and executes the next block out of some extrinsics,
+///   filling in the header of the block, and leaving out the extrinsics with fatal errors.
+/// - `fn state_root()` - which computes the state root of all pallets. Every pallet is expected to
+///   implement `support::Encode` over its storage, which `macros::storage` generates.
+/// - `fn import_blocks_from_file()` - which reads a JSON array of blocks from a file, and executes
+///   them in order with `execute_block`, returning a `support::ImportError` with the index of the
+///   first block which fails. This is only available with the `serde` feature.
+/// - `fn collect_events()` - which moves the events emitted by each pallet into the system pallet.
+///   Every pallet is expected to have a `take_events()` function. `execute_block` sets the phase of
+///   the system pallet before the hooks and each extrinsic, so every event records which extrinsic
+///   caused it.
+///
+/// A field of the `Runtime` can be marked with `#[history]`, instead of being a pallet. It must be
+/// a `support::BlockHistory`. Then `execute_block` and `author_block` record the state of every
+/// pallet before each block, and this also generates:
+/// - `fn revert_last_block()` - which restores the state from before the most recent block.
+///
+/// This also implements `support::Hooks` for the runtime, which calls `on_initialize` and
+/// `on_finalize` on every pallet. `execute_block` runs these before and after the extrinsics of
+/// each block, using `support::PalletHooks` for each pallet. The `call` macro implements this for
+/// every pallet which implements `support::Hooks`, even if it does nothing, and pallets whose hooks
+/// need access to other pallets implement it by hand.
 ///
 /// This also generates code needed for dispatching calls to the pallets:
 /// - Note: For simplicity, we assume that the system pallet is not callable.
 /// - `enum RuntimeCall` - an "outer"-enum representing the accumulation of all possible calls to
-///   all pallets. The system pallet is not included.
-/// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet. Basic
-///   logic like incrementing the nonce of the user is included in the generated code. The system
-///   pallet is not included.
+///   all pallets. The system pallet is not included. It implements `support::Encode`, so it can be
+///   signed, and `serde` serialization when the `serde` feature of the runtime crate is enabled. A call of
+///   a pallet which the runtime does not have decodes from JSON as `RuntimeCall::Unknown`, and
+///   dispatching it fails with "unknown call", so a block from a newer runtime can still be read.
+/// - implements the trait `support::Dispatch` to dispatch calls to the appropriate pallet, using
+///   `support::DispatchCall` for each pallet's `Call`. The system pallet is not included.
+///   `apply_block` dispatches each extrinsic with a `support::DispatchContext` holding the
+///   `support::Origin::Signed` origin of its caller and the number of the block, which calls can
+///   take instead of only `caller: T::AccountId`.
+/// - implements the trait `support::GetWeight` for `RuntimeCall`, using the weight of each
+///   pallet's `Call`. `apply_block` rejects any extrinsic which would take the total weight of the
+///   block over the `MaxBlockWeight` of the system pallet, with "block weight exceeded". A block
+///   with more extrinsics than the `MaxExtrinsicsPerBlock` of the system pallet is rejected with
+///   "too many extrinsics", however little they weigh, and `author_block` leaves the extra ones
+///   out instead.
+///   Before dispatching an extrinsic, `apply_block` charges its fee with `support::ChargeFee`,
+///   which the runtime is expected to implement. After each extrinsic which is part of the block,
+///   `apply_block` calls `support::OnExtrinsic`, which the runtime is also expected to implement.
+/// - implements the trait `support::GetCallName` for `RuntimeCall`, using the name of each
+///   pallet's call, and `RuntimeCall::pallet_name()`. `apply_block` logs the pallet and call of
+///   each extrinsic with the `log` crate, at the `info` level when it succeeds, and at the `warn`
+///   level with the error when it fails.
+/// - `enum RuntimeEvent` - an "outer"-enum representing the accumulation of all possible events
+///   of all pallets, with a `From` implementation for each pallet's `Event`. Every pallet is
+///   expected to have an `Event<T>` enum, even if it is never emitted. The system pallet is not
+///   included.
+/// - implements `AsMut` for each pallet, including system, so pallets can get access to their own
+///   storage, or the storage of other pallets, from the runtime.
 #[proc_macro_attribute]
 pub fn runtime(
 	attr: proc_macro::TokenStream,
@@ -1,15 +1,34 @@
 use super::parse::RuntimeDef;
-use quote::quote;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 
 /// See the `fn runtime` docs at the `lib.rs` of this crate for a high level definition.
 pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
-	let RuntimeDef { runtime_struct, pallets } = def;
+	let RuntimeDef { runtime_struct, pallets, history } = def;
 
 	// This is a vector of all the pallet names, not including system.
 	let pallet_names = pallets.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
 	// This is a vector of all the pallet types, not including system.
 	let pallet_types = pallets.iter().map(|(_, type_)| type_.clone()).collect::<Vec<_>>();
 
+	// Every block is executed on top of a copy of the state before it, which `execute_block` restores
+	// when the block is rejected. When the runtime has a `#[history]` field, the copy is also recorded
+	// after a block is executed, so the block can be reverted. The history itself is never copied.
+	// These are the pieces of code which handle the history.
+	let (history_init, take_snapshot, record_snapshot, restore_history) = match &history {
+		Some(history) => (
+			quote! { #history: Default::default(), },
+			quote! { let snapshot = crate::support::CloneState::clone_state(self); },
+			quote! { self.#history.push(snapshot); },
+			quote! {
+				let history = core::mem::take(&mut self.#history);
+				*self = snapshot;
+				self.#history = history;
+			},
+		),
+		None => (quote! {}, quote! {}, quote! {}, quote! { *self = snapshot; }),
+	};
+
 	// This quote block implements functions on the `Runtime` struct.
 	let runtime_impl = quote! {
 		impl #runtime_struct {
@@ -19,31 +38,373 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 					// Since system is not included in the list of pallets, we manually add it here.
 					system: <system::Pallet::<Self>>::new(),
 					#(
-						#pallet_names: <#pallet_types>::new()
-					),*
+						#pallet_names: <#pallet_types>::new(),
+					)*
+					#history_init
+				}
+			}
+
+			// Execute a block of extrinsics. Increments the block number, checks the block is built on
+			// top of the last executed block, checks the state root after executing the block matches
+			// the one in the header, and records the hash of the block. The events emitted by the
+			// pallets during the block are collected in the system pallet.
+			//
+			// Returns an error if the block itself is invalid, or if any of its extrinsics fails with a
+			// fatal `DispatchError`, in which case the rest of the block is not executed, and the state
+			// from before the block is restored, so a rejected block changes nothing. Otherwise,
+			// returns every event emitted during the block, in the order they were emitted, along with
+			// the phase of the block which emitted them. This is how a client learns the outcome of
+			// each extrinsic. The events are also kept in the system pallet, so they can still be queried
+			// with `events_at` later.
+			//
+			// The block is only borrowed, so the same block can be checked with `validate_block` first,
+			// or executed again after reverting it.
+			fn execute_block(
+				&mut self,
+				block: &types::Block,
+			) -> Result<Vec<system::EventRecord<RuntimeEvent>>, &'static str> {
+				let snapshot = crate::support::CloneState::clone_state(self);
+				// The state root can only be checked once the block is executed, unlike the rest of the
+				// header, which `apply_block` checks with `validate_header` before executing it.
+				let res = self.apply_block(&block.header, &block.extrinsics, true).and_then(|_| {
+					if self.state_root() != block.header.state_root {
+						return Err(crate::support::HeaderError::StateRootMismatch.into())
+					}
+					Ok(())
+				});
+				if let Err(e) = res {
+					crate::support::CloneState::restore_state(self, snapshot);
+					return Err(e)
 				}
+				self.system.set_block_hash(block.header.block_number, crate::support::hash(&block.header));
+				#record_snapshot
+				Ok(self.system.events().to_vec())
+			}
+
+			// Check that `block` could be imported next, without executing it or changing any state, so
+			// a client can pre-check a block before calling `execute_block`. The header must follow the
+			// last executed block, and every extrinsic must be signed by an account which is not frozen,
+			// use the next nonce of its caller, and fit in the block weight, and no extrinsic may appear
+			// twice. The block may not have more extrinsics than the `MaxExtrinsicsPerBlock` of the
+			// system pallet. Like `execute_block`, this rejects the whole block if any extrinsic is
+			// invalid.
+			//
+			// The nonces used by earlier extrinsics in the block are tracked on top of the nonces in
+			// the system pallet. Fees and the outcome of each call are not checked, since they depend
+			// on executing the extrinsics before them.
+			fn validate_block(&self, block: &types::Block) -> Result<(), String> {
+				self.validate_header(&block.header).map_err(|e| e.to_string())?;
+				let max_extrinsics = <<#runtime_struct as system::Config>::MaxExtrinsicsPerBlock as crate::support::Get<u32>>::get();
+				if block.extrinsics.len() > max_extrinsics as usize {
+					return Err(crate::support::DispatchError::Full.to_string())
+				}
+				let mut nonces = std::collections::BTreeMap::new();
+				let mut seen = std::collections::HashSet::new();
+				let mut block_weight: crate::support::Weight = 0;
+				let max_block_weight = <<#runtime_struct as system::Config>::MaxBlockWeight as crate::support::Get<_>>::get();
+				for (i, extrinsic) in block.extrinsics.iter().enumerate() {
+					let caller = &extrinsic.caller;
+					let expected = nonces.get(caller).copied().unwrap_or_else(|| self.system.nonce(caller));
+					let weight = crate::support::GetWeight::weight(&extrinsic.call);
+					let unique = seen.insert(extrinsic);
+					let res = if unique { Ok(()) } else { Err("duplicate extrinsic") }
+						.and_then(|_| if extrinsic.verify_signed() { Ok(()) } else { Err("Invalid signature.") })
+						.and_then(|_| if self.system.is_frozen(caller) { Err("account frozen") } else { Ok(()) })
+						.and_then(|_| if extrinsic.nonce == expected { Ok(()) } else { Err("Invalid nonce.") })
+						.and_then(|_| match block_weight.checked_add(weight) {
+							Some(total) if total <= max_block_weight => Ok(total),
+							_ => Err("block weight exceeded"),
+						});
+					block_weight = res.map_err(|e| format!("extrinsic {}: {}", i, e))?;
+					nonces.insert(caller, expected + <<#runtime_struct as system::Config>::Nonce as num::One>::one());
+				}
+				Ok(())
 			}
 
-			// Execute a block of extrinsics. Increments the block number.
-			fn execute_block(&mut self, block: types::Block) -> crate::support::DispatchResult {
-				self.system.inc_block_number();
-				if block.header.block_number != self.system.block_number() {
-					return Err(&"block number does not match what is expected")
+			// Check that `header` can be the header of the next block, without executing it: its block
+			// number must be the one after the last executed block, and its parent hash must be the hash
+			// of that block. Returns the first field which is invalid. The state root is not checked,
+			// since it is only known after executing the block, which `execute_block` does.
+			fn validate_header(&self, header: &types::Header) -> Result<(), crate::support::HeaderError> {
+				let block_number = self
+					.system
+					.block_number()
+					.checked_add(1)
+					.ok_or(crate::support::HeaderError::BlockNumberOverflow)?;
+				if header.block_number != block_number {
+					return Err(crate::support::HeaderError::BlockNumberMismatch)
 				}
-				for (i, support::Extrinsic { caller, call }) in block.extrinsics.into_iter().enumerate() {
-					self.system.inc_nonce(&caller);
-					let _res = self.dispatch(caller, call).map_err(|e| {
-						eprintln!(
-							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tError: {}",
-							block.header.block_number, i, e
-						)
-					});
+				if header.parent_hash != self.system.parent_hash() {
+					return Err(crate::support::HeaderError::ParentHashMismatch)
 				}
 				Ok(())
 			}
+
+			// Build the next block out of `extrinsics` and execute it, like a block author would. The
+			// header of the returned block contains the resulting state root, so the block can be
+			// executed by any other runtime with the same state. The outcome of every one of the
+			// `extrinsics` is returned, but those which fail with a fatal `DispatchError` are left out
+			// of the block, so it is always valid. Panics if the block number overflows, since there
+			// is no next block to author.
+			fn author_block(
+				&mut self,
+				extrinsics: Vec<types::Extrinsic>,
+			) -> (types::Block, Vec<crate::support::DispatchResult>) {
+				let mut header = types::Header {
+					block_number: self.system.block_number().checked_add(1).expect("block number overflow"),
+					parent_hash: self.system.parent_hash(),
+					state_root: [0; 32],
+				};
+				#take_snapshot
+				let results = self
+					.apply_block(&header, &extrinsics, false)
+					.expect("the block number is always the next one");
+				let extrinsics = extrinsics
+					.into_iter()
+					.zip(&results)
+					.filter(|(_, res)| !res.is_err_and(|e| e.is_fatal()))
+					.map(|(extrinsic, _)| extrinsic)
+					.collect();
+				header.state_root = self.state_root();
+				self.system.set_block_hash(header.block_number, crate::support::hash(&header));
+				#record_snapshot
+				(types::Block { header, extrinsics }, results)
+			}
+
+			// Compute the state root of all of the pallets in the runtime.
+			fn state_root(&self) -> crate::support::StateRoot {
+				// The state is the encoding of every pallet, one after the other, in the order of the
+				// pallets in the runtime.
+				struct State<'a>(&'a #runtime_struct);
+				impl crate::support::Encode for State<'_> {
+					fn encode_to(&self, dest: &mut Vec<u8>) {
+						crate::support::Encode::encode_to(&self.0.system, dest);
+						#( crate::support::Encode::encode_to(&self.0.#pallet_names, dest); )*
+					}
+				}
+				crate::support::state_root(&State(self))
+			}
+
+			// Execute the extrinsics of a block with the given `header`, including the hooks of every
+			// pallet, after checking the header with `validate_header`, so an invalid header changes no
+			// state. This does not check the state root of the block, or record its hash.
+			//
+			// When `abort_on_fatal` is set, an extrinsic which fails with a fatal `DispatchError` aborts
+			// the block with that error, leaving the state partially changed, which `execute_block`
+			// restores. Otherwise it is skipped, and does not count towards the index of the extrinsics
+			// after it, since a block author leaves it out of the block.
+			//
+			// The same flag decides what happens to a block with more extrinsics than the
+			// `MaxExtrinsicsPerBlock` of the system pallet. When it is set, the whole block is rejected
+			// with "too many extrinsics" before any state changes. Otherwise, the block is truncated:
+			// once it is full, every remaining extrinsic fails with `DispatchError::Full`, which is
+			// fatal, so a block author leaves them out.
+			fn apply_block(
+				&mut self,
+				header: &types::Header,
+				extrinsics: &[types::Extrinsic],
+				abort_on_fatal: bool,
+			) -> Result<Vec<crate::support::DispatchResult>, &'static str> {
+				self.validate_header(header)?;
+				let max_extrinsics = <<#runtime_struct as system::Config>::MaxExtrinsicsPerBlock as crate::support::Get<u32>>::get();
+				if abort_on_fatal && extrinsics.len() > max_extrinsics as usize {
+					return Err(crate::support::DispatchError::Full.message())
+				}
+				self.system.reset_events();
+				self.system.inc_block_number()?;
+				self.system.set_phase(system::Phase::Initialization);
+				crate::support::Hooks::on_initialize(&mut self.system, header.block_number);
+				crate::support::Hooks::on_initialize(self, header.block_number);
+				self.collect_events();
+				let mut results = Vec::with_capacity(extrinsics.len());
+				// The total weight of the extrinsics executed so far in this block, and its limit.
+				let mut block_weight: crate::support::Weight = 0;
+				let max_block_weight = <<#runtime_struct as system::Config>::MaxBlockWeight as crate::support::Get<_>>::get();
+				// The index of the next extrinsic in the block.
+				let mut i: u32 = 0;
+				// Every extrinsic seen so far in this block.
+				let mut seen = std::collections::HashSet::new();
+				for extrinsic in extrinsics {
+					// The events of this extrinsic are tagged with its index in the block.
+					self.system.set_phase(system::Phase::ApplyExtrinsic(i));
+					// The signature is verified before anything else, so a forged extrinsic cannot even
+					// use up the nonce of the account it pretends to be.
+					let verified = extrinsic.verify_signed();
+					// An extrinsic which appears twice in a block is always invalid, even though its
+					// nonce would also reject it, so a block cannot replay an extrinsic.
+					let unique = seen.insert(extrinsic);
+					let crate::support::Extrinsic { caller, call, nonce, .. } = extrinsic.clone();
+					let weight = crate::support::GetWeight::weight(&call);
+					let (pallet_name, call_name) = (call.pallet_name(), crate::support::GetCallName::call_name(&call));
+					let logged_caller = caller.clone();
+					// The extrinsics of a frozen account are rejected before their nonce is checked, so
+					// they never pay a fee or use up the nonce. The nonce is checked next, so a replayed
+					// extrinsic never pays a fee or executes. An extrinsic which does not fit in the block
+					// weight, or whose fee cannot be paid, is not executed at all, so it does not use up
+					// the nonce of the caller. All of these are fatal, so every extrinsic in a block has
+					// paid its fee and used up its nonce. Once the fee is paid, the call is dispatched,
+					// even if it then fails.
+					let res = if i < max_extrinsics { Ok(()) } else { Err(crate::support::DispatchError::Full) }
+						.and_then(|_| if unique { Ok(()) } else { Err(crate::support::DispatchError::Duplicate) })
+						.and_then(|_| if verified { Ok(()) } else { Err(crate::support::DispatchError::BadOrigin) })
+						.and_then(|_| if self.system.is_frozen(&caller) { Err(crate::support::DispatchError::Frozen) } else { Ok(()) })
+						.and_then(|_| self.system.check_nonce(&caller, nonce))
+						.and_then(|_| match block_weight.checked_add(weight) {
+							Some(total) if total <= max_block_weight => Ok(total),
+							_ => Err(crate::support::DispatchError::Exhausted),
+						})
+						.and_then(|total| {
+							crate::support::ChargeFee::charge_fee(self, &caller, weight)
+								.map_err(|_| crate::support::DispatchError::CannotPayFee)?;
+							block_weight = total;
+							self.system.inc_nonce(&caller);
+							let origin = crate::support::Origin::Signed(caller);
+							let context = crate::support::DispatchContext { origin, block_number: header.block_number };
+							crate::support::Dispatch::dispatch(self, context, call)
+						});
+					// A failed extrinsic is only logged, and unless its error is fatal, the rest of the
+					// block is still executed.
+					match &res {
+						Ok(()) => log::info!(
+							"Extrinsic dispatched\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tCaller: {:?}\n\tCall: {}::{}",
+							header.block_number, i, logged_caller, pallet_name, call_name
+						),
+						Err(e) => log::warn!(
+							"Extrinsic Error\n\tBlock Number: {}\n\tExtrinsic Number: {}\n\tCaller: {:?}\n\tCall: {}::{}\n\tError: {}",
+							header.block_number, i, logged_caller, pallet_name, call_name, e
+						),
+					}
+					match res {
+						Err(e) if e.is_fatal() && abort_on_fatal => return Err(e.message()),
+						Err(e) if e.is_fatal() => {},
+						// The extrinsic is part of the block, and was made by its caller, so the runtime is
+						// told about it.
+						_ => {
+							crate::support::OnExtrinsic::on_extrinsic(self, &logged_caller, header.block_number, i);
+							i += 1
+						},
+					}
+					results.push(res);
+					self.collect_events();
+				}
+				self.system.set_phase(system::Phase::Finalization);
+				crate::support::Hooks::on_finalize(self, header.block_number);
+				self.collect_events();
+				Ok(results)
+			}
+
+			// Read a JSON array of blocks from the file at `path`, and execute them in order. Stops at
+			// the first block which cannot be executed, for example because its block number does not
+			// match what is expected, and returns its index in the file.
+			#[cfg(feature = "serde")]
+			fn import_blocks_from_file(
+				&mut self,
+				path: &str,
+			) -> Result<(), crate::support::ImportError> {
+				let json = std::fs::read_to_string(path).map_err(|e| {
+					log::error!("Import Error\n\tPath: {}\n\tError: {}", path, e);
+					crate::support::ImportError::Read
+				})?;
+				let blocks: Vec<types::Block> = serde_json::from_str(&json).map_err(|e| {
+					log::error!("Import Error\n\tPath: {}\n\tError: {}", path, e);
+					crate::support::ImportError::Parse
+				})?;
+				for (i, block) in blocks.iter().enumerate() {
+					self.execute_block(block).map_err(|e| {
+						log::error!("Import Error\n\tPath: {}\n\tBlock Index: {}\n\tError: {}", path, i, e);
+						crate::support::ImportError::Block { index: i, error: e }
+					})?;
+				}
+				Ok(())
+			}
+
+			// Move all of the events emitted by each pallet into the system pallet.
+			fn collect_events(&mut self) {
+				#(
+					for event in self.#pallet_names.take_events() {
+						self.system.deposit_event(event.into());
+					}
+				)*
+			}
 		}
 	};
 
+	// This quote block implements `support::CloneState` for the `Runtime`, which `execute_block` uses
+	// to restore the state when it rejects a block.
+	let clone_state_impl = quote! {
+		impl crate::support::CloneState for #runtime_struct {
+			// Copy the state of every pallet in the runtime, without the history, if it has one.
+			fn clone_state(&self) -> Self {
+				Self {
+					system: self.system.clone(),
+					#(
+						#pallet_names: self.#pallet_names.clone(),
+					)*
+					#history_init
+				}
+			}
+
+			// Restore the state of every pallet from `snapshot`, keeping the history of the runtime, if
+			// it has one.
+			fn restore_state(&mut self, snapshot: Self) {
+				#restore_history
+			}
+		}
+	};
+
+	// This quote block implements reverting blocks, when the runtime has a `#[history]` field.
+	let history_impl = match &history {
+		Some(history) => quote! {
+			impl #runtime_struct {
+				// Revert the most recent block executed by this runtime, by restoring the state of every
+				// pallet from before that block, including the block number. Only the blocks recorded in
+				// the history can be reverted, and blocks which failed to execute are not recorded.
+				fn revert_last_block(&mut self) -> Result<(), &'static str> {
+					let snapshot = self.#history.pop().ok_or("no block to revert")?;
+					crate::support::CloneState::restore_state(self, snapshot);
+					Ok(())
+				}
+			}
+		},
+		None => quote! {},
+	};
+
+	// This quote block implements the `Hooks` trait for the `Runtime`, by calling the hooks of each
+	// pallet in the order they are included in the `Runtime` struct. See `support::PalletHooks`.
+	let hooks_impl = quote! {
+		impl crate::support::Hooks<<#runtime_struct as system::Config>::BlockNumber> for #runtime_struct {
+			fn on_initialize(&mut self, block_number: <#runtime_struct as system::Config>::BlockNumber) {
+				#(
+					<Self as crate::support::PalletHooks<#pallet_types, _>>::on_initialize(self, block_number);
+				)*
+			}
+
+			fn on_finalize(&mut self, block_number: <#runtime_struct as system::Config>::BlockNumber) {
+				#(
+					<Self as crate::support::PalletHooks<#pallet_types, _>>::on_finalize(self, block_number);
+				)*
+			}
+		}
+	};
+
+	// The variants of `RuntimeCall`, and the arms which dispatch them, for each pallet. These are
+	// spanned to the pallet in the `Runtime` struct, so if a pallet has no `Call`, or the runtime
+	// cannot dispatch it, the compiler points at that pallet.
+	let call_variants = pallets.iter().map(|(name, type_)| {
+		quote_spanned! { type_.span() => #name(#name::Call<#runtime_struct>) }
+	});
+	let dispatch_arms = pallets.iter().map(|(name, type_)| {
+		quote_spanned! { type_.span() =>
+			RuntimeCall::#name(call) => {
+				<Self as crate::support::DispatchCall<
+					<#runtime_struct as system::Config>::AccountId,
+					<#runtime_struct as system::Config>::BlockNumber,
+					#name::Call<#runtime_struct>,
+				>>::dispatch_call(self, context, call)?;
+			}
+		}
+	});
+
 	// This quote block implements the `RuntimeCall` enum and implements the `Dispatch` trait.
 	let dispatch_impl = quote! {
 		// These are all the calls which are exposed to the world.
@@ -51,41 +412,230 @@ pub fn expand_runtime(def: RuntimeDef) -> proc_macro2::TokenStream {
 		//
 		// The parsed function names will be `snake_case`, and that will show up in the enum.
 		#[allow(non_camel_case_types)]
+		#[derive(Clone, Debug, PartialEq, Eq, Hash, macros::Encode)]
 		pub enum RuntimeCall {
-			#( #pallet_names(#pallet_names::Call<#runtime_struct>) ),*
+			#( #call_variants, )*
+			// A call of a pallet which this runtime does not have, like a pallet from a newer version
+			// of the runtime, which was decoded from a block. It keeps the name of the pallet and the
+			// JSON of the call, so it can be encoded again, but dispatching it always fails.
+			Unknown { pallet: String, call: String },
+		}
+
+		// The weight of a `RuntimeCall` is the weight of the pallet call it contains.
+		impl crate::support::GetWeight for RuntimeCall {
+			fn weight(&self) -> crate::support::Weight {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => crate::support::GetWeight::weight(call),
+					)*
+					RuntimeCall::Unknown { .. } => crate::support::DEFAULT_WEIGHT,
+				}
+			}
+		}
+
+		// The name of a `RuntimeCall` is the name of the pallet call it contains.
+		impl crate::support::GetCallName for RuntimeCall {
+			fn call_name(&self) -> &'static str {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(call) => crate::support::GetCallName::call_name(call),
+					)*
+					RuntimeCall::Unknown { .. } => "unknown",
+				}
+			}
+		}
+
+		impl RuntimeCall {
+			// The name of the pallet this call is dispatched to, like `"balances"`.
+			pub fn pallet_name(&self) -> &'static str {
+				match self {
+					#(
+						RuntimeCall::#pallet_names(_) => stringify!(#pallet_names),
+					)*
+					RuntimeCall::Unknown { .. } => "unknown",
+				}
+			}
 		}
 
 		impl crate::support::Dispatch for #runtime_struct {
 			type Caller = <Runtime as system::Config>::AccountId;
+			type BlockNumber = <Runtime as system::Config>::BlockNumber;
 			type Call = RuntimeCall;
 			// Dispatch a call on behalf of a caller. Increments the caller's nonce.
 			//
 			// Dispatch allows us to identify which underlying pallet call we want to execute.
 			// Note that we extract the `caller` from the extrinsic, and use that information
-			// to determine who we are executing the call on behalf of.
+			// to determine who we are executing the call on behalf of. The `context` also tells the
+			// pallet which block the call is executed in.
 			fn dispatch(
 				&mut self,
-				caller: Self::Caller,
+				context: crate::support::DispatchContext<Self::Caller, Self::BlockNumber>,
 				runtime_call: Self::Call,
 			) -> crate::support::DispatchResult {
 				// This match statement will allow us to correctly route `RuntimeCall`s
 				// to the appropriate pallet level call.
 				match runtime_call {
+					#( #dispatch_arms, )*
+					RuntimeCall::Unknown { .. } => return Err("unknown call".into()),
+				}
+				Ok(())
+			}
+		}
+	};
+
+	// This quote block encodes and decodes `RuntimeCall` with serde. Known calls are encoded just
+	// like a derived enum would be, as the name or index of their pallet and the call itself. We do
+	// not derive it, since a block can contain the call of a pallet we do not have, which is decoded
+	// as `RuntimeCall::Unknown` instead of failing to decode the whole block. Only self-describing
+	// formats like JSON can skip over the unknown call, so compact formats still fail to decode it.
+	let pallet_indices = (0..pallet_names.len() as u32).collect::<Vec<_>>();
+	let serde_impl = quote! {
+		#[cfg(feature = "serde")]
+		impl serde::Serialize for RuntimeCall {
+			fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+				match self {
 					#(
-						RuntimeCall::#pallet_names(call) => {
-							self.#pallet_names.dispatch(caller, call)?;
+						RuntimeCall::#pallet_names(call) => serializer.serialize_newtype_variant(
+							"RuntimeCall",
+							#pallet_indices,
+							stringify!(#pallet_names),
+							call,
+						),
+					)*
+					// The JSON of a newtype variant is a map with a single entry, from its name to
+					// its value, which we write by hand, since the name is not known in advance.
+					RuntimeCall::Unknown { pallet, call } => {
+						use serde::ser::{Error, SerializeMap};
+						let call: serde_json::Value = serde_json::from_str(call).map_err(S::Error::custom)?;
+						let mut map = serializer.serialize_map(Some(1))?;
+						map.serialize_entry(pallet, &call)?;
+						map.end()
+					}
+				}
+			}
+		}
+
+		#[cfg(feature = "serde")]
+		impl<'de> serde::Deserialize<'de> for RuntimeCall {
+			fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				const PALLETS: &[&str] = &[#( stringify!(#pallet_names) ),*];
+
+				// The pallet of a call, which is encoded as its name or its index.
+				enum Pallet {
+					Known(u32),
+					Unknown(String),
+				}
+
+				impl<'de> serde::Deserialize<'de> for Pallet {
+					fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+						struct PalletVisitor;
+						impl serde::de::Visitor<'_> for PalletVisitor {
+							type Value = Pallet;
+
+							fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+								f.write_str("the name or index of a pallet")
+							}
+
+							// An unknown index cannot be skipped, so it is an error.
+							fn visit_u64<E: serde::de::Error>(self, index: u64) -> Result<Pallet, E> {
+								match u32::try_from(index) {
+									Ok(index) if (index as usize) < PALLETS.len() => Ok(Pallet::Known(index)),
+									_ => Err(E::invalid_value(serde::de::Unexpected::Unsigned(index), &self)),
+								}
+							}
+
+							fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Pallet, E> {
+								Ok(match PALLETS.iter().position(|pallet| *pallet == name) {
+									Some(index) => Pallet::Known(index as u32),
+									None => Pallet::Unknown(name.to_string()),
+								})
+							}
 						}
-					),*
+						deserializer.deserialize_identifier(PalletVisitor)
+					}
 				}
-				Ok(())
+
+				struct CallVisitor;
+				impl<'de> serde::de::Visitor<'de> for CallVisitor {
+					type Value = RuntimeCall;
+
+					fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+						f.write_str("a runtime call")
+					}
+
+					fn visit_enum<A: serde::de::EnumAccess<'de>>(self, data: A) -> Result<RuntimeCall, A::Error> {
+						use serde::de::{Error, VariantAccess};
+						match data.variant()? {
+							#(
+								(Pallet::Known(#pallet_indices), call) => {
+									call.newtype_variant().map(RuntimeCall::#pallet_names)
+								},
+							)*
+							(Pallet::Known(index), _) => Err(A::Error::custom(format!("no pallet with index {}", index))),
+							(Pallet::Unknown(pallet), call) => {
+								let call: serde_json::Value = call.newtype_variant()?;
+								Ok(RuntimeCall::Unknown { pallet, call: call.to_string() })
+							},
+						}
+					}
+				}
+				deserializer.deserialize_enum("RuntimeCall", PALLETS, CallVisitor)
 			}
 		}
 	};
 
+	// This quote block implements the `RuntimeEvent` enum, which the events of every pallet can be
+	// converted into.
+	let event_impl = quote! {
+		// These are all the events which can be emitted by the pallets in our runtime.
+		// Note that it is just an accumulation of the events emitted by each pallet.
+		//
+		// The variant names are the same as the pallet names, just like in `RuntimeCall`.
+		#[allow(non_camel_case_types)]
+		//
+		// The events are part of the state of the system pallet, so they are copied along with it.
+		#[derive(Debug, Clone)]
+		pub enum RuntimeEvent {
+			#( #pallet_names(#pallet_names::Event<#runtime_struct>) ),*
+		}
+
+		#(
+			impl From<#pallet_names::Event<#runtime_struct>> for RuntimeEvent {
+				fn from(event: #pallet_names::Event<#runtime_struct>) -> Self {
+					RuntimeEvent::#pallet_names(event)
+				}
+			}
+		)*
+	};
+
+	// This quote block gives mutable access to each pallet of the `Runtime`, which is all that most
+	// pallets need to dispatch their calls. See `support::DispatchCall`.
+	let as_mut_impl = quote! {
+		impl AsMut<system::Pallet<#runtime_struct>> for #runtime_struct {
+			fn as_mut(&mut self) -> &mut system::Pallet<#runtime_struct> {
+				&mut self.system
+			}
+		}
+
+		#(
+			impl AsMut<#pallet_types> for #runtime_struct {
+				fn as_mut(&mut self) -> &mut #pallet_types {
+					&mut self.#pallet_names
+				}
+			}
+		)*
+	};
+
 	// We combine and return all the generated code.
 	quote! {
 		#dispatch_impl
+		#serde_impl
+		#event_impl
 		#runtime_impl
+		#clone_state_impl
+		#history_impl
+		#hooks_impl
+		#as_mut_impl
 	}
 	.into()
 }
@@ -6,11 +6,7 @@ pub fn runtime(
 	_attr: proc_macro::TokenStream,
 	item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-	// The final expanded code will be placed here.
-	// Since our macro only adds new code, our final product will contain all of our old code too,
-	// hence we clone `item`.
-	let mut finished = item.clone();
-	let item_mod = syn::parse_macro_input!(item as syn::Item);
+	let mut item_mod = syn::parse_macro_input!(item as syn::Item);
 
 	// First we parse the `Runtime` struct...
 	let generated: proc_macro::TokenStream = match parse::RuntimeDef::try_from(item_mod.clone()) {
@@ -19,6 +15,16 @@ pub fn runtime(
 		Err(e) => e.to_compile_error().into(),
 	};
 
+	// The final expanded code will be placed here.
+	// Since our macro only adds new code, our final product will contain all of our old code too,
+	// except for the `#[history]` attribute, which is not a real attribute.
+	if let syn::Item::Struct(item_struct) = &mut item_mod {
+		for field in &mut item_struct.fields {
+			field.attrs.retain(|attr| !parse::is_history_attr(attr));
+		}
+	}
+	let mut finished: proc_macro::TokenStream = quote::quote!(#item_mod).into();
+
 	// Add our generated code to the end, and return the final result.
 	finished.extend(generated);
 	return finished;
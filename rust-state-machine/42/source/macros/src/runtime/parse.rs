@@ -8,6 +8,9 @@ pub struct RuntimeDef {
 	/// This is the list of pallets included in the `Runtime` struct. We omit `system` from this
 	/// list, but during parsing we check that system exists.
 	pub pallets: Vec<(syn::Ident, syn::Type)>,
+	/// This is the name of the field marked with `#[history]`, if any, which keeps the states of
+	/// the runtime before its most recent blocks. It is not a pallet.
+	pub history: Option<syn::Ident>,
 }
 
 impl RuntimeDef {
@@ -26,17 +29,32 @@ impl RuntimeDef {
 
 		// Here is where we will store a list of all the pallets.
 		let mut pallets = vec![];
+		let mut history = None;
 		// We skip `system`, which we ensure is the first field in `check_system`.
 		for field in item_struct.fields.into_iter().skip(1) {
 			if let Some(ident) = field.ident {
+				if field.attrs.iter().any(is_history_attr) {
+					if history.is_some() {
+						let msg = "Invalid runtime, expected at most one `#[history]` field";
+						return Err(syn::Error::new(ident.span(), msg))
+					}
+					history = Some(ident);
+					continue
+				}
+				check_pallet(&ident, &field.ty)?;
 				pallets.push((ident, field.ty))
 			}
 		}
 
-		Ok(Self { runtime_struct, pallets })
+		Ok(Self { runtime_struct, pallets, history })
 	}
 }
 
+/// Check if `attr` is the `#[history]` attribute of a field of the `Runtime`.
+pub fn is_history_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("history")
+}
+
 /// This function checks that the `system` pallet is the first pallet included in the `Runtime`
 /// struct. We make many assumptions about the `system` pallet in order to keep these macros simple.
 /// For example, we assume that the system pallet has no callable functions, and that it contains
@@ -65,3 +83,28 @@ fn check_system(item_struct: &syn::ItemStruct) -> syn::Result<()> {
 
 	Ok(())
 }
+
+/// This function checks that the type of the pallet `name` is `name::Pallet<..>`.
+///
+/// We use the name of the field to find the `Call` and `Event` types of the pallet, so the field
+/// must have the same name as the module of the pallet. Checking this here gives a much clearer
+/// error than the type errors we would otherwise generate.
+fn check_pallet(name: &syn::Ident, ty: &syn::Type) -> syn::Result<()> {
+	let is_pallet = match ty {
+		syn::Type::Path(type_path) if type_path.qself.is_none() => {
+			let segments = &type_path.path.segments;
+			segments.len() == 2 && segments[0].ident == *name && segments[1].ident == "Pallet"
+		},
+		_ => false,
+	};
+
+	if !is_pallet {
+		let msg = format!(
+			"Invalid pallet `{name}`, expected a field of type `{name}::Pallet<Self>`. The runtime \
+			 uses the `Call` and `Event` types of the module with the same name as the field"
+		);
+		return Err(syn::Error::new(ty.span(), msg))
+	}
+
+	Ok(())
+}
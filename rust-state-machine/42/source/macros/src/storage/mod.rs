@@ -0,0 +1,94 @@
+use quote::quote;
+use syn::spanned::Spanned;
+
+/// See the `fn storage` docs at the `lib.rs` of this crate for a high level definition.
+pub fn storage(
+	_attr: proc_macro::TokenStream,
+	item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+	let item_struct = syn::parse_macro_input!(item as syn::ItemStruct);
+
+	match expand_storage(item_struct) {
+		Ok(expanded) => expanded.into(),
+		Err(e) => e.to_compile_error().into(),
+	}
+}
+
+/// Check if `attr` is the `#[transient]` attribute of a storage field.
+fn is_transient_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("transient")
+}
+
+/// Check if `attr` is the `#[not_in_state_root]` attribute of a storage field.
+fn is_not_in_state_root_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("not_in_state_root")
+}
+
+fn expand_storage(mut item_struct: syn::ItemStruct) -> syn::Result<proc_macro2::TokenStream> {
+	// Every field of the pallet struct should be named, like the storage items of a pallet.
+	let fields = match &mut item_struct.fields {
+		syn::Fields::Named(fields) => fields,
+		_ => {
+			let msg = "Invalid storage, expected a struct with named fields";
+			return Err(syn::Error::new(item_struct.span(), msg))
+		},
+	};
+
+	// Here is where we will store the types of the fields which are part of the state, and the
+	// names and types of the fields which are part of the state root.
+	let mut fields_type = vec![];
+	let mut encoded_name = vec![];
+	let mut encoded_type = vec![];
+	for field in &mut fields.named {
+		// A `#[transient]` field is not a real attribute, so we replace it with skipping the field,
+		// which then takes its default value when the storage is loaded.
+		if field.attrs.iter().any(is_transient_attr) {
+			field.attrs.retain(|attr| !is_transient_attr(attr));
+			field.attrs.push(syn::parse_quote! { #[cfg_attr(feature = "serde", serde(skip))] });
+			continue;
+		}
+		fields_type.push(field.ty.clone());
+		// A `#[not_in_state_root]` field is saved and loaded like the rest of the state, but is not
+		// encoded, so it does not change the state root.
+		if field.attrs.iter().any(is_not_in_state_root_attr) {
+			field.attrs.retain(|attr| !is_not_in_state_root_attr(attr));
+		} else {
+			encoded_name.push(field.ident.clone());
+			encoded_type.push(field.ty.clone());
+		}
+	}
+
+	// Like for a `Call` enum, we require that each of the field types can be serialized.
+	let serde_attrs = crate::call::expand::expand_serde_attrs(&[fields_type]);
+
+	// Like `macros::Encode`, we require that each of the encoded field types which depends on a
+	// generic parameter can be encoded, rather than the parameters themselves.
+	let params = item_struct
+		.generics
+		.type_params()
+		.map(|param| param.ident.clone())
+		.collect::<Vec<_>>();
+	let mut generics = item_struct.generics.clone();
+	let where_clause = generics.make_where_clause();
+	for ty in encoded_type {
+		if crate::encode::mentions_any(quote! { #ty }, &params) {
+			where_clause.predicates.push(syn::parse_quote! { #ty: crate::support::Encode });
+		}
+	}
+	let name = &item_struct.ident;
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+	Ok(quote! {
+		#serde_attrs
+		#item_struct
+
+		// The state of the pallet is made up of its storage, in order, except for the fields which
+		// are transient or not part of the state root.
+		impl #impl_generics crate::support::Encode for #name #ty_generics #where_clause {
+			#[allow(unused_variables)]
+			fn encode_to(&self, encoded: &mut Vec<u8>) {
+				#( crate::support::Encode::encode_to(&self.#encoded_name, encoded); )*
+			}
+		}
+	})
+}
@@ -0,0 +1,289 @@
+use crate::support::{write_snapshot_map, DispatchResult};
+use num::traits::{CheckedAdd, CheckedSub, Zero};
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Assets Module.
+pub trait Config: crate::system::Config {
+	/// A type which uniquely identifies a fungible asset.
+	/// Usually an unsigned integer.
+	type AssetId: Ord + Clone;
+	/// A type which can represent the balance of an account in any asset.
+	/// Usually a large unsigned integer.
+	type Balance: Zero + CheckedSub + CheckedAdd + Copy + PartialOrd;
+}
+
+/// The details of an asset: the account which may mint it, and how much of it exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssetDetails<AccountId, Balance> {
+	/// The account which created the asset, which is the only one allowed to mint it.
+	pub admin: AccountId,
+	/// The total amount of the asset held by all accounts.
+	pub supply: Balance,
+}
+
+/// The events which can be emitted by the Assets Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `admin` created the new asset `asset_id`.
+	Created { asset_id: T::AssetId, admin: T::AccountId },
+	/// `amount` of the asset `asset_id` was minted into the account `who`.
+	Minted { asset_id: T::AssetId, who: T::AccountId, amount: T::Balance },
+	/// `amount` of the asset `asset_id` was transferred from `from` to `to`.
+	Transferred { asset_id: T::AssetId, from: T::AccountId, to: T::AccountId, amount: T::Balance },
+}
+
+/// This is the Assets Module.
+/// It keeps track of any number of fungible assets, each with its own admin, total supply and
+/// balance of every account. Unlike the Balances Module, which keeps track of the single native
+/// token of the chain, assets are created by users, and cannot be used to pay fees.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an asset to its details.
+	assets: BTreeMap<T::AssetId, AssetDetails<T::AccountId, T::Balance>>,
+	/// A storage map from an asset and an account to the balance of the account in that asset.
+	/// Accounts with no balance in an asset have no entry.
+	balances: BTreeMap<(T::AssetId, T::AccountId), T::Balance>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Assets Module.
+	pub fn new() -> Self {
+		Self { assets: BTreeMap::new(), balances: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Assets Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::AssetId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "assets", "assets", &self.assets)?;
+		write_snapshot_map(f, "assets", "balances", &self.balances)
+	}
+
+	/// Get the details of the asset `asset_id`, if it exists.
+	pub fn asset(&self, asset_id: &T::AssetId) -> Option<&AssetDetails<T::AccountId, T::Balance>> {
+		self.assets.get(asset_id)
+	}
+
+	/// Get the balance of `who` in the asset `asset_id`.
+	/// If the account has no balance in the asset, or the asset does not exist, we return zero.
+	pub fn balance(&self, asset_id: &T::AssetId, who: &T::AccountId) -> T::Balance {
+		*self
+			.balances
+			.get(&(asset_id.clone(), who.clone()))
+			.unwrap_or(&T::Balance::zero())
+	}
+
+	/// Get the total supply of the asset `asset_id`, or zero if it does not exist.
+	pub fn total_supply(&self, asset_id: &T::AssetId) -> T::Balance {
+		self.asset(asset_id).map_or(T::Balance::zero(), |details| details.supply)
+	}
+
+	/// Check if `who` is the admin of any asset, or has a balance in any asset.
+	pub fn has_assets(&self, who: &T::AccountId) -> bool {
+		self.assets.values().any(|details| details.admin == *who)
+			|| self.balances.keys().any(|(_, holder)| holder == who)
+	}
+
+	/// Move the balance of `from` in every asset to `to`, and make `to` the admin of every asset
+	/// which `from` created. Any balance of `to` is overwritten, and the total supply of every
+	/// asset is unchanged. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		let held = self
+			.balances
+			.iter()
+			.filter(|((_, holder), _)| holder == from)
+			.map(|((asset_id, _), amount)| (asset_id.clone(), *amount))
+			.collect::<Vec<_>>();
+		for (asset_id, amount) in held {
+			self.balances.remove(&(asset_id.clone(), from.clone()));
+			self.set_balance(&asset_id, to, amount);
+		}
+		for details in self.assets.values_mut().filter(|details| details.admin == *from) {
+			details.admin = to.clone();
+		}
+	}
+
+	// Set the balance of `who` in the asset `asset_id`, removing its entry if it is zero.
+	fn set_balance(&mut self, asset_id: &T::AssetId, who: &T::AccountId, amount: T::Balance) {
+		let key = (asset_id.clone(), who.clone());
+		if amount.is_zero() {
+			self.balances.remove(&key);
+		} else {
+			self.balances.insert(key, amount);
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Create a new asset with the id `asset_id` and no supply, with the `caller` as its admin.
+	/// This function will return an error if an asset with that id already exists.
+	pub fn create(&mut self, caller: T::AccountId, asset_id: T::AssetId) -> DispatchResult {
+		if self.assets.contains_key(&asset_id) {
+			return Err("asset already exists".into());
+		}
+		let details = AssetDetails { admin: caller.clone(), supply: T::Balance::zero() };
+		self.assets.insert(asset_id.clone(), details);
+		self.deposit_event(Event::Created { asset_id, admin: caller });
+		Ok(())
+	}
+
+	/// Mint `amount` of the asset `asset_id` into the account `who`, increasing its total supply.
+	/// This function will return an error if the asset does not exist, if the `caller` is not its
+	/// admin, or if the total supply would overflow.
+	pub fn mint(
+		&mut self,
+		caller: T::AccountId,
+		asset_id: T::AssetId,
+		who: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		let details = self.assets.get_mut(&asset_id).ok_or("asset does not exist")?;
+		if details.admin != caller {
+			return Err("caller is not the asset admin".into());
+		}
+		// No balance can be larger than the total supply, so it cannot overflow if the supply does.
+		details.supply = details.supply.checked_add(&amount).ok_or("total supply overflow")?;
+		let balance = self.balance(&asset_id, &who);
+		self.set_balance(&asset_id, &who, balance.checked_add(&amount).ok_or("balance overflow")?);
+		self.deposit_event(Event::Minted { asset_id, who, amount });
+		Ok(())
+	}
+
+	/// Transfer `amount` of the asset `asset_id` from the `caller` to `to`.
+	/// This function will return an error if the asset does not exist, or if the caller does not
+	/// have enough of it.
+	pub fn transfer(
+		&mut self,
+		caller: T::AccountId,
+		asset_id: T::AssetId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		if !self.assets.contains_key(&asset_id) {
+			return Err("asset does not exist".into());
+		}
+		let from_balance = self.balance(&asset_id, &caller);
+		let new_from_balance = from_balance.checked_sub(&amount).ok_or("insufficient balance")?;
+		self.set_balance(&asset_id, &caller, new_from_balance);
+		// The amount is part of the total supply, so it can always be added to another balance.
+		let to_balance = self.balance(&asset_id, &to);
+		self.set_balance(
+			&asset_id,
+			&to,
+			to_balance.checked_add(&amount).ok_or("balance overflow")?,
+		);
+		self.deposit_event(Event::Transferred { asset_id, from: caller, to, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	struct TestConfig;
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {
+		type AssetId = u32;
+		type Balance = u128;
+	}
+
+	#[test]
+	fn create_and_mint() {
+		let mut assets = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			assets.mint(alice.clone(), 0, bob.clone(), 10),
+			Err("asset does not exist".into())
+		);
+		assert_eq!(assets.create(alice.clone(), 0), Ok(()));
+		assert_eq!(assets.create(bob.clone(), 0), Err("asset already exists".into()));
+		assert_eq!(assets.asset(&0).map(|details| &details.admin), Some(&alice));
+
+		// Only the admin of an asset can mint it, into any account.
+		assert_eq!(
+			assets.mint(bob.clone(), 0, bob.clone(), 10),
+			Err("caller is not the asset admin".into())
+		);
+		assert_eq!(assets.mint(alice.clone(), 0, bob.clone(), 10), Ok(()));
+		assert_eq!(assets.mint(alice.clone(), 0, alice.clone(), 5), Ok(()));
+		assert_eq!(assets.balance(&0, &bob), 10);
+		assert_eq!(assets.balance(&0, &alice), 5);
+		assert_eq!(assets.total_supply(&0), 15);
+		assert_eq!(
+			assets.mint(alice.clone(), 0, bob.clone(), u128::MAX),
+			Err("total supply overflow".into())
+		);
+		assert_eq!(assets.total_supply(&0), 15);
+		assert_eq!(assets.take_events().len(), 3);
+	}
+
+	#[test]
+	fn assets_are_independent() {
+		let mut assets = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Each asset has its own admin, supply and balances.
+		assert_eq!(assets.create(alice.clone(), 0), Ok(()));
+		assert_eq!(assets.create(bob.clone(), 1), Ok(()));
+		assert_eq!(assets.mint(alice.clone(), 0, alice.clone(), 100), Ok(()));
+		assert_eq!(assets.mint(bob.clone(), 1, bob.clone(), 50), Ok(()));
+		assert_eq!(
+			assets.mint(alice.clone(), 1, alice.clone(), 50),
+			Err("caller is not the asset admin".into())
+		);
+
+		assert_eq!(assets.transfer(alice.clone(), 0, bob.clone(), 30), Ok(()));
+		assert_eq!(
+			assets.transfer(alice.clone(), 1, bob.clone(), 1),
+			Err("insufficient balance".into())
+		);
+		assert_eq!(assets.transfer(bob.clone(), 1, alice.clone(), 50), Ok(()));
+
+		assert_eq!((assets.balance(&0, &alice), assets.balance(&0, &bob)), (70, 30));
+		assert_eq!((assets.balance(&1, &alice), assets.balance(&1, &bob)), (50, 0));
+		assert_eq!((assets.total_supply(&0), assets.total_supply(&1)), (100, 50));
+		// An account with no balance left in an asset has no entry for it.
+		assert!(!assets.balances.contains_key(&(1, bob.clone())));
+		assert_eq!(
+			assets.transfer(alice.clone(), 2, bob.clone(), 0),
+			Err("asset does not exist".into())
+		);
+	}
+}
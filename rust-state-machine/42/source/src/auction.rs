@@ -0,0 +1,532 @@
+use crate::{
+	balances,
+	support::{
+		write_snapshot_map, write_snapshot_value, DispatchContext, DispatchResult, PalletHooks,
+	},
+	uniques,
+};
+use num::traits::Zero;
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Auction Module.
+///
+/// The items which are sold are the items of the Uniques Module.
+pub trait Config: balances::Config + uniques::Config {}
+
+/// The identifier of an auction. Auctions are numbered in the order they are started.
+pub type AuctionId = u32;
+
+/// An auction of `item` by `seller`, which ends at `end_block`.
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Auction<AccountId, Item, BlockNumber> {
+	/// The account which is selling the item, and is paid the winning bid.
+	pub seller: AccountId,
+	/// The item being sold, which is locked until the auction ends.
+	pub item: Item,
+	/// The last block in which bids can be placed. The auction is settled at the end of it.
+	pub end_block: BlockNumber,
+}
+
+/// An `Auction` with the types of a given config.
+pub type AuctionOf<T> = Auction<
+	<T as crate::system::Config>::AccountId,
+	<T as uniques::Config>::ItemId,
+	<T as crate::system::Config>::BlockNumber,
+>;
+
+/// The events which can be emitted by the Auction Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `seller` started the auction `id` of `item`, which ends at `end_block`.
+	Started { id: AuctionId, seller: T::AccountId, item: T::ItemId, end_block: T::BlockNumber },
+	/// `bidder` placed the new highest bid of `amount` in the auction `id`.
+	Bid { id: AuctionId, bidder: T::AccountId, amount: T::Balance },
+	/// The auction `id` ended, and `winner` paid `amount` to the seller for the item.
+	Sold { id: AuctionId, winner: T::AccountId, amount: T::Balance },
+	/// The auction `id` ended without a winner, either because nobody bid, or because the winning
+	/// bid could not be paid to the seller, in which case it was refunded.
+	Unsold { id: AuctionId },
+}
+
+/// This is the Auction Module.
+/// It allows an account to auction an item it owns in the Uniques Module until a given block.
+/// While the auction is open, anyone but the seller can bid more than the current highest bid, and
+/// at the end of the last block the highest bidder pays their bid to the seller, and is given the
+/// item.
+///
+/// The item is locked in the Uniques Module while it is being sold, and only the highest bid of
+/// each auction is reserved in the Balances Module: when it is outbid, it is unreserved again, so
+/// the calls and hooks of this pallet are given access to the whole runtime.
+#[derive(Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The id of the next auction to be started.
+	next_id: AuctionId,
+	/// A storage map from an auction id to the auction, for every auction which has not ended.
+	auctions: BTreeMap<AuctionId, AuctionOf<T>>,
+	/// A storage map from an auction id to its highest bidder and their bid, if anyone has bid.
+	bids: BTreeMap<AuctionId, (T::AccountId, T::Balance)>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+// We cannot derive `Debug`, since that would only require `T` to implement it, and not our
+// auctions.
+impl<T: Config> core::fmt::Debug for Pallet<T>
+where
+	AuctionOf<T>: core::fmt::Debug,
+	T::AccountId: core::fmt::Debug,
+	T::Balance: core::fmt::Debug,
+	Event<T>: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("Pallet")
+			.field("next_id", &self.next_id)
+			.field("auctions", &self.auctions)
+			.field("bids", &self.bids)
+			.field("events", &self.events)
+			.finish()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Auction Module.
+	pub fn new() -> Self {
+		Self { next_id: 0, auctions: BTreeMap::new(), bids: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Auction Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		AuctionOf<T>: core::fmt::Debug,
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "auction", "next_id", &self.next_id)?;
+		write_snapshot_map(f, "auction", "auctions", &self.auctions)?;
+		write_snapshot_map(f, "auction", "bids", &self.bids)
+	}
+
+	/// Get the auction with the given `id`, if it has not ended.
+	pub fn auction(&self, id: AuctionId) -> Option<&AuctionOf<T>> {
+		self.auctions.get(&id)
+	}
+
+	/// Get the highest bidder of the auction `id` and their bid, if anyone has bid.
+	pub fn highest_bid(&self, id: AuctionId) -> Option<&(T::AccountId, T::Balance)> {
+		self.bids.get(&id)
+	}
+
+	// Remove all of the auctions which end by block `now`, along with their highest bid.
+	fn take_ended(&mut self, now: T::BlockNumber) -> Vec<(AuctionId, AuctionOf<T>)> {
+		let ended = self
+			.auctions
+			.iter()
+			.filter(|(_, auction)| auction.end_block <= now)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		ended
+			.into_iter()
+			.filter_map(|id| self.auctions.remove(&id).map(|auction| (id, auction)))
+			.collect()
+	}
+}
+
+// Items are locked in the Uniques Module, and bids are reserved in the Balances Module, so our
+// calls need access to both. Our hooks, which settle the auctions, are implemented by hand below.
+#[macros::call(custom_hooks, R: AsMut<balances::Pallet<T>> + AsMut<uniques::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Start an auction of `item` by the caller, which is open for bids until `end_block`. The
+	/// item is locked in the Uniques Module until the auction ends, so it cannot be given away or
+	/// sold twice in the meantime.
+	/// This function will return an error if `end_block` is before the block of the `context`, or
+	/// if the item cannot be locked. See `uniques::Pallet::lock`.
+	pub fn start_auction<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		item: T::ItemId,
+		end_block: T::BlockNumber,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<uniques::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let seller = origin.ensure_signed()?;
+		if end_block < block_number {
+			return Err("auction would end in the past".into());
+		}
+		let auction: &mut Self = runtime.as_mut();
+		let id = auction.next_id;
+		let next_id = id.checked_add(1).ok_or("too many auctions")?;
+
+		let uniques: &mut uniques::Pallet<T> = runtime.as_mut();
+		uniques.lock(&seller, &item)?;
+
+		let auction: &mut Self = runtime.as_mut();
+		auction.next_id = next_id;
+		auction
+			.auctions
+			.insert(id, Auction { seller: seller.clone(), item: item.clone(), end_block });
+		auction.deposit_event(Event::Started { id, seller, item, end_block });
+		Ok(())
+	}
+
+	/// Bid `amount` in the auction `id`, which is reserved from the free balance of the caller.
+	/// The bid must be higher than the current highest bid, which is then unreserved for the
+	/// previous highest bidder. If the caller already has the highest bid, only the difference is
+	/// reserved.
+	/// This function will return an error if the auction does not exist, if the block of the
+	/// `context` is after the end block, if the caller is the seller, if the bid is not higher than
+	/// the current highest bid, or if the caller does not have enough free balance.
+	pub fn bid<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		id: AuctionId,
+		amount: T::Balance,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let bidder = origin.ensure_signed()?;
+		let auction: &mut Self = runtime.as_mut();
+		let open = auction.auction(id).ok_or("auction does not exist")?;
+		if block_number > open.end_block {
+			return Err("auction has ended".into());
+		}
+		if bidder == open.seller {
+			return Err("seller cannot bid".into());
+		}
+		let previous = auction.highest_bid(id).cloned();
+		let highest = previous.as_ref().map_or(T::Balance::zero(), |(_, bid)| *bid);
+		if amount <= highest {
+			return Err("bid too low".into());
+		}
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		match previous {
+			// The bid is higher, so this cannot underflow.
+			Some((leader, bid)) if leader == bidder => balances.reserve(&bidder, amount - bid)?,
+			Some((leader, bid)) => {
+				balances.reserve(&bidder, amount)?;
+				// The previous bid is always reserved, so all of it is unreserved.
+				balances.unreserve(&leader, bid);
+			},
+			None => balances.reserve(&bidder, amount)?,
+		}
+
+		let auction: &mut Self = runtime.as_mut();
+		auction.bids.insert(id, (bidder.clone(), amount));
+		auction.deposit_event(Event::Bid { id, bidder, amount });
+		Ok(())
+	}
+}
+
+// At the end of each block, we settle the auctions which end in it: the highest bidder pays their
+// reserved bid to the seller, and is given the item, which is unlocked either way. This needs
+// access to the Balances and Uniques Modules, so we implement `PalletHooks` by hand, instead of
+// implementing `Hooks` for the pallet.
+impl<T: Config, R> PalletHooks<Pallet<T>, T::BlockNumber> for R
+where
+	R: AsMut<Pallet<T>> + AsMut<balances::Pallet<T>> + AsMut<uniques::Pallet<T>>,
+{
+	fn on_finalize(&mut self, block_number: T::BlockNumber) {
+		let auction: &mut Pallet<T> = self.as_mut();
+		for (id, ended) in auction.take_ended(block_number) {
+			let auction: &mut Pallet<T> = self.as_mut();
+			let sale = match auction.bids.remove(&id) {
+				Some((winner, amount)) => {
+					let balances: &mut balances::Pallet<T> = self.as_mut();
+					// The seller may not be able to receive the bid, for example if it is below
+					// the existential deposit, in which case the bid is refunded instead.
+					match balances.repatriate_reserved(&winner, &ended.seller, amount) {
+						Ok(()) => Some((winner, amount)),
+						Err(_) => {
+							balances.unreserve(&winner, amount);
+							None
+						},
+					}
+				},
+				None => None,
+			};
+
+			let uniques: &mut uniques::Pallet<T> = self.as_mut();
+			uniques.unlock(&ended.item);
+			let event = match sale {
+				Some((winner, amount)) => {
+					uniques
+						.transfer(ended.seller, ended.item, winner.clone())
+						.expect("the seller owns the item while it is locked");
+					Event::Sold { id, winner, amount }
+				},
+				None => Event::Unsold { id },
+			};
+			let auction: &mut Pallet<T> = self.as_mut();
+			auction.deposit_event(event);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext, PalletHooks},
+		uniques,
+	};
+
+	// A minimal runtime which only contains the pallets needed by the auction pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		uniques: uniques::Pallet<TestRuntime>,
+		auction: super::Pallet<TestRuntime>,
+	}
+
+	struct ExistentialDeposit;
+	impl crate::support::Get<u128> for ExistentialDeposit {
+		fn get() -> u128 {
+			5
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ExistentialDeposit;
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl uniques::Config for TestRuntime {
+		type ItemId = &'static str;
+	}
+
+	impl super::Config for TestRuntime {}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<uniques::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut uniques::Pallet<TestRuntime> {
+			&mut self.uniques
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.auction
+		}
+	}
+
+	// The context of a call made by `caller` in the block `block_number`.
+	fn context(caller: &str, block_number: u32) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), block_number)
+	}
+
+	fn bid(id: u32, amount: u128) -> Call<TestRuntime> {
+		Call::bid { id, amount }
+	}
+
+	// Run the hooks of the auction pallet at the end of block `block_number`.
+	fn finalize(runtime: &mut TestRuntime, block_number: u32) {
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_finalize(runtime, block_number);
+	}
+
+	// A runtime where alice owns a painting and a sculpture, and auctions the painting in the
+	// auction 0 until block 10, and bob and charlie can bid up to 100 each.
+	fn new_runtime() -> TestRuntime {
+		let mut runtime = TestRuntime {
+			balances: balances::Pallet::new(),
+			uniques: uniques::Pallet::new(),
+			auction: super::Pallet::new(),
+		};
+		let alice = "alice".to_string();
+		assert_eq!(runtime.uniques.mint(alice.clone(), "painting"), Ok(()));
+		assert_eq!(runtime.uniques.mint(alice, "sculpture"), Ok(()));
+		runtime.balances.set_balance(&"bob".to_string(), 100);
+		runtime.balances.set_balance(&"charlie".to_string(), 100);
+		let start = Call::start_auction { item: "painting", end_block: 10 };
+		assert_eq!(runtime.dispatch_call(context("alice", 1), start), Ok(()));
+		runtime
+	}
+
+	#[test]
+	fn start_auction() {
+		let mut runtime = new_runtime();
+
+		let auction = runtime.auction.auction(0).unwrap();
+		assert_eq!(
+			(auction.seller.as_str(), auction.item, auction.end_block),
+			("alice", "painting", 10)
+		);
+		assert_eq!(runtime.auction.highest_bid(0), None);
+		assert!(runtime.uniques.is_locked(&"painting"));
+
+		let start = Call::start_auction { item: "sculpture", end_block: 4 };
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 5), start),
+			Err("auction would end in the past".into())
+		);
+
+		// Only the owner of an item can auction it, and only once at a time.
+		let start = |item| Call::start_auction { item, end_block: 10 };
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 1), start("sculpture")),
+			Err("caller does not own the item".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 1), start("vase")),
+			Err("item does not exist".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 1), start("painting")),
+			Err("item is locked".into())
+		);
+		assert_eq!(runtime.auction.auction(1), None);
+		assert!(!runtime.uniques.is_locked(&"sculpture"));
+
+		// The seller cannot give the item away while it is being sold.
+		assert_eq!(
+			runtime.uniques.transfer("alice".to_string(), "painting", "bob".to_string()),
+			Err("item is locked".into())
+		);
+	}
+
+	#[test]
+	fn competitive_auction() {
+		let mut runtime = new_runtime();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 2), bid(0, 10)),
+			Err("seller cannot bid".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 2), bid(1, 10)),
+			Err("auction does not exist".into())
+		);
+		assert_eq!(runtime.dispatch_call(context("bob", 2), bid(0, 0)), Err("bid too low".into()));
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 2), bid(0, 101)),
+			Err("Not enough funds.".into())
+		);
+
+		// Each new highest bid refunds the previous leader.
+		assert_eq!(runtime.dispatch_call(context("bob", 2), bid(0, 30)), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 30);
+		assert_eq!(
+			runtime.dispatch_call(context("charlie", 3), bid(0, 30)),
+			Err("bid too low".into())
+		);
+		assert_eq!(runtime.dispatch_call(context("charlie", 3), bid(0, 40)), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.balances.reserved_balance(&charlie), 40);
+
+		// A leader who raises their own bid only reserves the difference.
+		assert_eq!(runtime.dispatch_call(context("bob", 4), bid(0, 50)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("bob", 5), bid(0, 70)), Ok(()));
+		assert_eq!(runtime.balances.reserved_balance(&bob), 70);
+		assert_eq!(runtime.balances.balance(&bob), 30);
+		assert_eq!(runtime.balances.reserved_balance(&charlie), 0);
+		assert_eq!(runtime.auction.highest_bid(0), Some(&(bob, 70)));
+		assert_eq!(runtime.auction.take_events().len(), 5);
+	}
+
+	#[test]
+	fn settlement_at_end_block() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		assert_eq!(runtime.dispatch_call(context("bob", 2), bid(0, 30)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("charlie", 10), bid(0, 60)), Ok(()));
+
+		// Nothing happens before the end block.
+		finalize(&mut runtime, 9);
+		assert!(runtime.auction.auction(0).is_some());
+
+		// At the end of the end block, the highest bidder pays the seller.
+		finalize(&mut runtime, 10);
+		assert_eq!(runtime.auction.auction(0), None);
+		assert_eq!(runtime.auction.highest_bid(0), None);
+		assert_eq!(runtime.balances.balance(&alice), 60);
+		assert_eq!(runtime.balances.balance(&charlie), 40);
+		assert_eq!(runtime.balances.reserved_balance(&charlie), 0);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.balances.total_issuance(), 200);
+		assert_eq!(runtime.uniques.owner_of(&"painting"), Some(&charlie));
+		assert!(!runtime.uniques.is_locked(&"painting"));
+		assert!(matches!(
+			runtime.auction.take_events().last(),
+			Some(Event::Sold { id: 0, winner, amount: 60 }) if *winner == charlie
+		));
+
+		// Once an auction has ended, nobody can bid in it.
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 11), bid(0, 80)),
+			Err("auction does not exist".into())
+		);
+	}
+
+	#[test]
+	fn unsold_auctions() {
+		let mut runtime = new_runtime();
+		let bob = "bob".to_string();
+
+		// Bids after the end block are rejected, and an auction without bids is not sold.
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 11), bid(0, 10)),
+			Err("auction has ended".into())
+		);
+		finalize(&mut runtime, 10);
+		assert!(matches!(runtime.auction.take_events().last(), Some(Event::Unsold { id: 0 })));
+		// The seller keeps the item, which can be sold again.
+		assert_eq!(runtime.uniques.owner_of(&"painting"), Some(&"alice".to_string()));
+		assert!(!runtime.uniques.is_locked(&"painting"));
+
+		// A winning bid which would leave the seller below the existential deposit is refunded.
+		let start = Call::start_auction { item: "sculpture", end_block: 20 };
+		assert_eq!(runtime.dispatch_call(context("alice", 11), start), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("bob", 12), bid(1, 4)), Ok(()));
+		finalize(&mut runtime, 20);
+		assert_eq!(runtime.balances.balance(&bob), 100);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 0);
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 0);
+		assert_eq!(runtime.uniques.owner_of(&"sculpture"), Some(&"alice".to_string()));
+		assert!(matches!(runtime.auction.take_events().last(), Some(Event::Unsold { id: 1 })));
+	}
+}
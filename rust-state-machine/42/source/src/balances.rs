@@ -1,77 +1,1001 @@
-use num::traits::{CheckedAdd, CheckedSub, Zero};
-use std::collections::BTreeMap;
+use crate::support::{
+	write_snapshot_map, write_snapshot_value, Get, StorageMap, Weight, DEFAULT_WEIGHT,
+};
+use num::traits::{CheckedAdd, CheckedSub, Saturating, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An identifier for a lock placed on an account's balance.
+/// Each pallet which locks balance should use its own unique id.
+pub type LockId = [u8; 8];
+
+/// The weight added to a `transfer_batch` call for each transfer in the batch, on top of the
+/// `DEFAULT_WEIGHT` of the call itself.
+pub const TRANSFER_BATCH_ITEM_WEIGHT: Weight = 1;
+
+/// All of the balance of an account, as returned by `Pallet::account`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccountData<Balance> {
+	/// The free balance, which includes the frozen balance.
+	pub free: Balance,
+	/// The reserved balance, which cannot be used.
+	pub reserved: Balance,
+	/// The part of the free balance which is frozen by locks, and cannot be transferred.
+	pub frozen: Balance,
+}
+
+/// The errors which can be returned by the Balances Module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalancesError {
+	/// The account does not have enough free balance.
+	InsufficientBalance,
+	/// An arithmetic operation overflowed.
+	Overflow,
+	/// The operation would leave an account with less than the existential deposit.
+	BelowExistentialDeposit,
+	/// The operation would use balance which is frozen by a lock.
+	LiquidityRestrictions,
+	/// The account cannot pay the fee of an extrinsic.
+	CannotPayFee,
+	/// The amount of a transfer is less than the minimum transfer amount.
+	BelowMinimumTransfer,
+	/// The spender is not allowed to transfer this much on behalf of the owner.
+	InsufficientAllowance,
+}
+
+// The message of each of our errors, which is what a `DispatchError` holds once our errors are
+// dispatched. See the `From<BalancesError>` impl for `DispatchError` below.
+impl From<BalancesError> for &'static str {
+	fn from(error: BalancesError) -> Self {
+		match error {
+			BalancesError::InsufficientBalance => "Not enough funds.",
+			BalancesError::Overflow => "Overflow",
+			BalancesError::BelowExistentialDeposit => "Balance below existential deposit.",
+			BalancesError::LiquidityRestrictions => "Liquidity restrictions.",
+			BalancesError::CannotPayFee => "cannot pay fee",
+			BalancesError::BelowMinimumTransfer => "transfer below minimum",
+			BalancesError::InsufficientAllowance => "insufficient allowance",
+		}
+	}
+}
+
+// Every error of the Balances Module is recoverable.
+impl From<BalancesError> for crate::support::DispatchError {
+	fn from(error: BalancesError) -> Self {
+		Self::Other(error.into())
+	}
+}
+
+impl core::fmt::Display for BalancesError {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		f.write_str((*self).into())
+	}
+}
+
+/// Where the fees paid for extrinsics go. See `Config::FeeDestination`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeeDestination {
+	/// The fees are burned, which reduces the total issuance.
+	Burn,
+	/// The fees are given to the `Treasury` account, where they can be spent.
+	#[default]
+	Treasury,
+}
+
+/// Where the dust of reaped accounts goes. See `Config::DustDestination`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DustDestination {
+	/// The dust is burned, which reduces the total issuance.
+	Burn,
+	/// The dust is given to the `Treasury` account, where it can be spent.
+	#[default]
+	Treasury,
+}
+
+/// A hook which is told about every successful transfer, so other modules can react to them. See
+/// `Config::OnTransfer`.
+///
+/// The hook does nothing for `()`. A pair of hooks runs both of them in order, so any number of
+/// hooks can be combined by nesting pairs.
+pub trait OnTransfer<AccountId, Balance> {
+	/// Called after `amount` was transferred from `from` to `to`.
+	fn on_transfer(from: &AccountId, to: &AccountId, amount: Balance);
+}
+
+impl<AccountId, Balance> OnTransfer<AccountId, Balance> for () {
+	fn on_transfer(_from: &AccountId, _to: &AccountId, _amount: Balance) {}
+}
+
+impl<AccountId, Balance: Copy, A, B> OnTransfer<AccountId, Balance> for (A, B)
+where
+	A: OnTransfer<AccountId, Balance>,
+	B: OnTransfer<AccountId, Balance>,
+{
+	fn on_transfer(from: &AccountId, to: &AccountId, amount: Balance) {
+		A::on_transfer(from, to, amount);
+		B::on_transfer(from, to, amount);
+	}
+}
 
 /// The configuration trait for the Balances Module.
 /// Contains the basic types needed for handling balances.
 pub trait Config: crate::system::Config {
 	/// A type which can represent the balance of an account.
 	/// Usually this is a large unsigned integer.
-	type Balance: Zero + CheckedSub + CheckedAdd + Copy;
+	/// Most balance arithmetic fails on overflow, but some of it, like slashing, saturates instead.
+	/// See `saturating_add_balance`.
+	type Balance: Zero + CheckedSub + CheckedAdd + Saturating + Copy + PartialOrd;
+	/// The minimum balance an account must have to exist. Accounts which would end up with less
+	/// than this (but more than zero) cannot be created or left behind by a transfer.
+	type ExistentialDeposit: Get<Self::Balance>;
+	/// The smallest amount which can be transferred at once. Transfers are cheap to make, so a
+	/// positive minimum stops accounts from spamming the chain with tiny, or even empty, transfers.
+	type MinimumTransfer: Get<Self::Balance>;
+	/// The fee paid for each unit of weight used by an extrinsic.
+	type FeeMultiplier: Get<Self::Balance>;
+	/// The account which collects the fees paid for extrinsics.
+	type Treasury: Get<Self::AccountId>;
+	/// Whether the fees paid for extrinsics are burned, or collected by the `Treasury` account.
+	type FeeDestination: Get<FeeDestination>;
+	/// Whether the dust left behind when an account is reaped is burned, or collected by the
+	/// `Treasury` account.
+	type DustDestination: Get<DustDestination>;
+	/// The hook which is called after every successful transfer, but not after a transfer which
+	/// fails. Use `()` for no hook.
+	type OnTransfer: OnTransfer<Self::AccountId, Self::Balance>;
+}
+
+/// The events which can be emitted by the Balances Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `amount` was transferred from `from` to `to`.
+	Transferred { from: T::AccountId, to: T::AccountId, amount: T::Balance },
+	/// `who` paid a `fee` for an extrinsic, which was collected by the treasury.
+	FeePaid { who: T::AccountId, fee: T::Balance },
+	/// The account `who` was reaped with less than the existential deposit, and this `amount` of
+	/// dust was handled according to the `DustDestination`.
+	DustLost { who: T::AccountId, amount: T::Balance },
+	/// `owner` allowed `spender` to transfer up to `amount` on their behalf.
+	Approved { owner: T::AccountId, spender: T::AccountId, amount: T::Balance },
+}
+
+/// Add `amount` to `balance`, saturating at the maximum balance instead of overflowing.
+///
+/// Most of the arithmetic of this pallet returns an error on overflow, but some operations, like
+/// slashes, rewards and fees, should never fail, and use these helpers instead.
+pub fn saturating_add_balance<Balance: Saturating>(balance: Balance, amount: Balance) -> Balance {
+	balance.saturating_add(amount)
+}
+
+/// Subtract `amount` from `balance`, saturating at zero instead of underflowing.
+/// See `saturating_add_balance`.
+pub fn saturating_sub_balance<Balance: Saturating>(balance: Balance, amount: Balance) -> Balance {
+	balance.saturating_sub(amount)
 }
 
+/// The default storage backend of the balances: an in memory map from accounts to their balance.
+pub type BalanceMap<T> = BTreeMap<<T as crate::system::Config>::AccountId, <T as Config>::Balance>;
+
 /// This is the Balances Module.
 /// It is a simple module which keeps track of how much balance each account has in this state
 /// machine.
-#[derive(Debug)]
-pub struct Pallet<T: Config> {
-	// A simple storage mapping from accounts to their balances.
-	balances: BTreeMap<T::AccountId, T::Balance>,
+///
+/// The free and reserved balances are kept in the storage backend `S`, which is a `BalanceMap` by
+/// default. See `StorageMap`.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config, S = BalanceMap<T>> {
+	// A simple storage mapping from accounts to their free balances.
+	balances: S,
+	// A storage mapping from accounts to balance which is reserved, and cannot be used.
+	reserved: S,
+	// A storage mapping from accounts to the named locks placed on their free balance.
+	// Locks do not stack: the free balance cannot go below the largest lock on the account.
+	locks: BTreeMap<T::AccountId, Vec<(LockId, T::Balance)>>,
+	// A storage mapping from an owner and a spender to the amount of the free balance of the owner
+	// which the spender is allowed to transfer. See `approve`.
+	allowances: BTreeMap<(T::AccountId, T::AccountId), T::Balance>,
+	// The total amount of balance in the system, across all accounts.
+	total_issuance: T::Balance,
+	// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
 }
 
-impl<T: Config> Pallet<T> {
+impl<T: Config, S: StorageMap<T::AccountId, T::Balance>> Pallet<T, S> {
 	// Create a new instance of the balances module.
 	pub fn new() -> Self {
-		Self { balances: BTreeMap::new() }
+		Self {
+			balances: S::default(),
+			reserved: S::default(),
+			locks: BTreeMap::new(),
+			allowances: BTreeMap::new(),
+			total_issuance: T::Balance::zero(),
+			events: Vec::new(),
+		}
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Balances Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "balances", "free", self.balances.iter())?;
+		write_snapshot_map(f, "balances", "reserved", self.reserved.iter())?;
+		write_snapshot_map(f, "balances", "locks", &self.locks)?;
+		write_snapshot_map(f, "balances", "allowances", &self.allowances)?;
+		write_snapshot_value(f, "balances", "total_issuance", &self.total_issuance)
 	}
 
 	/// Set the balance of an account `who` to some `amount`.
+	/// The old balance of the account is removed from the total issuance, and the new one is added.
+	/// Panics if the total issuance would overflow. See `try_set_balance`.
 	pub fn set_balance(&mut self, who: &T::AccountId, amount: T::Balance) {
-		self.balances.insert(who.clone(), amount);
+		self.try_set_balance(who, amount).expect("total issuance overflow")
 	}
 
-	/// Get the balance of an account `who`.
+	/// Set the balance of an account `who` to some `amount`, like `set_balance`.
+	/// This function will return an error if the total issuance would overflow, in which case
+	/// nothing changes.
+	pub fn try_set_balance(
+		&mut self,
+		who: &T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		// The old balance is part of the total issuance, so this cannot underflow.
+		let new_total_issuance = saturating_sub_balance(self.total_issuance, self.balance(who))
+			.checked_add(&amount)
+			.ok_or(BalancesError::Overflow)?;
+
+		self.insert_free(who, amount);
+		self.total_issuance = new_total_issuance;
+
+		Ok(())
+	}
+
+	/// Get the free balance of an account `who`.
 	/// If the account has no stored balance, we return zero.
+	/// This does not include any balance which is reserved.
 	pub fn balance(&self, who: &T::AccountId) -> T::Balance {
 		*self.balances.get(who).unwrap_or(&T::Balance::zero())
 	}
+
+	/// Get the reserved balance of an account `who`.
+	/// If the account has no reserved balance, we return zero.
+	pub fn reserved_balance(&self, who: &T::AccountId) -> T::Balance {
+		*self.reserved.get(who).unwrap_or(&T::Balance::zero())
+	}
+
+	/// Get all of the balance of an account `who` at once: its free, reserved and frozen balance.
+	pub fn account(&self, who: &T::AccountId) -> AccountData<T::Balance> {
+		AccountData {
+			free: self.balance(who),
+			reserved: self.reserved_balance(who),
+			frozen: self.frozen_balance(who),
+		}
+	}
+
+	/// Get all of the accounts which have a free or reserved balance, in order.
+	pub fn accounts(&self) -> impl Iterator<Item = &T::AccountId> {
+		let accounts = self.balances.iter().chain(self.reserved.iter()).map(|(who, _)| who);
+		accounts.collect::<BTreeSet<_>>().into_iter()
+	}
+
+	/// Iterate over every account which has a free balance, together with that balance, in order.
+	/// The reserved balance of the accounts is not included. See `reserved_balance`.
+	pub fn iter(&self) -> impl Iterator<Item = (&T::AccountId, &T::Balance)> {
+		self.balances.iter()
+	}
+
+	/// Get the number of accounts which have a free or reserved balance. Accounts which are reaped,
+	/// for example after transferring all of their balance, are no longer counted.
+	pub fn account_count(&self) -> usize {
+		self.accounts().count()
+	}
+
+	/// Get the amount which `spender` is allowed to transfer on behalf of `owner`.
+	/// If `owner` never approved `spender`, we return zero.
+	pub fn allowance(&self, owner: &T::AccountId, spender: &T::AccountId) -> T::Balance {
+		let key = (owner.clone(), spender.clone());
+		*self.allowances.get(&key).unwrap_or(&T::Balance::zero())
+	}
+
+	/// Check if `who` has approved any spender, or was approved by any owner.
+	pub fn has_allowances(&self, who: &T::AccountId) -> bool {
+		self.allowances.keys().any(|(owner, spender)| owner == who || spender == who)
+	}
+
+	/// Get the amount of free balance of `who` which is frozen by locks.
+	/// This is the largest lock placed on the account, or zero if there are no locks.
+	pub fn frozen_balance(&self, who: &T::AccountId) -> T::Balance {
+		let mut frozen = T::Balance::zero();
+		for (_, amount) in self.locks.get(who).into_iter().flatten() {
+			if *amount > frozen {
+				frozen = *amount;
+			}
+		}
+		frozen
+	}
+
+	/// Get the total issuance of balance across all accounts.
+	pub fn total_issuance(&self) -> T::Balance {
+		self.total_issuance
+	}
+
+	/// Create `amount` of new balance in the account `who`, increasing the total issuance.
+	/// This function will return an error if the account balance or total issuance overflows.
+	pub fn mint(&mut self, who: T::AccountId, amount: T::Balance) -> Result<(), BalancesError> {
+		let new_balance = self.balance(&who).checked_add(&amount).ok_or(BalancesError::Overflow)?;
+		let new_total_issuance =
+			self.total_issuance.checked_add(&amount).ok_or(BalancesError::Overflow)?;
+
+		self.insert_free(&who, new_balance);
+		self.total_issuance = new_total_issuance;
+
+		Ok(())
+	}
+
+	/// Create up to `amount` of new balance in the account `who` as a reward, increasing the total
+	/// issuance. Unlike `mint`, this never fails: if the total issuance would overflow, we only
+	/// create as much balance as fits. Like `deposit_creating`, if the account would be left below
+	/// the existential deposit, nothing is created. Returns the amount which was actually created.
+	pub fn reward(&mut self, who: T::AccountId, amount: T::Balance) -> T::Balance {
+		let new_total_issuance = saturating_add_balance(self.total_issuance, amount);
+		let rewarded = new_total_issuance - self.total_issuance;
+		if rewarded.is_zero() {
+			return rewarded;
+		}
+
+		// The balance of an account is part of the total issuance, so this cannot overflow.
+		let new_balance = saturating_add_balance(self.balance(&who), rewarded);
+		if new_balance < T::ExistentialDeposit::get() {
+			return T::Balance::zero();
+		}
+		self.insert_free(&who, new_balance);
+		self.total_issuance = new_total_issuance;
+
+		rewarded
+	}
+
+	/// Reward each of the `recipients` with their amount, like calling `reward` for each of them in
+	/// order, for example to pay out staking rewards. Each reward saturates on its own, so a reward
+	/// which would overflow the total issuance does not stop the rest. Returns the total amount
+	/// which was actually created.
+	pub fn distribute_rewards(&mut self, recipients: &[(T::AccountId, T::Balance)]) -> T::Balance {
+		recipients.iter().fold(T::Balance::zero(), |total, (who, amount)| {
+			saturating_add_balance(total, self.reward(who.clone(), *amount))
+		})
+	}
+
+	/// Deposit up to `amount` of new balance into the account `who`, creating the account if it
+	/// does not exist yet, and increasing the total issuance. This is the counterpart to `slash`.
+	/// Like `reward`, this never fails: if the total issuance would overflow, we only deposit as
+	/// much balance as fits. If the account would be left below the existential deposit, nothing
+	/// is deposited. Returns the amount which was actually deposited.
+	pub fn deposit_creating(&mut self, who: T::AccountId, amount: T::Balance) -> T::Balance {
+		self.reward(who, amount)
+	}
+
+	/// Destroy `amount` of balance from the account `who`, decreasing the total issuance.
+	/// If `who` is left with less than the existential deposit, their account is reaped.
+	/// This function will return an error if the account does not have enough funds.
+	pub fn burn(&mut self, who: T::AccountId, amount: T::Balance) -> Result<(), BalancesError> {
+		let new_balance = self
+			.balance(&who)
+			.checked_sub(&amount)
+			.ok_or(BalancesError::InsufficientBalance)?;
+		let new_total_issuance = self
+			.total_issuance
+			.checked_sub(&amount)
+			.ok_or(BalancesError::InsufficientBalance)?;
+
+		self.insert_free(&who, new_balance);
+		self.total_issuance = new_total_issuance;
+		self.reap_dust(&who);
+
+		Ok(())
+	}
+
+	/// Move `amount` from the free balance of `who` to their reserved balance.
+	/// This function will return an error if `who` does not have enough free balance.
+	pub fn reserve(&mut self, who: &T::AccountId, amount: T::Balance) -> Result<(), BalancesError> {
+		let new_balance = self
+			.balance(who)
+			.checked_sub(&amount)
+			.ok_or(BalancesError::InsufficientBalance)?;
+		let new_reserved =
+			self.reserved_balance(who).checked_add(&amount).ok_or(BalancesError::Overflow)?;
+
+		self.insert_free(who, new_balance);
+		self.insert_reserved(who, new_reserved);
+
+		Ok(())
+	}
+
+	/// Move up to `amount` from the reserved balance of `who` back to their free balance.
+	/// If `who` has less than `amount` reserved, we simply unreserve everything they have.
+	/// Returns the amount which was actually unreserved.
+	pub fn unreserve(&mut self, who: &T::AccountId, amount: T::Balance) -> T::Balance {
+		let reserved = self.reserved_balance(who);
+		let (actual, new_reserved) = match reserved.checked_sub(&amount) {
+			Some(new_reserved) => (amount, new_reserved),
+			None => (reserved, T::Balance::zero()),
+		};
+
+		// Free and reserved balance are both part of the total issuance, so this cannot overflow.
+		let new_balance = self.balance(who) + actual;
+
+		self.insert_reserved(who, new_reserved);
+		self.insert_free(who, new_balance);
+
+		actual
+	}
+
+	/// Move `amount` from the reserved balance of `from` to the free balance of `to`, for example to
+	/// pay out funds which were held on behalf of `to`. The total issuance is unchanged.
+	/// This function will return an error if `from` has less than `amount` reserved, or if `to`
+	/// would be left with less than the existential deposit.
+	pub fn repatriate_reserved(
+		&mut self,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		let new_reserved = self
+			.reserved_balance(from)
+			.checked_sub(&amount)
+			.ok_or(BalancesError::InsufficientBalance)?;
+		let new_to_balance =
+			self.balance(to).checked_add(&amount).ok_or(BalancesError::Overflow)?;
+		if new_to_balance < T::ExistentialDeposit::get() {
+			return Err(BalancesError::BelowExistentialDeposit);
+		}
+
+		self.insert_reserved(from, new_reserved);
+		self.insert_free(to, new_to_balance);
+
+		Ok(())
+	}
+
+	/// Destroy up to `amount` of balance from the account `who`, as a punishment, decreasing the
+	/// total issuance. The free balance is slashed first, even if it is frozen by a lock, and then
+	/// the reserved balance.
+	/// Unlike `burn`, this never fails: if `who` has less than `amount`, we simply slash everything
+	/// they have. Returns the amount which was actually slashed.
+	/// If `who` is left with a free balance below the existential deposit, their account is reaped,
+	/// and the dust is not counted as slashed.
+	pub fn slash(&mut self, who: T::AccountId, amount: T::Balance) -> T::Balance {
+		let free = self.balance(&who);
+		let new_free = saturating_sub_balance(free, amount);
+		let remaining = saturating_sub_balance(amount, free);
+		let reserved = self.reserved_balance(&who);
+		let new_reserved = saturating_sub_balance(reserved, remaining);
+
+		if new_free < free {
+			self.insert_free(&who, new_free);
+		}
+		if new_reserved < reserved {
+			self.insert_reserved(&who, new_reserved);
+		}
+		// Free and reserved balance are both part of the total issuance, so this cannot underflow.
+		let slashed = (free - new_free) + (reserved - new_reserved);
+		self.total_issuance = saturating_sub_balance(self.total_issuance, slashed);
+		self.reap_dust(&who);
+
+		slashed
+	}
+
+	// Reap the free balance of `who` if it is below the existential deposit, but not zero, and
+	// handle the dust which is left behind, so it does not silently vanish.
+	fn reap_dust(&mut self, who: &T::AccountId) {
+		let free = self.balance(who);
+		if free.is_zero() || free >= T::ExistentialDeposit::get() {
+			return;
+		}
+		self.balances.remove(who);
+		self.handle_dust(free);
+		self.deposit_event(Event::DustLost { who: who.clone(), amount: free });
+	}
+
+	// Transfer `amount` from `caller` to `to`, like `transfer`, without calling the `OnTransfer`
+	// hook.
+	fn do_transfer(
+		&mut self,
+		caller: T::AccountId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		if amount < T::MinimumTransfer::get() {
+			return Err(BalancesError::BelowMinimumTransfer);
+		}
+
+		let caller_balance = self.balance(&caller);
+		let to_balance = self.balance(&to);
+
+		let new_caller_balance =
+			caller_balance.checked_sub(&amount).ok_or(BalancesError::InsufficientBalance)?;
+		let new_to_balance = to_balance.checked_add(&amount).ok_or(BalancesError::Overflow)?;
+
+		if new_caller_balance < self.frozen_balance(&caller) {
+			return Err(BalancesError::LiquidityRestrictions);
+		}
+
+		let existential_deposit = T::ExistentialDeposit::get();
+		if !new_caller_balance.is_zero() && new_caller_balance < existential_deposit {
+			return Err(BalancesError::BelowExistentialDeposit);
+		}
+		if new_to_balance < existential_deposit {
+			return Err(BalancesError::BelowExistentialDeposit);
+		}
+
+		// A transfer to the caller themselves is checked like any other above, but both new balances
+		// are computed from the same old balance, so writing them would mint `amount` out of
+		// nothing. The funds do not go anywhere, so there is nothing to write.
+		if caller == to {
+			self.deposit_event(Event::Transferred { from: caller, to, amount });
+			return Ok(());
+		}
+
+		// A transfer only moves funds between accounts, so the total issuance is unchanged.
+		self.insert_free(&caller, new_caller_balance);
+		self.insert_free(&to, new_to_balance);
+
+		self.deposit_event(Event::Transferred { from: caller, to, amount });
+
+		Ok(())
+	}
+
+	/// Handle an `amount` of dust which was removed from a reaped account, by either burning it or
+	/// giving it to the treasury, depending on the `DustDestination`.
+	pub fn handle_dust(&mut self, amount: T::Balance) {
+		match T::DustDestination::get() {
+			// The dust was part of the total issuance, so this cannot underflow.
+			DustDestination::Burn => {
+				self.total_issuance = saturating_sub_balance(self.total_issuance, amount);
+			},
+			// The dust only moves to the treasury, so the total issuance is unchanged.
+			DustDestination::Treasury => {
+				let treasury = T::Treasury::get();
+				let new_treasury_balance = saturating_add_balance(self.balance(&treasury), amount);
+				self.insert_free(&treasury, new_treasury_balance);
+			},
+		}
+	}
+
+	/// Withdraw a `fee` from the free balance of `who`, and either burn it or give it to the
+	/// treasury, depending on the `FeeDestination`.
+	/// Like a transfer, the fee cannot be paid with balance which is frozen by a lock, and `who`
+	/// must be left with at least the existential deposit, or exactly zero.
+	/// This function will return an error if `who` cannot pay the fee.
+	pub fn withdraw_fee(
+		&mut self,
+		who: &T::AccountId,
+		fee: T::Balance,
+	) -> Result<(), BalancesError> {
+		if fee.is_zero() {
+			return Ok(());
+		}
+
+		let new_balance = self.balance(who).checked_sub(&fee).ok_or(BalancesError::CannotPayFee)?;
+		if new_balance < self.frozen_balance(who) {
+			return Err(BalancesError::CannotPayFee);
+		}
+		if !new_balance.is_zero() && new_balance < T::ExistentialDeposit::get() {
+			return Err(BalancesError::CannotPayFee);
+		}
+
+		self.insert_free(who, new_balance);
+		match T::FeeDestination::get() {
+			// The fee is part of the total issuance, so this cannot underflow.
+			FeeDestination::Burn => {
+				self.total_issuance = saturating_sub_balance(self.total_issuance, fee);
+			},
+			// The fee only moves between accounts, so the total issuance is unchanged. The fee is
+			// part of the total issuance, so this cannot overflow.
+			FeeDestination::Treasury => {
+				let treasury = T::Treasury::get();
+				let new_treasury_balance = saturating_add_balance(self.balance(&treasury), fee);
+				self.insert_free(&treasury, new_treasury_balance);
+			},
+		}
+
+		self.deposit_event(Event::FeePaid { who: who.clone(), fee });
+		Ok(())
+	}
+
+	/// Move the free and reserved balance of `from` to `to`, along with the locks placed on it, and
+	/// the allowances it approved or was approved for.
+	/// Any balance, locks or allowances of `to` are overwritten. The total issuance is unchanged,
+	/// since the balance only changes hands. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		let free = self.balances.remove(from).unwrap_or_else(T::Balance::zero);
+		self.insert_free(to, free);
+		let reserved = self.reserved.remove(from).unwrap_or_else(T::Balance::zero);
+		self.insert_reserved(to, reserved);
+		if let Some(locks) = self.locks.remove(from) {
+			self.locks.insert(to.clone(), locks);
+		}
+		let rename = |who: T::AccountId| if who == *from { to.clone() } else { who };
+		self.allowances = core::mem::take(&mut self.allowances)
+			.into_iter()
+			.map(|((owner, spender), amount)| ((rename(owner), rename(spender)), amount))
+			.collect();
+	}
+
+	/// Place a lock with `id` on `amount` of the free balance of `who`.
+	/// If a lock with the same `id` already exists, it is overwritten.
+	pub fn set_lock(&mut self, id: LockId, who: &T::AccountId, amount: T::Balance) {
+		let locks = self.locks.entry(who.clone()).or_default();
+		match locks.iter_mut().find(|(lock_id, _)| *lock_id == id) {
+			Some(lock) => lock.1 = amount,
+			None => locks.push((id, amount)),
+		}
+	}
+
+	/// Remove the lock with `id` from the account `who`, if it exists.
+	pub fn remove_lock(&mut self, id: LockId, who: &T::AccountId) {
+		if let Some(locks) = self.locks.get_mut(who) {
+			locks.retain(|(lock_id, _)| *lock_id != id);
+			if locks.is_empty() {
+				self.locks.remove(who);
+			}
+		}
+	}
+
+	// Store the free balance of `who`, removing the entry when it reaches zero, so empty accounts
+	// take no space in storage.
+	fn insert_free(&mut self, who: &T::AccountId, amount: T::Balance) {
+		if amount.is_zero() {
+			self.balances.remove(who);
+		} else {
+			self.balances.insert(who.clone(), amount);
+		}
+	}
+
+	// Store the reserved balance of `who`, removing the entry when it reaches zero.
+	fn insert_reserved(&mut self, who: &T::AccountId, amount: T::Balance) {
+		if amount.is_zero() {
+			self.reserved.remove(who);
+		} else {
+			self.reserved.insert(who.clone(), amount);
+		}
+	}
 }
 
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
 #[macros::call]
-impl<T: Config> Pallet<T> {
+impl<T: Config, S: StorageMap<T::AccountId, T::Balance> + Clone> Pallet<T, S> {
 	/// Transfer `amount` from one account to another.
 	/// This function verifies that `from` has at least `amount` balance to transfer,
 	/// and that no mathematical overflows occur.
+	/// Both accounts must be left with at least the existential deposit, except that `caller` may
+	/// be left with exactly zero, in which case their account is removed.
+	/// The `caller` cannot transfer balance which is frozen by a lock.
+	/// The `amount` must be at least the minimum transfer amount.
+	/// A transfer from `caller` to themselves is checked like any other, but leaves their balance
+	/// unchanged.
+	/// Once the transfer has succeeded, it is passed to the `OnTransfer` hook.
 	pub fn transfer(
 		&mut self,
 		caller: T::AccountId,
 		to: T::AccountId,
 		amount: T::Balance,
-	) -> crate::support::DispatchResult {
-		let caller_balance = self.balance(&caller);
-		let to_balance = self.balance(&to);
+	) -> Result<(), BalancesError> {
+		self.do_transfer(caller.clone(), to.clone(), amount)?;
+		T::OnTransfer::on_transfer(&caller, &to, amount);
+		Ok(())
+	}
 
-		let new_caller_balance = caller_balance.checked_sub(&amount).ok_or("Not enough funds.")?;
-		let new_to_balance = to_balance.checked_add(&amount).ok_or("Overflow")?;
+	/// Transfer balance from `caller` to many accounts at once.
+	/// Either all of the transfers succeed, or none of them do: we first check that `caller` can
+	/// afford the sum of all `transfers`, and if any single transfer still fails, we undo the ones
+	/// which were already made.
+	/// The weight of the call grows with the number of `transfers`.
+	#[weight(DEFAULT_WEIGHT + TRANSFER_BATCH_ITEM_WEIGHT * transfers.len() as Weight)]
+	pub fn transfer_batch(
+		&mut self,
+		caller: T::AccountId,
+		transfers: Vec<(T::AccountId, T::Balance)>,
+	) -> Result<(), BalancesError> {
+		let mut total = T::Balance::zero();
+		for (_, amount) in &transfers {
+			total = total.checked_add(amount).ok_or(BalancesError::Overflow)?;
+		}
+		if self.balance(&caller) < total {
+			return Err(BalancesError::InsufficientBalance);
+		}
 
-		self.balances.insert(caller, new_caller_balance);
-		self.balances.insert(to, new_to_balance);
+		let balances_before = self.balances.clone();
+		let events_before = self.events.len();
+		for (to, amount) in &transfers {
+			if let Err(e) = self.do_transfer(caller.clone(), to.clone(), *amount) {
+				self.balances = balances_before;
+				self.events.truncate(events_before);
+				return Err(e);
+			}
+		}
 
+		// The hook is only called once we know that none of the transfers will be undone.
+		for (to, amount) in &transfers {
+			T::OnTransfer::on_transfer(&caller, to, *amount);
+		}
+		Ok(())
+	}
+
+	/// Transfer `amount` from `source` to `dest`, without `source` making the call.
+	/// This is a privileged call, which the runtime only allows privileged origins, like the root
+	/// origin of the sudo key, to make. Otherwise, it is the same as `source` calling `transfer`, so
+	/// `source` must have enough balance which is not frozen.
+	#[privileged]
+	pub fn force_transfer(
+		&mut self,
+		source: T::AccountId,
+		dest: T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		self.transfer(source, dest, amount)
+	}
+
+	/// Transfer the entire free balance of `caller` to `to`, leaving `caller` with zero.
+	/// If `caller` has no free balance, this does nothing.
+	pub fn transfer_all(
+		&mut self,
+		caller: T::AccountId,
+		to: T::AccountId,
+	) -> Result<(), BalancesError> {
+		let amount = self.balance(&caller);
+		if amount.is_zero() {
+			return Ok(());
+		}
+		self.transfer(caller, to, amount)
+	}
+
+	/// Allow `spender` to transfer up to `amount` of the free balance of the `caller`, with
+	/// `transfer_from`. This replaces any allowance which `spender` already had, so an `amount` of
+	/// zero revokes it. Nothing is reserved, so the `caller` can still spend their balance as usual.
+	pub fn approve(
+		&mut self,
+		caller: T::AccountId,
+		spender: T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		let key = (caller.clone(), spender.clone());
+		if amount.is_zero() {
+			self.allowances.remove(&key);
+		} else {
+			self.allowances.insert(key, amount);
+		}
+		self.deposit_event(Event::Approved { owner: caller, spender, amount });
+		Ok(())
+	}
+
+	/// Transfer `amount` from `owner` to `to` on behalf of `owner`, spending from the allowance
+	/// which `owner` gave to the `caller` with `approve`. Otherwise, it is the same as `owner`
+	/// calling `transfer`.
+	/// This function will return an error if the allowance is less than `amount`, or if the
+	/// transfer fails, in which case the allowance is not spent.
+	pub fn transfer_from(
+		&mut self,
+		caller: T::AccountId,
+		owner: T::AccountId,
+		to: T::AccountId,
+		amount: T::Balance,
+	) -> Result<(), BalancesError> {
+		let key = (owner.clone(), caller);
+		let new_allowance = self
+			.allowance(&key.0, &key.1)
+			.checked_sub(&amount)
+			.ok_or(BalancesError::InsufficientAllowance)?;
+		self.transfer(owner, to, amount)?;
+
+		if new_allowance.is_zero() {
+			self.allowances.remove(&key);
+		} else {
+			self.allowances.insert(key, new_allowance);
+		}
 		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	struct TestConfig;
+	use super::{AccountData, BalancesError};
 
-	impl crate::system::Config for TestConfig {
-		type AccountId = String;
-		type BlockNumber = u32;
-		type Nonce = u32;
+	// Declare a test config `$name`. The test configs only differ in their balance type and the
+	// parameters given here, so everything else is shared.
+	macro_rules! test_config {
+		(
+			$name:ident {
+				Balance = $balance:ty,
+				ExistentialDeposit = $existential_deposit:ty,
+				MinimumTransfer = $minimum_transfer:ty,
+				FeeDestination = $fee_destination:ty,
+				DustDestination = $dust_destination:ty,
+				OnTransfer = $on_transfer:ty $(,)?
+			}
+		) => {
+			struct $name;
+
+			impl crate::system::Config for $name {
+				type AccountId = String;
+				type BlockNumber = u32;
+				type Nonce = u32;
+				type Hash = u64;
+				type RuntimeEvent = ();
+				type MaxBlockWeight = ();
+				type EventHistoryDepth = ();
+				type MaxExtrinsicsPerBlock = ();
+			}
+
+			impl super::Config for $name {
+				type Balance = $balance;
+				type ExistentialDeposit = $existential_deposit;
+				type MinimumTransfer = $minimum_transfer;
+				type FeeMultiplier = ();
+				type Treasury = Treasury;
+				type FeeDestination = $fee_destination;
+				type DustDestination = $dust_destination;
+				type OnTransfer = $on_transfer;
+			}
+		};
 	}
 
-	impl super::Config for TestConfig {
-		type Balance = u128;
+	crate::parameter_types! {
+		Treasury: String = "treasury".to_string();
+		ExistentialDeposit: u128 = 5;
+		MinimumTransfer: u128 = 3;
+		Burn: super::FeeDestination = super::FeeDestination::Burn;
+		BurnDust: super::DustDestination = super::DustDestination::Burn;
+	}
+
+	// Count the transfers made on the current thread, so each test sees only its own transfers.
+	struct CountTransfers;
+
+	thread_local! {
+		static TRANSFERS: core::cell::Cell<u32> = const { core::cell::Cell::new(0) };
+	}
+
+	impl super::OnTransfer<String, u128> for CountTransfers {
+		fn on_transfer(_from: &String, _to: &String, _amount: u128) {
+			TRANSFERS.with(|transfers| transfers.set(transfers.get() + 1));
+		}
+	}
+
+	fn transfer_count() -> u32 {
+		TRANSFERS.with(|transfers| transfers.get())
+	}
+
+	test_config!(TestConfig {
+		Balance = u128,
+		ExistentialDeposit = ExistentialDeposit,
+		MinimumTransfer = (),
+		FeeDestination = (),
+		DustDestination = (),
+		OnTransfer = CountTransfers,
+	});
+
+	// The same as `TestConfig`, except that transfers must be of at least 3.
+	test_config!(MinimumTransferConfig {
+		Balance = u128,
+		ExistentialDeposit = ExistentialDeposit,
+		MinimumTransfer = MinimumTransfer,
+		FeeDestination = (),
+		DustDestination = (),
+		OnTransfer = (),
+	});
+
+	// The same as `TestConfig`, except that balances are `u32`, so they overflow much sooner. There
+	// is no existential deposit.
+	test_config!(U32Config {
+		Balance = u32,
+		ExistentialDeposit = (),
+		MinimumTransfer = (),
+		FeeDestination = (),
+		DustDestination = (),
+		OnTransfer = (),
+	});
+
+	// The same as `U32Config`, except that balances are `u64`.
+	test_config!(U64Config {
+		Balance = u64,
+		ExistentialDeposit = (),
+		MinimumTransfer = (),
+		FeeDestination = (),
+		DustDestination = (),
+		OnTransfer = (),
+	});
+
+	// The same as `TestConfig`, except that fees and dust are burned.
+	test_config!(BurnConfig {
+		Balance = u128,
+		ExistentialDeposit = ExistentialDeposit,
+		MinimumTransfer = (),
+		FeeDestination = Burn,
+		DustDestination = BurnDust,
+		OnTransfer = (),
+	});
+
+	// A storage backend which keeps its entries in a vector sorted by key, to check that the pallet
+	// only relies on the `StorageMap` trait.
+	#[derive(Debug, Clone)]
+	struct VecMap<K, V>(Vec<(K, V)>);
+
+	impl<K, V> Default for VecMap<K, V> {
+		fn default() -> Self {
+			Self(Vec::new())
+		}
+	}
+
+	impl<K: Ord, V> crate::support::StorageMap<K, V> for VecMap<K, V> {
+		fn get(&self, key: &K) -> Option<&V> {
+			let index = self.0.binary_search_by(|(k, _)| k.cmp(key)).ok()?;
+			Some(&self.0[index].1)
+		}
+
+		fn insert(&mut self, key: K, value: V) -> Option<V> {
+			match self.0.binary_search_by(|(k, _)| k.cmp(&key)) {
+				Ok(index) => Some(core::mem::replace(&mut self.0[index].1, value)),
+				Err(index) => {
+					self.0.insert(index, (key, value));
+					None
+				},
+			}
+		}
+
+		fn remove(&mut self, key: &K) -> Option<V> {
+			let index = self.0.binary_search_by(|(k, _)| k.cmp(key)).ok()?;
+			Some(self.0.remove(index).1)
+		}
+
+		fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+		where
+			K: 'a,
+			V: 'a,
+		{
+			self.0.iter().map(|(k, v)| (k, v))
+		}
+	}
+
+	#[test]
+	fn storage_backend() {
+		let mut balances = super::Pallet::<TestConfig, VecMap<String, u128>>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 30), Ok(()));
+		assert_eq!(balances.reserve(&bob, 10), Ok(()));
+		assert_eq!(balances.balance(&alice), 70);
+		assert_eq!(balances.balance(&bob), 20);
+		assert_eq!(balances.reserved_balance(&bob), 10);
+
+		// A failed batch is still undone.
+		let transfers = vec![(charlie.clone(), 10), ("dave".to_string(), 1)];
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), transfers),
+			Err(BalancesError::BelowExistentialDeposit)
+		);
+		assert_eq!(balances.balance(&charlie), 0);
+
+		// Transferring everything removes the account.
+		assert_eq!(balances.transfer_all(alice.clone(), charlie.clone()), Ok(()));
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&bob, &charlie]);
+		assert_eq!(balances.total_issuance(), 100);
 	}
 
 	#[test]
@@ -90,7 +1014,7 @@ mod tests {
 
 		assert_eq!(
 			balances.transfer("alice".to_string(), "bob".to_string(), 51),
-			Err("Not enough funds.")
+			Err(BalancesError::InsufficientBalance)
 		);
 
 		balances.set_balance(&"alice".to_string(), 100);
@@ -100,7 +1024,851 @@ mod tests {
 
 		assert_eq!(
 			balances.transfer("alice".to_string(), "bob".to_string(), 51),
-			Err("Not enough funds.")
+			Err(BalancesError::InsufficientBalance)
+		);
+	}
+
+	#[test]
+	fn total_issuance() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(balances.total_issuance(), 0);
+		balances.set_balance(&"alice".to_string(), 100);
+		balances.set_balance(&"bob".to_string(), 50);
+		balances.set_balance(&"charlie".to_string(), 25);
+		assert_eq!(balances.total_issuance(), 175);
+
+		// Overwriting an existing balance replaces its contribution to the total issuance.
+		balances.set_balance(&"alice".to_string(), 10);
+		assert_eq!(balances.total_issuance(), 85);
+
+		// Transfers move funds around, but do not change the total issuance.
+		assert_eq!(balances.transfer("bob".to_string(), "alice".to_string(), 20), Ok(()));
+		assert_eq!(balances.total_issuance(), 85);
+	}
+
+	#[test]
+	fn try_set_balance() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Every overwrite replaces the old balance in the total issuance.
+		assert_eq!(balances.try_set_balance(&alice, 100), Ok(()));
+		assert_eq!(balances.try_set_balance(&alice, 30), Ok(()));
+		assert_eq!(balances.try_set_balance(&bob, 50), Ok(()));
+		assert_eq!(balances.try_set_balance(&alice, 70), Ok(()));
+		assert_eq!(balances.total_issuance(), 120);
+
+		// A balance which would overflow the total issuance is rejected, and changes nothing.
+		assert_eq!(balances.try_set_balance(&bob, u128::MAX), Err(BalancesError::Overflow));
+		assert_eq!(balances.balance(&bob), 50);
+		assert_eq!(balances.total_issuance(), 120);
+
+		// The total issuance can reach exactly the maximum balance.
+		assert_eq!(balances.try_set_balance(&alice, u128::MAX - 50), Ok(()));
+		assert_eq!(balances.total_issuance(), u128::MAX);
+		assert_eq!(balances.try_set_balance(&alice, 0), Ok(()));
+		assert_eq!(balances.total_issuance(), 50);
+		// Setting a balance to zero reaps the account.
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&bob]);
+	}
+
+	#[test]
+	fn mint_and_burn() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+
+		// Minting into an account with no balance creates that balance.
+		assert_eq!(balances.mint("alice".to_string(), 100), Ok(()));
+		assert_eq!(balances.balance(&"alice".to_string()), 100);
+		assert_eq!(balances.total_issuance(), 100);
+
+		assert_eq!(balances.burn("alice".to_string(), 40), Ok(()));
+		assert_eq!(balances.balance(&"alice".to_string()), 60);
+		assert_eq!(balances.total_issuance(), 60);
+
+		// Burning from an account with no balance fails.
+		assert_eq!(balances.burn("bob".to_string(), 1), Err(BalancesError::InsufficientBalance));
+
+		// Burning more than the account has fails, and changes nothing.
+		assert_eq!(balances.burn("alice".to_string(), 61), Err(BalancesError::InsufficientBalance));
+		assert_eq!(balances.balance(&"alice".to_string()), 60);
+		assert_eq!(balances.total_issuance(), 60);
+
+		// Minting past the maximum balance fails, and changes nothing.
+		assert_eq!(balances.mint("alice".to_string(), u128::MAX), Err(BalancesError::Overflow));
+		assert_eq!(balances.balance(&"alice".to_string()), 60);
+		assert_eq!(balances.total_issuance(), 60);
+
+		// Minting nothing does not create an account, and burning everything reaps it.
+		assert_eq!(balances.mint("fred".to_string(), 0), Ok(()));
+		assert_eq!(balances.burn("alice".to_string(), 60), Ok(()));
+		assert_eq!(balances.account_count(), 0);
+		assert_eq!(balances.total_issuance(), 0);
+	}
+
+	#[test]
+	fn reserve_and_unreserve() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.reserve(&alice, 101), Err(BalancesError::InsufficientBalance));
+		assert_eq!(balances.reserve(&alice, 60), Ok(()));
+		assert_eq!(balances.balance(&alice), 40);
+		assert_eq!(balances.reserved_balance(&alice), 60);
+		// Reserved balance is still part of the total issuance.
+		assert_eq!(balances.total_issuance(), 100);
+
+		// Partially unreserve.
+		assert_eq!(balances.unreserve(&alice, 20), 20);
+		assert_eq!(balances.balance(&alice), 60);
+		assert_eq!(balances.reserved_balance(&alice), 40);
+
+		// Unreserving more than is reserved only unreserves what is there.
+		assert_eq!(balances.unreserve(&alice, 50), 40);
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.reserved_balance(&alice), 0);
+		assert_eq!(balances.total_issuance(), 100);
+		// No zero entry is left behind.
+		assert_eq!(balances.reserved.get(&alice), None);
+
+		// Reserving everything removes the free balance entry.
+		assert_eq!(balances.reserve(&alice, 100), Ok(()));
+		assert_eq!(balances.balances.get(&alice), None);
+		assert_eq!(balances.reserved_balance(&alice), 100);
+	}
+
+	#[test]
+	fn repatriate_reserved() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.reserve(&alice, 30), Ok(()));
+
+		// Bob would be left with less than the existential deposit.
+		assert_eq!(
+			balances.repatriate_reserved(&alice, &bob, 4),
+			Err(BalancesError::BelowExistentialDeposit)
+		);
+		assert_eq!(balances.repatriate_reserved(&alice, &bob, 20), Ok(()));
+		assert_eq!(balances.reserved_balance(&alice), 10);
+		assert_eq!(balances.balance(&alice), 70);
+		assert_eq!(balances.balance(&bob), 20);
+		assert_eq!(balances.total_issuance(), 100);
+
+		// Only reserved balance can be repatriated.
+		assert_eq!(
+			balances.repatriate_reserved(&alice, &bob, 11),
+			Err(BalancesError::InsufficientBalance)
+		);
+		assert_eq!(balances.reserved_balance(&alice), 10);
+
+		// Repatriating everything removes the reserved balance entry.
+		assert_eq!(balances.repatriate_reserved(&alice, &bob, 10), Ok(()));
+		assert_eq!(balances.reserved.get(&alice), None);
+		assert_eq!(balances.balance(&bob), 30);
+	}
+
+	#[test]
+	fn existential_deposit() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		balances.set_balance(&alice, 100);
+
+		// A transfer which would leave dust in the sender's account is rejected.
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 97),
+			Err(BalancesError::BelowExistentialDeposit)
+		);
+		// A transfer which would create a receiver below the existential deposit is rejected.
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 4),
+			Err(BalancesError::BelowExistentialDeposit)
+		);
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.balance(&bob), 0);
+
+		// Transferring everything out of an account reaps it.
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 100), Ok(()));
+		assert!(!balances.balances.contains_key(&alice));
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&bob), 100);
+		assert_eq!(balances.total_issuance(), 100);
+	}
+
+	#[test]
+	fn balance_locks() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		balances.set_balance(&alice, 100);
+		balances.set_lock(*b"staking ", &alice, 30);
+		balances.set_lock(*b"vesting ", &alice, 60);
+		// Locks do not stack, only the largest lock applies.
+		assert_eq!(balances.frozen_balance(&alice), 60);
+
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 41),
+			Err(BalancesError::LiquidityRestrictions)
+		);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 20), Ok(()));
+		assert_eq!(balances.balance(&alice), 80);
+
+		// Setting a lock with the same id overwrites it.
+		balances.set_lock(*b"vesting ", &alice, 10);
+		assert_eq!(balances.frozen_balance(&alice), 30);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 50), Ok(()));
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 1),
+			Err(BalancesError::LiquidityRestrictions)
+		);
+
+		balances.remove_lock(*b"staking ", &alice);
+		assert_eq!(balances.frozen_balance(&alice), 10);
+		balances.remove_lock(*b"vesting ", &alice);
+		assert_eq!(balances.frozen_balance(&alice), 0);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 30), Ok(()));
+	}
+
+	#[test]
+	fn transfer_batch() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), vec![(bob.clone(), 60), (charlie.clone(), 41)]),
+			Err(BalancesError::InsufficientBalance)
+		);
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), vec![(bob.clone(), 60), (charlie.clone(), 40)]),
+			Ok(())
+		);
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&bob), 60);
+		assert_eq!(balances.balance(&charlie), 40);
+
+		// If a later transfer fails, the earlier ones are undone.
+		// We write storage directly, since `set_balance` would overflow the total issuance.
+		balances.balances.insert(charlie.clone(), u128::MAX);
+		assert_eq!(
+			balances.transfer_batch(bob.clone(), vec![(alice.clone(), 10), (charlie.clone(), 10)]),
+			Err(BalancesError::Overflow)
+		);
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&bob), 60);
+		assert_eq!(balances.balance(&charlie), u128::MAX);
+		// Only the events of the first, successful batch are kept.
+		assert_eq!(balances.take_events().len(), 2);
+	}
+
+	#[test]
+	fn allowances() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		balances.set_balance(&alice, 100);
+
+		// Without an allowance, bob cannot spend the balance of alice.
+		assert_eq!(balances.allowance(&alice, &bob), 0);
+		assert_eq!(
+			balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 10),
+			Err(BalancesError::InsufficientAllowance)
+		);
+
+		assert_eq!(balances.approve(alice.clone(), bob.clone(), 50), Ok(()));
+		assert_eq!(balances.allowance(&alice, &bob), 50);
+		// The allowance is only for bob to spend from alice, and not the other way around.
+		assert_eq!(balances.allowance(&bob, &alice), 0);
+
+		// Spending part of the allowance decrements it.
+		assert_eq!(balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 20), Ok(()));
+		assert_eq!(balances.balance(&alice), 80);
+		assert_eq!(balances.balance(&charlie), 20);
+		assert_eq!(balances.balance(&bob), 0);
+		assert_eq!(balances.allowance(&alice, &bob), 30);
+
+		// Spending more than is left fails, and spends nothing.
+		assert_eq!(
+			balances.transfer_from(bob.clone(), alice.clone(), charlie.clone(), 31),
+			Err(BalancesError::InsufficientAllowance)
+		);
+		assert_eq!(balances.allowance(&alice, &bob), 30);
+
+		// The rest of the allowance can be spent, after which it is used up.
+		assert_eq!(balances.transfer_from(bob.clone(), alice.clone(), bob.clone(), 30), Ok(()));
+		assert_eq!(balances.balance(&bob), 30);
+		assert_eq!(balances.allowance(&alice, &bob), 0);
+		assert_eq!(
+			balances.transfer_from(bob.clone(), alice.clone(), bob.clone(), 1),
+			Err(BalancesError::InsufficientAllowance)
+		);
+	}
+
+	#[test]
+	fn allowance_needs_balance() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		balances.set_balance(&alice, 10);
+
+		// An allowance is not backed by any balance, so the owner must still be able to pay.
+		assert_eq!(balances.approve(alice.clone(), bob.clone(), 50), Ok(()));
+		assert_eq!(
+			balances.transfer_from(bob.clone(), alice.clone(), bob.clone(), 20),
+			Err(BalancesError::InsufficientBalance)
+		);
+		assert_eq!(balances.allowance(&alice, &bob), 50);
+		assert_eq!(balances.balance(&alice), 10);
+
+		// Approving a new amount replaces the allowance, and zero revokes it.
+		assert_eq!(balances.approve(alice.clone(), bob.clone(), 5), Ok(()));
+		assert_eq!(balances.allowance(&alice, &bob), 5);
+		assert_eq!(balances.approve(alice.clone(), bob.clone(), 0), Ok(()));
+		assert_eq!(balances.allowance(&alice, &bob), 0);
+		assert!(balances.allowances.is_empty());
+	}
+
+	#[test]
+	fn on_transfer_hook() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let before = transfer_count();
+
+		// Failed transfers do not call the hook.
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 10),
+			Err(BalancesError::InsufficientBalance)
+		);
+		assert_eq!(transfer_count(), before);
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 10), Ok(()));
+		assert_eq!(transfer_count(), before + 1);
+		assert_eq!(balances.force_transfer(alice.clone(), bob.clone(), 10), Ok(()));
+		assert_eq!(transfer_count(), before + 2);
+
+		// Each transfer in a batch calls the hook once.
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), vec![(bob.clone(), 10), (charlie.clone(), 10)]),
+			Ok(())
+		);
+		assert_eq!(transfer_count(), before + 4);
+
+		// A batch which is rolled back does not call the hook, even for its successful transfers.
+		balances.balances.insert(charlie.clone(), u128::MAX);
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), vec![(bob.clone(), 10), (charlie.clone(), 10)]),
+			Err(BalancesError::Overflow)
 		);
+		assert_eq!(transfer_count(), before + 4);
+	}
+
+	#[test]
+	fn transfer_batch_weight() {
+		use super::{Call, TRANSFER_BATCH_ITEM_WEIGHT};
+		use crate::support::{GetWeight, DEFAULT_WEIGHT};
+
+		let transfer = Call::<TestConfig>::transfer { to: "bob".to_string(), amount: 1 };
+		let batch = |n: usize| Call::<TestConfig>::transfer_batch {
+			transfers: vec![("bob".to_string(), 1); n],
+		};
+		assert_eq!(transfer.weight(), DEFAULT_WEIGHT);
+		assert_eq!(batch(0).weight(), DEFAULT_WEIGHT);
+		assert_eq!(batch(1).weight(), DEFAULT_WEIGHT + TRANSFER_BATCH_ITEM_WEIGHT);
+		// A batch of ten transfers pays for each of them on top of the base weight of the call.
+		assert_eq!(batch(10).weight() - transfer.weight(), 10 * TRANSFER_BATCH_ITEM_WEIGHT);
+	}
+
+	#[test]
+	fn transfer_all() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Nothing to transfer.
+		assert_eq!(balances.transfer_all(alice.clone(), bob.clone()), Ok(()));
+		assert_eq!(balances.balance(&bob), 0);
+
+		balances.set_balance(&alice, 100);
+		balances.set_balance(&bob, 10);
+		assert_eq!(balances.transfer_all(alice.clone(), bob.clone()), Ok(()));
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&bob), 110);
+	}
+
+	#[test]
+	fn force_transfer() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		// Only the balance of the source matters.
+		balances.set_balance(&bob, 100);
+		assert_eq!(balances.force_transfer(bob.clone(), charlie.clone(), 60), Ok(()));
+		assert_eq!(balances.balance(&bob), 40);
+		assert_eq!(balances.balance(&charlie), 60);
+		assert_eq!(
+			balances.force_transfer(bob, charlie, 60),
+			Err(BalancesError::InsufficientBalance)
+		);
+	}
+
+	#[test]
+	fn slash() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		balances.set_balance(&alice, 100);
+		balances.reserve(&alice, 30).unwrap();
+		balances.set_lock(*b"testlock", &alice, 70);
+
+		// The free balance is slashed first, even though it is locked.
+		assert_eq!(balances.slash(alice.clone(), 50), 50);
+		assert_eq!(balances.balance(&alice), 20);
+		assert_eq!(balances.reserved_balance(&alice), 30);
+		assert_eq!(balances.total_issuance(), 50);
+
+		// Then the reserved balance. We cannot slash more than alice has.
+		assert_eq!(balances.slash(alice.clone(), 60), 50);
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.reserved_balance(&alice), 0);
+		assert_eq!(balances.total_issuance(), 0);
+
+		// Slashing an account with nothing does nothing. Alice was slashed to zero, so neither of
+		// them has an account left.
+		assert_eq!(balances.slash(bob.clone(), 10), 0);
+		assert_eq!(balances.account_count(), 0);
+	}
+
+	#[test]
+	fn saturating_helpers() {
+		use super::{saturating_add_balance, saturating_sub_balance};
+
+		assert_eq!(saturating_add_balance(1u128, 2), 3);
+		assert_eq!(saturating_add_balance(u128::MAX - 1, 1), u128::MAX);
+		assert_eq!(saturating_add_balance(u128::MAX, 1), u128::MAX);
+		assert_eq!(saturating_add_balance(u128::MAX, u128::MAX), u128::MAX);
+
+		assert_eq!(saturating_sub_balance(3u128, 2), 1);
+		assert_eq!(saturating_sub_balance(1u128, 1), 0);
+		assert_eq!(saturating_sub_balance(0u128, 1), 0);
+		assert_eq!(saturating_sub_balance(1u128, u128::MAX), 0);
+	}
+
+	#[test]
+	fn reward() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(balances.reward(alice.clone(), 100), 100);
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.total_issuance(), 100);
+
+		// Rewards saturate at the maximum total issuance, instead of failing like `mint`.
+		assert_eq!(balances.reward(bob.clone(), u128::MAX), u128::MAX - 100);
+		assert_eq!(balances.balance(&bob), u128::MAX - 100);
+		assert_eq!(balances.total_issuance(), u128::MAX);
+		assert_eq!(balances.reward(alice.clone(), 1), 0);
+		assert_eq!(balances.balance(&alice), 100);
+
+		// A reward of zero does not create an account, and neither does a reward below the
+		// existential deposit.
+		let mut balances = super::Pallet::<TestConfig>::new();
+		assert_eq!(balances.reward(alice, 0), 0);
+		assert_eq!(balances.reward(bob, 4), 0);
+		assert_eq!(balances.accounts().count(), 0);
+		assert_eq!(balances.total_issuance(), 0);
+	}
+
+	#[test]
+	fn distribute_rewards() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		balances.set_balance(&alice, 100);
+		balances.set_balance(&bob, 50);
+
+		// Charlie has no account yet, and one is created for him.
+		let recipients = [(alice.clone(), 10), (bob.clone(), 20), (charlie.clone(), 30)];
+		assert_eq!(balances.distribute_rewards(&recipients), 60);
+		assert_eq!(balances.balance(&alice), 110);
+		assert_eq!(balances.balance(&bob), 70);
+		assert_eq!(balances.balance(&charlie), 30);
+		assert_eq!(balances.total_issuance(), 210);
+
+		// A reward below the existential deposit does not create an account for dave, but the
+		// same amount is still added to an account which exists.
+		let dave = "dave".to_string();
+		let recipients = [(dave.clone(), 4), (alice.clone(), 4)];
+		assert_eq!(balances.distribute_rewards(&recipients), 4);
+		assert_eq!(balances.balance(&dave), 0);
+		assert_eq!(balances.balance(&alice), 114);
+		assert_eq!(balances.account_count(), 3);
+		assert_eq!(balances.total_issuance(), 214);
+
+		// Once the total issuance is reached, the remaining rewards are not created.
+		let recipients = [(alice.clone(), u128::MAX - 224), (bob.clone(), 20), (charlie, 30)];
+		assert_eq!(balances.distribute_rewards(&recipients), u128::MAX - 214);
+		assert_eq!(balances.balance(&alice), u128::MAX - 110);
+		assert_eq!(balances.balance(&bob), 80);
+		assert_eq!(balances.total_issuance(), u128::MAX);
+		assert_eq!(balances.distribute_rewards(&[]), 0);
+	}
+
+	#[test]
+	fn deposit_creating() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// A deposit creates a new account.
+		assert_eq!(balances.deposit_creating(alice.clone(), 100), 100);
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.total_issuance(), 100);
+
+		// A deposit into an existing account adds to it, even if it is below the existential
+		// deposit on its own.
+		assert_eq!(balances.deposit_creating(alice.clone(), 1), 1);
+		assert_eq!(balances.balance(&alice), 101);
+		assert_eq!(balances.total_issuance(), 101);
+
+		// A new account is not created below the existential deposit.
+		assert_eq!(balances.deposit_creating(bob.clone(), 4), 0);
+		assert_eq!(balances.balance(&bob), 0);
+		assert_eq!(balances.account_count(), 1);
+		assert_eq!(balances.total_issuance(), 101);
+
+		// Deposits saturate at the maximum total issuance.
+		assert_eq!(balances.deposit_creating(bob.clone(), u128::MAX), u128::MAX - 101);
+		assert_eq!(balances.balance(&bob), u128::MAX - 101);
+		assert_eq!(balances.total_issuance(), u128::MAX);
+	}
+
+	#[test]
+	fn slash_saturates() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		// Slashing the maximum balance from an account with the maximum balance leaves zero.
+		balances.set_balance(&alice, u128::MAX);
+		balances.reserve(&alice, 1).unwrap();
+		assert_eq!(balances.slash(alice.clone(), u128::MAX), u128::MAX);
+		assert_eq!(balances.account(&alice), AccountData { free: 0, reserved: 0, frozen: 0 });
+		assert_eq!(balances.total_issuance(), 0);
+		// Nothing is left of the account.
+		assert_eq!(balances.account_count(), 0);
+	}
+
+	#[test]
+	fn account_count() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		assert_eq!(balances.account_count(), 0);
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.account_count(), 1);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 40), Ok(()));
+		assert_eq!(balances.account_count(), 2);
+
+		// Transferring everything reaps the account of alice.
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 60), Ok(()));
+		assert_eq!(balances.account_count(), 1);
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&bob]);
+
+		// Every other way of emptying an account reaps it too, and nothing creates an empty one.
+		let carol = "carol".to_string();
+		balances.set_balance(&carol, 5);
+		assert_eq!(balances.slash(carol.clone(), 5), 5);
+		let dave = "dave".to_string();
+		balances.set_balance(&dave, 5);
+		assert_eq!(balances.burn(dave.clone(), 5), Ok(()));
+		let eve = "eve".to_string();
+		balances.set_balance(&eve, 5);
+		balances.set_balance(&eve, 0);
+		assert_eq!(balances.mint("fred".to_string(), 0), Ok(()));
+		assert_eq!(balances.account_count(), 1);
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&bob]);
+	}
+
+	#[test]
+	fn iter() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		balances.set_balance(&charlie, 50);
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 30), Ok(()));
+		assert_eq!(
+			balances.iter().collect::<Vec<_>>(),
+			[(&alice, &70), (&bob, &30), (&charlie, &50)]
+		);
+
+		// Without any reserved balance, the free balances add up to the total issuance.
+		let total = balances.iter().map(|(_, balance)| balance).sum::<u128>();
+		assert_eq!(total, balances.total_issuance());
+
+		// Reserved balance is not part of the iteration, but still counts towards the issuance.
+		balances.reserve(&bob, 10).unwrap();
+		let total = balances.iter().map(|(_, balance)| balance).sum::<u128>();
+		assert_eq!(total + balances.reserved_balance(&bob), balances.total_issuance());
+	}
+
+	#[test]
+	fn u32_balance_overflow() {
+		let mut balances = super::Pallet::<U32Config>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Minting up to the maximum balance is fine, but not a single unit more.
+		assert_eq!(balances.mint(alice.clone(), u32::MAX - 10), Ok(()));
+		assert_eq!(balances.mint(alice.clone(), 11), Err(BalancesError::Overflow));
+		assert_eq!(balances.balance(&alice), u32::MAX - 10);
+
+		// The total issuance overflows as well, even when the account balance would not.
+		assert_eq!(balances.mint(bob.clone(), 10), Ok(()));
+		assert_eq!(balances.total_issuance(), u32::MAX);
+		let error: &'static str = balances.mint(bob.clone(), 1).unwrap_err().into();
+		assert_eq!(error, "Overflow");
+		assert_eq!(balances.balance(&bob), 10);
+
+		// Transfers only move balance around, so they still work at the maximum issuance.
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), u32::MAX - 10), Ok(()));
+		assert_eq!(balances.balance(&bob), u32::MAX);
+
+		// Rewards saturate instead.
+		assert_eq!(balances.reward(alice.clone(), 1), 0);
+		assert_eq!(balances.total_issuance(), u32::MAX);
+	}
+
+	#[test]
+	fn u64_balance_overflow() {
+		let mut balances = super::Pallet::<U64Config>::new();
+		let alice = "alice".to_string();
+
+		// Amounts which fit in a `u32` are far from overflowing a `u64`.
+		assert_eq!(balances.mint(alice.clone(), u32::MAX.into()), Ok(()));
+		assert_eq!(balances.mint(alice.clone(), u32::MAX.into()), Ok(()));
+		assert_eq!(balances.balance(&alice), 2 * u64::from(u32::MAX));
+
+		assert_eq!(balances.mint(alice.clone(), u64::MAX), Err(BalancesError::Overflow));
+		assert_eq!(balances.total_issuance(), 2 * u64::from(u32::MAX));
+	}
+
+	#[test]
+	fn account_data() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let zero = AccountData { free: 0, reserved: 0, frozen: 0 };
+		assert_eq!(balances.account(&alice), zero);
+
+		balances.set_balance(&alice, 100);
+		balances.reserve(&alice, 30).unwrap();
+		balances.set_lock(*b"testlock", &alice, 50);
+		assert_eq!(balances.account(&alice), AccountData { free: 70, reserved: 30, frozen: 50 });
+	}
+
+	#[test]
+	fn migrate_account() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		balances.set_balance(&alice, 100);
+		balances.reserve(&alice, 30).unwrap();
+		balances.set_lock(*b"testlock", &alice, 50);
+		let charlie = "charlie".to_string();
+		assert_eq!(balances.approve(alice.clone(), charlie.clone(), 10), Ok(()));
+		assert_eq!(balances.approve(charlie.clone(), alice.clone(), 20), Ok(()));
+
+		balances.migrate_account(&alice, &bob);
+		assert_eq!(balances.account(&alice), AccountData { free: 0, reserved: 0, frozen: 0 });
+		assert_eq!(balances.account(&bob), AccountData { free: 70, reserved: 30, frozen: 50 });
+		assert_eq!(balances.total_issuance(), 100);
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&bob]);
+		// The allowances move with the account, on either side.
+		assert!(!balances.has_allowances(&alice));
+		assert_eq!(balances.allowance(&bob, &charlie), 10);
+		assert_eq!(balances.allowance(&charlie, &bob), 20);
+	}
+
+	#[test]
+	fn error_messages() {
+		let message: &'static str = BalancesError::InsufficientBalance.into();
+		assert_eq!(message, "Not enough funds.");
+		assert_eq!(BalancesError::Overflow.to_string(), "Overflow");
+		let error = crate::support::DispatchError::from(BalancesError::CannotPayFee);
+		assert_eq!(error, crate::support::DispatchError::Other("cannot pay fee"));
+	}
+
+	#[test]
+	fn transfer_to_self() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		balances.set_balance(&alice, 100);
+
+		// Transferring the whole balance to yourself used to double it.
+		assert_eq!(balances.transfer(alice.clone(), alice.clone(), 100), Ok(()));
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.transfer(alice.clone(), alice.clone(), 40), Ok(()));
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.total_issuance(), 100);
+
+		// The caller must still have the funds they transfer.
+		assert_eq!(
+			balances.transfer(alice.clone(), alice.clone(), 101),
+			Err(BalancesError::InsufficientBalance)
+		);
+		assert_eq!(balances.balance(&alice), 100);
+
+		// The caller cannot transfer frozen balance to themselves, or leave themselves with dust.
+		balances.set_lock(*b"testlock", &alice, 70);
+		assert_eq!(
+			balances.transfer(alice.clone(), alice.clone(), 40),
+			Err(BalancesError::LiquidityRestrictions)
+		);
+		balances.remove_lock(*b"testlock", &alice);
+		assert_eq!(
+			balances.transfer(alice.clone(), alice.clone(), 97),
+			Err(BalancesError::BelowExistentialDeposit)
+		);
+		assert_eq!(balances.balance(&alice), 100);
+	}
+
+	#[test]
+	fn minimum_transfer() {
+		let mut balances = super::Pallet::<MinimumTransferConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		balances.set_balance(&alice, 100);
+		balances.set_balance(&bob, 10);
+
+		// Empty transfers are rejected too, since the minimum is positive.
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 0),
+			Err(BalancesError::BelowMinimumTransfer)
+		);
+		assert_eq!(
+			balances.transfer(alice.clone(), bob.clone(), 2),
+			Err(BalancesError::BelowMinimumTransfer)
+		);
+		assert_eq!(balances.balance(&alice), 100);
+		assert_eq!(balances.transfer(alice.clone(), bob.clone(), 3), Ok(()));
+		assert_eq!(balances.balance(&alice), 97);
+		assert_eq!(balances.balance(&bob), 13);
+
+		// Every transfer of a batch must be of at least the minimum.
+		assert_eq!(
+			balances.transfer_batch(alice.clone(), vec![(bob.clone(), 10), (bob.clone(), 1)]),
+			Err(BalancesError::BelowMinimumTransfer)
+		);
+		assert_eq!(balances.balance(&bob), 13);
+		let message: &'static str = BalancesError::BelowMinimumTransfer.into();
+		assert_eq!(message, "transfer below minimum");
+	}
+
+	#[test]
+	fn withdraw_fee() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let treasury = "treasury".to_string();
+
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.withdraw_fee(&alice, 10), Ok(()));
+		assert_eq!(balances.balance(&alice), 90);
+		assert_eq!(balances.balance(&treasury), 10);
+		assert_eq!(balances.total_issuance(), 100);
+
+		// The fee cannot be paid with more than the free balance, or with frozen balance, and must
+		// not leave the account below the existential deposit.
+		assert_eq!(balances.withdraw_fee(&alice, 91), Err(BalancesError::CannotPayFee));
+		assert_eq!(balances.withdraw_fee(&alice, 88), Err(BalancesError::CannotPayFee));
+		balances.set_lock(*b"staking ", &alice, 50);
+		assert_eq!(balances.withdraw_fee(&alice, 41), Err(BalancesError::CannotPayFee));
+		balances.remove_lock(*b"staking ", &alice);
+		assert_eq!(balances.balance(&alice), 90);
+
+		// Paying everything kills the account.
+		assert_eq!(balances.withdraw_fee(&alice, 90), Ok(()));
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&treasury), 100);
+		assert_eq!(balances.total_issuance(), 100);
+	}
+
+	#[test]
+	fn burned_fees() {
+		let mut balances = super::Pallet::<BurnConfig>::new();
+		let alice = "alice".to_string();
+		let treasury = "treasury".to_string();
+
+		// The fee is taken from alice like before, but it is not given to anyone.
+		balances.set_balance(&alice, 100);
+		assert_eq!(balances.withdraw_fee(&alice, 10), Ok(()));
+		assert_eq!(balances.balance(&alice), 90);
+		assert_eq!(balances.balance(&treasury), 0);
+		assert_eq!(balances.total_issuance(), 90);
+
+		// The same checks apply to fees which are burned.
+		assert_eq!(balances.withdraw_fee(&alice, 88), Err(BalancesError::CannotPayFee));
+		assert_eq!(balances.withdraw_fee(&alice, 90), Ok(()));
+		assert_eq!(balances.total_issuance(), 0);
+		assert_eq!(balances.account_count(), 0);
+	}
+
+	#[test]
+	fn dust_goes_to_treasury() {
+		let mut balances = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let treasury = "treasury".to_string();
+
+		// Slashing alice down to 3, below the existential deposit of 5, reaps her account. The dust
+		// is not counted as slashed, and moves to the treasury.
+		balances.set_balance(&alice, 20);
+		assert_eq!(balances.slash(alice.clone(), 17), 17);
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&treasury), 3);
+		assert_eq!(balances.total_issuance(), 3);
+		assert_eq!(balances.accounts().collect::<Vec<_>>(), vec![&treasury]);
+		assert!(matches!(
+			balances.take_events().as_slice(),
+			[super::Event::DustLost { who, amount: 3 }] if *who == alice
+		));
+
+		// Burning down to exactly the existential deposit keeps the account, but burning below it
+		// reaps the account in the same way.
+		balances.set_balance(&alice, 20);
+		assert_eq!(balances.burn(alice.clone(), 15), Ok(()));
+		assert_eq!(balances.balance(&alice), 5);
+		assert_eq!(balances.burn(alice.clone(), 1), Ok(()));
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&treasury), 7);
+		assert_eq!(balances.total_issuance(), 7);
+	}
+
+	#[test]
+	fn dust_is_burned() {
+		let mut balances = super::Pallet::<BurnConfig>::new();
+		let alice = "alice".to_string();
+
+		balances.set_balance(&alice, 20);
+		assert_eq!(balances.burn(alice.clone(), 18), Ok(()));
+		assert_eq!(balances.balance(&alice), 0);
+		assert_eq!(balances.balance(&"treasury".to_string()), 0);
+		assert_eq!(balances.total_issuance(), 0);
+		assert_eq!(balances.account_count(), 0);
 	}
 }
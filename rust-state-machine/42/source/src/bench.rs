@@ -0,0 +1,134 @@
+use crate::{balances, support, types, GenesisConfig, Runtime, RuntimeCall};
+use std::time::{Duration, Instant};
+
+/// The result of running a benchmark for some number of iterations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+	/// The name of what was benchmarked, like `"transfer"`.
+	pub name: &'static str,
+	/// The number of times it was run.
+	pub iterations: u32,
+	/// The total time taken by all of the iterations.
+	pub elapsed: Duration,
+}
+
+impl BenchResult {
+	/// The number of iterations run in each second, on average.
+	pub fn per_second(&self) -> f64 {
+		self.iterations as f64 / self.elapsed.as_secs_f64()
+	}
+}
+
+// One line of the report printed by `print_report`.
+impl core::fmt::Display for BenchResult {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(
+			f,
+			"{:<16} {:>8} iterations in {:>10.3?} ({:.0} per second)",
+			self.name,
+			self.iterations,
+			self.elapsed,
+			self.per_second()
+		)
+	}
+}
+
+// Run `f` for each of the `inputs`, which are prepared beforehand so they are not measured.
+fn measure<I>(name: &'static str, inputs: Vec<I>, mut f: impl FnMut(I)) -> BenchResult {
+	let iterations = inputs.len() as u32;
+	let start = Instant::now();
+	for input in inputs {
+		f(input);
+	}
+	BenchResult { name, iterations, elapsed: start.elapsed() }
+}
+
+// A runtime where alice has enough balance for any benchmark, and is the sudo key.
+fn bench_runtime() -> Runtime {
+	Runtime::from_genesis(GenesisConfig {
+		balances: vec![("alice".to_string(), types::Balance::MAX / 2)],
+		sudo_key: Some("alice".to_string()),
+		..Default::default()
+	})
+}
+
+impl Runtime {
+	/// Measure `n` transfers from one account to another, in the Balances Module.
+	pub fn bench_transfer(n: u32) -> BenchResult {
+		let mut runtime = bench_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let inputs = (0..n).map(|_| (alice.clone(), bob.clone())).collect();
+		measure("transfer", inputs, |(from, to)| {
+			runtime.balances.transfer(from, to, 1).expect("alice can pay every transfer");
+		})
+	}
+
+	/// Measure creating `n` proof of existence claims, each by a different account, so they do
+	/// not reach the claim limit.
+	pub fn bench_create_claim(n: u32) -> BenchResult {
+		let mut runtime = bench_runtime();
+		let inputs = (0..n)
+			.map(|i| {
+				let context = support::DispatchContext::signed(i.to_string(), 1);
+				(context, format!("claim {i}"))
+			})
+			.collect();
+		measure("create_claim", inputs, |(context, claim)| {
+			runtime
+				.proof_of_existence
+				.create_claim(context, claim, String::new())
+				.expect("every claim is new");
+		})
+	}
+
+	/// Measure executing `n` blocks, each with a single transfer. The blocks are authored on a
+	/// copy of the runtime beforehand, so only their execution is measured.
+	pub fn bench_execute_block(n: u32) -> BenchResult {
+		let mut runtime = bench_runtime();
+		let mut author = runtime.clone();
+		let alice = "alice".to_string();
+		let inputs = (0..n)
+			.map(|nonce| {
+				let call = RuntimeCall::balances(balances::Call::transfer {
+					to: "bob".to_string(),
+					amount: 1,
+				});
+				let extrinsic = support::Extrinsic::new_signed(alice.clone(), call, nonce);
+				let (block, _) = author.author_block(vec![extrinsic]);
+				block
+			})
+			.collect();
+		measure("execute_block", inputs, |block| {
+			runtime.execute_block(&block).expect("the block was authored on the same state");
+		})
+	}
+}
+
+/// Run all of the benchmarks for `n` iterations each.
+pub fn run(n: u32) -> Vec<BenchResult> {
+	vec![
+		Runtime::bench_transfer(n),
+		Runtime::bench_create_claim(n),
+		Runtime::bench_execute_block(n),
+	]
+}
+
+/// Print a report of the `results` of the benchmarks, one line per benchmark.
+pub fn print_report(results: &[BenchResult]) {
+	for result in results {
+		println!("{result}");
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn benchmarks_run() {
+		let results = super::run(3);
+		let names = results.iter().map(|result| result.name).collect::<Vec<_>>();
+		assert_eq!(names, ["transfer", "create_claim", "execute_block"]);
+		assert!(results.iter().all(|result| result.iterations == 3));
+		super::print_report(&results);
+	}
+}
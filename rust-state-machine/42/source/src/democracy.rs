@@ -0,0 +1,589 @@
+use crate::{
+	balances::{self, LockId},
+	support::{
+		write_snapshot_map, write_snapshot_value, Dispatch, DispatchContext, DispatchResult, Get,
+		PalletHooks,
+	},
+};
+use num::traits::{CheckedAdd, Zero};
+use std::collections::BTreeMap;
+
+/// The id of the lock which the Democracy Module places on the balance of conviction voters.
+pub const DEMOCRACY_ID: LockId = *b"democrac";
+
+/// The largest conviction a vote can have, which multiplies its weight six times.
+pub const MAX_CONVICTION: u8 = 6;
+
+/// The configuration trait for the Democracy Module.
+pub trait Config: balances::Config {
+	/// The overarching call type of the runtime, which can be proposed and voted on.
+	type RuntimeCall;
+	/// The number of aye votes a proposal needs before it is dispatched.
+	type VoteThreshold: Get<u32>;
+	/// The number of blocks the balance of a voter is locked for, per unit of conviction.
+	type LockPeriod: Get<Self::BlockNumber>;
+}
+
+/// The index of a proposal, which is used to vote on it.
+pub type ProposalIndex = u32;
+
+/// A call which was proposed by `proposer`, and the votes it has received so far.
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Proposal<AccountId, Call> {
+	/// The account which made the proposal, on whose behalf the call is dispatched.
+	pub proposer: AccountId,
+	/// The call which is dispatched if the proposal passes.
+	pub call: Call,
+	/// The number of aye votes.
+	pub ayes: u32,
+	/// The number of nay votes.
+	pub nays: u32,
+}
+
+/// A proposal of the runtime `T`.
+pub type ProposalOf<T> =
+	Proposal<<T as crate::system::Config>::AccountId, <T as Config>::RuntimeCall>;
+
+/// The events which can be emitted by the Democracy Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `proposer` made a new proposal at `index`.
+	Proposed { index: ProposalIndex, proposer: T::AccountId },
+	/// `who` voted on the proposal at `index`, with a vote which counts `conviction` times.
+	Voted { index: ProposalIndex, who: T::AccountId, aye: bool, conviction: u8 },
+	/// The balance of `who` which was locked by their conviction votes was unlocked.
+	Unlocked { who: T::AccountId },
+	/// The proposal at `index` passed, and its call was dispatched with the given `result`.
+	Executed { index: ProposalIndex, result: DispatchResult },
+}
+
+/// This is the Democracy Module.
+/// It allows any account to propose a call, which every account can vote on. At the end of each
+/// block, the proposals which reached the vote threshold are dispatched.
+///
+/// A vote can carry a conviction, from 1 to `MAX_CONVICTION`, which it counts as that many votes,
+/// in exchange for locking the free balance of the voter for that many `LockPeriod`s, using the
+/// locks of the Balances Module.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The index of the next proposal.
+	next_index: ProposalIndex,
+	/// A storage map from the index of a proposal to the proposal, for the proposals which have not
+	/// yet passed.
+	proposals: BTreeMap<ProposalIndex, Proposal<T::AccountId, T::RuntimeCall>>,
+	/// A storage map from a proposal and an account to its vote, so each account only votes once.
+	votes: BTreeMap<(ProposalIndex, T::AccountId), bool>,
+	/// A storage map from an account to the balance locked by its conviction votes, and the block
+	/// number at which it is unlocked.
+	locks: BTreeMap<T::AccountId, (T::Balance, T::BlockNumber)>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Democracy Module.
+	pub fn new() -> Self {
+		Self {
+			next_index: 0,
+			proposals: BTreeMap::new(),
+			votes: BTreeMap::new(),
+			locks: BTreeMap::new(),
+			events: Vec::new(),
+		}
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Democracy Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::RuntimeCall: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "democracy", "next_index", &self.next_index)?;
+		write_snapshot_map(f, "democracy", "proposals", &self.proposals)?;
+		write_snapshot_map(f, "democracy", "votes", &self.votes)?;
+		write_snapshot_map(f, "democracy", "locks", &self.locks)
+	}
+
+	/// Get the proposal at `index`, if it has not yet passed.
+	pub fn proposal(&self, index: ProposalIndex) -> Option<&ProposalOf<T>> {
+		self.proposals.get(&index)
+	}
+
+	/// Get the vote of `who` on the proposal at `index`, if they have voted.
+	pub fn vote_of(&self, index: ProposalIndex, who: &T::AccountId) -> Option<bool> {
+		self.votes.get(&(index, who.clone())).copied()
+	}
+
+	/// Get the balance of `who` which is locked by their conviction votes, and the block number at
+	/// which it is unlocked, if they have any.
+	pub fn lock_of(&self, who: &T::AccountId) -> Option<(T::Balance, T::BlockNumber)> {
+		self.locks.get(who).copied()
+	}
+
+	// Add the vote of `caller` on the proposal at `index` to its tally, counted `conviction` times.
+	fn add_vote(
+		&mut self,
+		caller: T::AccountId,
+		index: ProposalIndex,
+		aye: bool,
+		conviction: u8,
+	) -> DispatchResult {
+		let proposal = self.proposals.get_mut(&index).ok_or("proposal does not exist")?;
+		if self.votes.contains_key(&(index, caller.clone())) {
+			return Err("already voted on this proposal".into());
+		}
+
+		let tally = if aye { &mut proposal.ayes } else { &mut proposal.nays };
+		*tally = tally.checked_add(conviction.into()).ok_or("too many votes")?;
+		self.votes.insert((index, caller.clone()), aye);
+		self.deposit_event(Event::Voted { index, who: caller, aye, conviction });
+		Ok(())
+	}
+
+	// Remove the locks of every account whose conviction votes are unlocked at `block_number`.
+	fn unlock<R>(runtime: &mut R, block_number: T::BlockNumber)
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let democracy: &mut Self = runtime.as_mut();
+		let unlocked = democracy
+			.locks
+			.iter()
+			.filter(|(_, (_, until))| *until <= block_number)
+			.map(|(who, _)| who.clone())
+			.collect::<Vec<_>>();
+		for who in unlocked {
+			let democracy: &mut Self = runtime.as_mut();
+			democracy.locks.remove(&who);
+			democracy.deposit_event(Event::Unlocked { who: who.clone() });
+			let balances: &mut balances::Pallet<T> = runtime.as_mut();
+			balances.remove_lock(DEMOCRACY_ID, &who);
+		}
+	}
+
+	// Remove all of the proposals which have reached the vote threshold, along with their votes,
+	// returning them in order of their index.
+	fn take_passed(&mut self) -> Vec<(ProposalIndex, ProposalOf<T>)> {
+		let threshold = T::VoteThreshold::get();
+		let passed = self
+			.proposals
+			.iter()
+			.filter(|(_, proposal)| proposal.ayes >= threshold)
+			.map(|(index, _)| *index)
+			.collect::<Vec<_>>();
+		self.votes.retain(|(index, _), _| !passed.contains(index));
+		passed
+			.into_iter()
+			.filter_map(|index| self.proposals.remove(&index).map(|proposal| (index, proposal)))
+			.collect()
+	}
+}
+
+// Conviction votes lock balance in the Balances Module, so our calls need access to it. Our hooks,
+// which dispatch the proposals, are implemented by hand below.
+#[macros::call(custom_hooks, R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Propose that `call` is dispatched on behalf of the `caller`, once enough accounts vote for
+	/// it. The proposal gets the next proposal index, which is reported with a `Proposed` event.
+	pub fn propose(&mut self, caller: T::AccountId, call: Box<T::RuntimeCall>) -> DispatchResult {
+		let index = self.next_index;
+		self.next_index = index.checked_add(1).ok_or("too many proposals")?;
+		let proposal = Proposal { proposer: caller.clone(), call: *call, ayes: 0, nays: 0 };
+		self.proposals.insert(index, proposal);
+		self.deposit_event(Event::Proposed { index, proposer: caller });
+		Ok(())
+	}
+
+	/// Vote for the proposal at `index` if `aye`, or against it otherwise.
+	/// This function will return an error if the proposal does not exist, or if the `caller` has
+	/// already voted on it.
+	pub fn vote(
+		&mut self,
+		caller: T::AccountId,
+		index: ProposalIndex,
+		aye: bool,
+	) -> DispatchResult {
+		self.add_vote(caller, index, aye, 1)
+	}
+
+	/// Like `vote`, but the vote counts `conviction` times, from 1 to `MAX_CONVICTION`. In
+	/// exchange, the whole free balance of the `caller` is locked until `conviction` lock periods
+	/// after the block of the `context`. A lock which is already longer or larger is kept.
+	/// This function will return an error if the conviction is out of range, if the `caller` has
+	/// no balance to lock, or if the vote itself fails.
+	pub fn vote_with_conviction<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		index: ProposalIndex,
+		aye: bool,
+		conviction: u8,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		if !(1..=MAX_CONVICTION).contains(&conviction) {
+			return Err("invalid conviction".into());
+		}
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		let amount = balances.balance(&caller);
+		if amount.is_zero() {
+			return Err("no balance to lock".into());
+		}
+		let period = T::LockPeriod::get();
+		let until = (0..conviction)
+			.try_fold(block_number, |until, _| until.checked_add(&period))
+			.ok_or("lock period overflow")?;
+
+		let democracy: &mut Self = runtime.as_mut();
+		democracy.add_vote(caller.clone(), index, aye, conviction)?;
+		let lock = match democracy.locks.get(&caller) {
+			Some(&(locked, locked_until)) => {
+				(if locked > amount { locked } else { amount }, locked_until.max(until))
+			},
+			None => (amount, until),
+		};
+		democracy.locks.insert(caller.clone(), lock);
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.set_lock(DEMOCRACY_ID, &caller, lock.0);
+		Ok(())
+	}
+}
+
+// At the start of each block, we unlock the balance of the voters whose locks have expired, and at
+// the end of each block, we dispatch the proposals which reached the vote threshold. This needs
+// access to the whole runtime, so we implement `PalletHooks` by hand, instead of implementing
+// `Hooks` for the pallet.
+//
+// Note that the weight of these calls is not part of any extrinsic, so it is not charged to anyone.
+impl<T: Config, R> PalletHooks<Pallet<T>, T::BlockNumber> for R
+where
+	R: AsMut<Pallet<T>>
+		+ AsMut<balances::Pallet<T>>
+		+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+{
+	fn on_initialize(&mut self, block_number: T::BlockNumber) {
+		Pallet::unlock(self, block_number);
+	}
+
+	fn on_finalize(&mut self, block_number: T::BlockNumber) {
+		let democracy: &mut Pallet<T> = self.as_mut();
+		for (index, proposal) in democracy.take_passed() {
+			let context = DispatchContext::signed(proposal.proposer, block_number);
+			let result = self.dispatch(context, proposal.call);
+			let democracy: &mut Pallet<T> = self.as_mut();
+			democracy.deposit_event(Event::Executed { index, result });
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{
+			Dispatch, DispatchCall, DispatchContext, DispatchError, DispatchResult, PalletHooks,
+		},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the democracy pallet, and can
+	// dispatch `TestCall`s.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		democracy: super::Pallet<TestRuntime>,
+		// Every caller which successfully dispatched a `TestCall`.
+		dispatched: Vec<String>,
+	}
+
+	enum TestCall {
+		Succeed,
+		Fail,
+	}
+
+	struct VoteThreshold;
+	impl crate::support::Get<u32> for VoteThreshold {
+		fn get() -> u32 {
+			2
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ();
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type RuntimeCall = TestCall;
+		type VoteThreshold = VoteThreshold;
+		type LockPeriod = LockPeriod;
+	}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	struct LockPeriod;
+	impl crate::support::Get<u32> for LockPeriod {
+		fn get() -> u32 {
+			10
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.democracy
+		}
+	}
+
+	impl Dispatch for TestRuntime {
+		type Caller = String;
+		type BlockNumber = u32;
+		type Call = TestCall;
+
+		fn dispatch(
+			&mut self,
+			context: DispatchContext<String, u32>,
+			call: TestCall,
+		) -> DispatchResult {
+			match call {
+				TestCall::Succeed => {
+					self.dispatched.push(context.origin.ensure_signed()?);
+					Ok(())
+				},
+				TestCall::Fail => Err("test call failed".into()),
+			}
+		}
+	}
+
+	fn new_runtime() -> TestRuntime {
+		TestRuntime {
+			balances: balances::Pallet::new(),
+			democracy: super::Pallet::new(),
+			dispatched: Vec::new(),
+		}
+	}
+
+	fn propose(call: TestCall) -> Call<TestRuntime> {
+		Call::propose { call: Box::new(call) }
+	}
+
+	fn finalize(runtime: &mut TestRuntime) {
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_finalize(runtime, 1);
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	#[test]
+	fn proposal_passes() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Succeed)), Ok(()));
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: true }),
+			Ok(())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&charlie), Call::vote { index: 0, aye: false }),
+			Ok(())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::vote { index: 0, aye: true }),
+			Ok(())
+		);
+		assert_eq!(runtime.democracy.vote_of(0, &charlie), Some(false));
+
+		// The proposal is only dispatched at the end of the block, on behalf of the proposer.
+		assert!(runtime.dispatched.is_empty());
+		finalize(&mut runtime);
+		assert_eq!(runtime.dispatched, vec![alice.clone()]);
+		assert!(runtime.democracy.proposal(0).is_none());
+		assert_eq!(runtime.democracy.vote_of(0, &charlie), None);
+
+		// A proposal which passed can no longer be voted on, and is not dispatched again.
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: true }),
+			Err("proposal does not exist".into())
+		);
+		finalize(&mut runtime);
+		assert_eq!(runtime.dispatched, vec![alice]);
+
+		let events = runtime.democracy.take_events();
+		assert!(matches!(events.last(), Some(Event::Executed { index: 0, result: Ok(()) })));
+	}
+
+	#[test]
+	fn proposal_below_threshold_is_not_dispatched() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Succeed)), Ok(()));
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: true }),
+			Ok(())
+		);
+		// Nobody can vote twice, even to change their vote.
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: true }),
+			Err("already voted on this proposal".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: false }),
+			Err("already voted on this proposal".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 1, aye: true }),
+			Err("proposal does not exist".into())
+		);
+
+		finalize(&mut runtime);
+		assert!(runtime.dispatched.is_empty());
+		let proposal = runtime.democracy.proposal(0).expect("proposal is still pending");
+		assert_eq!((proposal.ayes, proposal.nays), (1, 0));
+	}
+
+	#[test]
+	fn failed_proposal_is_removed() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Fail)), Ok(()));
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::vote { index: 0, aye: true }),
+			Ok(())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vote { index: 0, aye: true }),
+			Ok(())
+		);
+
+		// The call fails when it is dispatched, but the proposal has still passed.
+		finalize(&mut runtime);
+		assert!(runtime.democracy.proposal(0).is_none());
+		let events = runtime.democracy.take_events();
+		assert!(matches!(
+			events.last(),
+			Some(Event::Executed {
+				index: 0,
+				result: Err(DispatchError::Other("test call failed"))
+			})
+		));
+	}
+
+	#[test]
+	fn conviction_votes() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 50);
+		let vote = |index, aye, conviction| Call::vote_with_conviction { index, aye, conviction };
+
+		// A vote with a conviction of 1x counts once, while one with 6x counts six times.
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Succeed)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context(&alice), vote(0, false, 1)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context(&bob), vote(0, true, 6)), Ok(()));
+		let proposal = runtime.democracy.proposal(0).expect("proposal is pending");
+		assert_eq!((proposal.ayes, proposal.nays), (6, 1));
+		assert!(matches!(
+			runtime.democracy.take_events().last(),
+			Some(Event::Voted { index: 0, aye: true, conviction: 6, .. })
+		));
+
+		// In exchange, the whole balance of each voter is locked for a lock period per conviction.
+		assert_eq!(runtime.democracy.lock_of(&alice), Some((100, 11)));
+		assert_eq!(runtime.democracy.lock_of(&bob), Some((50, 61)));
+		assert_eq!(runtime.balances.frozen_balance(&alice), 100);
+		assert_eq!(runtime.balances.frozen_balance(&bob), 50);
+
+		// The lock of each voter is released once its block is reached.
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_initialize(&mut runtime, 11);
+		assert_eq!(runtime.democracy.lock_of(&alice), None);
+		assert_eq!(runtime.balances.frozen_balance(&alice), 0);
+		assert_eq!(runtime.balances.frozen_balance(&bob), 50);
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_initialize(&mut runtime, 61);
+		assert_eq!(runtime.democracy.lock_of(&bob), None);
+		assert_eq!(runtime.balances.frozen_balance(&bob), 0);
+	}
+
+	#[test]
+	fn conviction_vote_checks() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		let vote = |index, conviction| Call::vote_with_conviction { index, aye: true, conviction };
+
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Succeed)), Ok(()));
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), vote(0, 0)),
+			Err("invalid conviction".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), vote(0, 7)),
+			Err("invalid conviction".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), vote(0, 1)),
+			Err("no balance to lock".into())
+		);
+		// A vote which fails does not lock anything.
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), vote(1, 1)),
+			Err("proposal does not exist".into())
+		);
+		assert_eq!(runtime.democracy.lock_of(&alice), None);
+
+		// A shorter lock does not shorten an earlier, longer one.
+		assert_eq!(runtime.dispatch_call(context(&alice), propose(TestCall::Succeed)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context(&alice), vote(0, 3)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context(&alice), vote(1, 1)), Ok(()));
+		assert_eq!(runtime.democracy.lock_of(&alice), Some((100, 31)));
+	}
+}
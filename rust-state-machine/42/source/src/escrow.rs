@@ -0,0 +1,449 @@
+use crate::{
+	balances,
+	support::{
+		write_snapshot_map, write_snapshot_value, DispatchContext, DispatchResult, Get, Hooks,
+	},
+};
+use num::traits::CheckedAdd;
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Escrow Module.
+pub trait Config: balances::Config {
+	/// The number of blocks after the unlock block of an escrow during which its beneficiary can
+	/// claim it. After that, the escrow expires, and only its sender can take the funds back.
+	type ClaimPeriod: Get<Self::BlockNumber>;
+}
+
+/// The identifier of an escrow. Escrows are numbered in the order they are locked.
+pub type EscrowId = u32;
+
+/// Funds which were locked by `sender` for `beneficiary`.
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Escrow<AccountId, Balance, BlockNumber> {
+	/// The account which locked the funds, and can refund them once the escrow expires.
+	pub sender: AccountId,
+	/// The account which can claim the funds, from the unlock block until the escrow expires.
+	pub beneficiary: AccountId,
+	/// The amount of balance which is reserved from the sender.
+	pub amount: Balance,
+	/// The first block in which the beneficiary can claim the funds.
+	pub unlock_block: BlockNumber,
+}
+
+/// An `Escrow` with the types of a given config.
+pub type EscrowOf<T> = Escrow<
+	<T as crate::system::Config>::AccountId,
+	<T as balances::Config>::Balance,
+	<T as crate::system::Config>::BlockNumber,
+>;
+
+/// The events which can be emitted by the Escrow Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `sender` locked `amount` for `beneficiary` in the escrow `id`, until `unlock_block`.
+	Locked {
+		id: EscrowId,
+		sender: T::AccountId,
+		beneficiary: T::AccountId,
+		amount: T::Balance,
+		unlock_block: T::BlockNumber,
+	},
+	/// `beneficiary` claimed the `amount` locked in the escrow `id`.
+	Claimed { id: EscrowId, beneficiary: T::AccountId, amount: T::Balance },
+	/// `sender` took back the `amount` locked in the expired escrow `id`.
+	Refunded { id: EscrowId, sender: T::AccountId, amount: T::Balance },
+}
+
+/// This is the Escrow Module.
+/// It allows an account to lock some of its balance for another account, which can only claim it
+/// once a given block is reached. If the beneficiary does not claim it in time, the sender can
+/// take it back.
+///
+/// Locked funds are reserved in the Balances Module, so like the Staking Module, the calls of this
+/// pallet are given access to the whole runtime.
+#[derive(Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The id of the next escrow to be locked.
+	next_id: EscrowId,
+	/// A storage map from an escrow id to the escrow, for every escrow which is still locked.
+	escrows: BTreeMap<EscrowId, EscrowOf<T>>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+// We cannot derive `Debug`, since that would only require `T` to implement it, and not our escrows.
+impl<T: Config> core::fmt::Debug for Pallet<T>
+where
+	EscrowOf<T>: core::fmt::Debug,
+	Event<T>: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("Pallet")
+			.field("next_id", &self.next_id)
+			.field("escrows", &self.escrows)
+			.field("events", &self.events)
+			.finish()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Escrow Module.
+	pub fn new() -> Self {
+		Self { next_id: 0, escrows: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Escrow Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "escrow", "next_id", &self.next_id)?;
+		write_snapshot_map(f, "escrow", "escrows", &self.escrows)
+	}
+
+	/// Get the escrow with the given `id`, if it is still locked.
+	pub fn escrow(&self, id: EscrowId) -> Option<&EscrowOf<T>> {
+		self.escrows.get(&id)
+	}
+
+	/// Check if `who` is the sender or the beneficiary of any escrow.
+	pub fn has_escrows(&self, who: &T::AccountId) -> bool {
+		self.escrows
+			.values()
+			.any(|escrow| escrow.sender == *who || escrow.beneficiary == *who)
+	}
+
+	/// Make `to` the beneficiary of every escrow whose beneficiary is `from`, so it can still claim
+	/// them. The escrows sent by `from` hold its reserved balance, so they are not moved. See
+	/// `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		let escrows = self.escrows.values_mut().filter(|escrow| escrow.beneficiary == *from);
+		for escrow in escrows {
+			escrow.beneficiary = to.clone();
+		}
+	}
+
+	/// Check if the escrow with the given `id` has expired at block number `now`, so it can no
+	/// longer be claimed. Escrows which do not exist are never expired.
+	pub fn is_expired(&self, id: EscrowId, now: T::BlockNumber) -> bool {
+		match self.escrow(id) {
+			// If the expiry block overflows, the escrow can never expire.
+			Some(escrow) => escrow
+				.unlock_block
+				.checked_add(&T::ClaimPeriod::get())
+				.is_some_and(|expiry| now >= expiry),
+			None => false,
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Locked funds are reserved in the Balances Module, so our calls need access to it.
+#[macros::call(R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Lock `amount` of the free balance of the `caller` for `beneficiary`, by reserving it in the
+	/// Balances Module. The beneficiary can claim it from `unlock_block` on.
+	/// This function will return an error if the caller does not have enough free balance.
+	pub fn lock<R>(
+		runtime: &mut R,
+		caller: T::AccountId,
+		beneficiary: T::AccountId,
+		amount: T::Balance,
+		unlock_block: T::BlockNumber,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let escrow: &mut Self = runtime.as_mut();
+		let id = escrow.next_id;
+		let next_id = id.checked_add(1).ok_or("too many escrows")?;
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.reserve(&caller, amount)?;
+
+		let escrow: &mut Self = runtime.as_mut();
+		escrow.next_id = next_id;
+		let locked = Escrow {
+			sender: caller.clone(),
+			beneficiary: beneficiary.clone(),
+			amount,
+			unlock_block,
+		};
+		escrow.escrows.insert(id, locked);
+		escrow.deposit_event(Event::Locked {
+			id,
+			sender: caller,
+			beneficiary,
+			amount,
+			unlock_block,
+		});
+		Ok(())
+	}
+
+	/// Claim the funds locked in the escrow `id`, which are moved from the reserved balance of the
+	/// sender to the free balance of the caller.
+	/// This function will return an error if the escrow does not exist, if the caller is not its
+	/// beneficiary, or if the block of the `context` is before the unlock block, or after the
+	/// escrow has expired.
+	pub fn claim<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		id: EscrowId,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		let escrow: &mut Self = runtime.as_mut();
+		let locked = escrow.escrow(id).ok_or("escrow does not exist")?.clone();
+		if caller != locked.beneficiary {
+			return Err("caller is not the beneficiary".into());
+		}
+		if block_number < locked.unlock_block {
+			return Err("escrow is still locked".into());
+		}
+		if escrow.is_expired(id, block_number) {
+			return Err("escrow has expired".into());
+		}
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.repatriate_reserved(&locked.sender, &locked.beneficiary, locked.amount)?;
+
+		let escrow: &mut Self = runtime.as_mut();
+		escrow.escrows.remove(&id);
+		let Escrow { beneficiary, amount, .. } = locked;
+		escrow.deposit_event(Event::Claimed { id, beneficiary, amount });
+		Ok(())
+	}
+
+	/// Take back the funds locked in the escrow `id`, which are unreserved for the caller.
+	/// This function will return an error if the escrow does not exist, if the caller is not its
+	/// sender, or if the escrow has not expired by the block of the `context`.
+	pub fn refund<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		id: EscrowId,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		let escrow: &mut Self = runtime.as_mut();
+		let locked = escrow.escrow(id).ok_or("escrow does not exist")?.clone();
+		if caller != locked.sender {
+			return Err("caller is not the sender".into());
+		}
+		if !escrow.is_expired(id, block_number) {
+			return Err("escrow has not expired".into());
+		}
+		escrow.escrows.remove(&id);
+
+		// The locked funds are always reserved, so all of them are unreserved.
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.unreserve(&locked.sender, locked.amount);
+
+		let escrow: &mut Self = runtime.as_mut();
+		let Escrow { sender, amount, .. } = locked;
+		escrow.deposit_event(Event::Refunded { id, sender, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the escrow pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		escrow: super::Pallet<TestRuntime>,
+	}
+
+	struct ExistentialDeposit;
+	impl crate::support::Get<u128> for ExistentialDeposit {
+		fn get() -> u128 {
+			1
+		}
+	}
+
+	struct ClaimPeriod;
+	impl crate::support::Get<u32> for ClaimPeriod {
+		fn get() -> u32 {
+			5
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ExistentialDeposit;
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type ClaimPeriod = ClaimPeriod;
+	}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.escrow
+		}
+	}
+
+	// The context of a call made by `caller` in the block `block_number`.
+	fn context(caller: &str, block_number: u32) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), block_number)
+	}
+
+	// A runtime where alice has locked 60 for bob in the escrow 0, until block 10.
+	fn new_runtime() -> TestRuntime {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), escrow: super::Pallet::new() };
+		runtime.balances.set_balance(&"alice".to_string(), 100);
+		let lock = Call::lock { beneficiary: "bob".to_string(), amount: 60, unlock_block: 10 };
+		assert_eq!(runtime.dispatch_call(context("alice", 1), lock), Ok(()));
+		runtime
+	}
+
+	#[test]
+	fn lock() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		assert_eq!(runtime.balances.balance(&alice), 40);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 60);
+		let escrow = runtime.escrow.escrow(0).unwrap();
+		assert_eq!(
+			(escrow.beneficiary.as_str(), escrow.amount, escrow.unlock_block),
+			("bob", 60, 10)
+		);
+
+		// Locking more than the free balance fails, and nothing changes.
+		let lock = Call::lock { beneficiary: "bob".to_string(), amount: 41, unlock_block: 10 };
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 1), lock),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.escrow.escrow(1), None);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 60);
+		assert_eq!(runtime.escrow.take_events().len(), 1);
+	}
+
+	#[test]
+	fn claim_after_unlock() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Only the beneficiary can claim the escrow.
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 10), Call::claim { id: 0 }),
+			Err("caller is not the beneficiary".into())
+		);
+		assert_eq!(runtime.dispatch_call(context("bob", 10), Call::claim { id: 0 }), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 40);
+		assert_eq!(runtime.balances.total_issuance(), 100);
+		assert_eq!(runtime.escrow.escrow(0), None);
+
+		// An escrow can only be claimed once.
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 11), Call::claim { id: 0 }),
+			Err("escrow does not exist".into())
+		);
+		let events = runtime.escrow.take_events();
+		assert!(matches!(
+			&events[..],
+			[Event::Locked { .. }, Event::Claimed { id: 0, amount: 60, .. }]
+		));
+	}
+
+	#[test]
+	fn premature_claim_is_rejected() {
+		let mut runtime = new_runtime();
+
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 9), Call::claim { id: 0 }),
+			Err("escrow is still locked".into())
+		);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 0);
+		assert_eq!(runtime.balances.reserved_balance(&"alice".to_string()), 60);
+
+		// Once the escrow expires, it can no longer be claimed.
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 15), Call::claim { id: 0 }),
+			Err("escrow has expired".into())
+		);
+	}
+
+	#[test]
+	fn refund_after_expiry() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		// The sender cannot take the funds back while the beneficiary can still claim them.
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 14), Call::refund { id: 0 }),
+			Err("escrow has not expired".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("bob", 15), Call::refund { id: 0 }),
+			Err("caller is not the sender".into())
+		);
+		assert_eq!(runtime.dispatch_call(context("alice", 15), Call::refund { id: 0 }), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.escrow.escrow(0), None);
+		assert_eq!(
+			runtime.dispatch_call(context("alice", 15), Call::refund { id: 0 }),
+			Err("escrow does not exist".into())
+		);
+	}
+}
@@ -0,0 +1,171 @@
+use crate::support::{write_snapshot_map, DispatchResult, Get};
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Identity Module.
+pub trait Config: crate::system::Config {
+	/// The type which represents the display name of an account, which is supplied by its owner.
+	/// Usually a string.
+	type Content: AsRef<[u8]> + Clone;
+	/// The maximum length of a display name, in bytes. Names are supplied by users, so we must
+	/// limit how much storage they can use.
+	type MaxNameLength: Get<u32>;
+}
+
+/// The events which can be emitted by the Identity Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `who` set their display name to `name`.
+	IdentitySet { who: T::AccountId, name: T::Content },
+	/// `who` cleared their display name.
+	IdentityCleared { who: T::AccountId },
+}
+
+/// This is the Identity Module.
+/// It allows each account to set a display name for itself, so others can recognize it.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an account to its display name.
+	identities: BTreeMap<T::AccountId, T::Content>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Identity Module.
+	pub fn new() -> Self {
+		Self { identities: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Identity Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Content: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "identity", "identities", &self.identities)
+	}
+
+	/// Get the display name of `who`, if they have set one.
+	pub fn identity_of(&self, who: &T::AccountId) -> Option<T::Content> {
+		self.identities.get(who).cloned()
+	}
+
+	/// Move the display name of `from` to `to`. Any name of `to` is overwritten. See
+	/// `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		if let Some(name) = self.identities.remove(from) {
+			self.identities.insert(to.clone(), name);
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Set the display name of the `caller` to `name`, replacing the name they had before, if any.
+	/// This function will return an error if the name is longer than the maximum length.
+	pub fn set_identity(&mut self, caller: T::AccountId, name: T::Content) -> DispatchResult {
+		let max_length = T::MaxNameLength::get();
+		if u32::try_from(name.as_ref().len()).map_or(true, |length| length > max_length) {
+			return Err("name is too long".into());
+		}
+		self.identities.insert(caller.clone(), name.clone());
+		self.deposit_event(Event::IdentitySet { who: caller, name });
+		Ok(())
+	}
+
+	/// Clear the display name of the `caller`.
+	/// This function will return an error if the caller has not set a display name.
+	pub fn clear_identity(&mut self, caller: T::AccountId) -> DispatchResult {
+		self.identities.remove(&caller).ok_or("no identity is set")?;
+		self.deposit_event(Event::IdentityCleared { who: caller });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Event;
+
+	struct TestConfig;
+
+	struct MaxNameLength;
+	impl crate::support::Get<u32> for MaxNameLength {
+		fn get() -> u32 {
+			5
+		}
+	}
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {
+		type Content = String;
+		type MaxNameLength = MaxNameLength;
+	}
+
+	#[test]
+	fn set_and_clear_identity() {
+		let mut identity = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// Nobody has an identity to start with.
+		assert_eq!(identity.identity_of(&alice), None);
+		assert_eq!(identity.clear_identity(alice.clone()), Err("no identity is set".into()));
+
+		assert_eq!(identity.set_identity(alice.clone(), "Alice".to_string()), Ok(()));
+		assert_eq!(identity.identity_of(&alice), Some("Alice".to_string()));
+		assert_eq!(identity.identity_of(&bob), None);
+
+		// Setting an identity again overwrites the old one.
+		assert_eq!(identity.set_identity(alice.clone(), "Al".to_string()), Ok(()));
+		assert_eq!(identity.identity_of(&alice), Some("Al".to_string()));
+
+		assert_eq!(identity.clear_identity(alice.clone()), Ok(()));
+		assert_eq!(identity.identity_of(&alice), None);
+		assert_eq!(identity.clear_identity(alice), Err("no identity is set".into()));
+
+		let events = identity.take_events();
+		assert_eq!(events.len(), 3);
+		assert!(matches!(&events[2], Event::IdentityCleared { who } if who == "alice"));
+	}
+
+	#[test]
+	fn name_length_is_limited() {
+		let mut identity = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		assert_eq!(identity.set_identity(alice.clone(), "Alice".to_string()), Ok(()));
+		assert_eq!(
+			identity.set_identity(alice.clone(), "Alice!".to_string()),
+			Err("name is too long".into())
+		);
+		// The old name is kept.
+		assert_eq!(identity.identity_of(&alice), Some("Alice".to_string()));
+	}
+}
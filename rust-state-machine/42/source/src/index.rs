@@ -0,0 +1,144 @@
+use crate::support::{write_snapshot_map, DispatchResult, Hooks};
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Index Module.
+pub trait Config: crate::system::Config {}
+
+/// The events which can be emitted by the Index Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// The `removed` entries of the history of `who` from before block `before` were pruned.
+	Pruned { who: T::AccountId, before: T::BlockNumber, removed: u32 },
+}
+
+/// This is the Index Module.
+/// It keeps the transaction history of every account: the block number and index in the block of
+/// every extrinsic the account made, in the order they were made. The runtime records each
+/// extrinsic once it is applied, with `support::OnExtrinsic`, whether its call succeeded or not.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an account to the block number and index of each of its extrinsics.
+	history: BTreeMap<T::AccountId, Vec<(T::BlockNumber, u32)>>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Index Module.
+	pub fn new() -> Self {
+		Self { history: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Index Module to a snapshot of the state, one line per storage value
+	/// or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "index", "history", &self.history)
+	}
+
+	/// Get the block number and index in the block of every extrinsic made by `who`, oldest first.
+	/// If the account never made an extrinsic, we return an empty history.
+	pub fn history(&self, who: &T::AccountId) -> &[(T::BlockNumber, u32)] {
+		self.history.get(who).map_or(&[], Vec::as_slice)
+	}
+
+	/// Move the transaction history of `from` to `to`. Any history of `to` is overwritten. See
+	/// `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		if let Some(history) = self.history.remove(from) {
+			self.history.insert(to.clone(), history);
+		}
+	}
+
+	/// Record that `who` made the extrinsic at `index` in the block `block_number`.
+	/// Extrinsics are applied in order, so this is always the newest entry of the history.
+	pub fn record(&mut self, who: T::AccountId, block_number: T::BlockNumber, index: u32) {
+		self.history.entry(who).or_default().push((block_number, index));
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Remove the entries of the history of the `caller` from before block `before`, so their
+	/// history does not grow forever. The extrinsic of this call is recorded once it is applied,
+	/// so it is never pruned by itself.
+	pub fn prune_history(
+		&mut self,
+		caller: T::AccountId,
+		before: T::BlockNumber,
+	) -> DispatchResult {
+		let history = self.history.entry(caller.clone()).or_default();
+		let len = history.len();
+		history.retain(|(block_number, _)| *block_number >= before);
+		let removed = (len - history.len()) as u32;
+		if history.is_empty() {
+			self.history.remove(&caller);
+		}
+		self.deposit_event(Event::Pruned { who: caller, before, removed });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Event;
+
+	struct TestConfig;
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {}
+
+	#[test]
+	fn prune_history() {
+		let mut index = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		index.record(alice.clone(), 1, 0);
+		index.record(bob.clone(), 1, 1);
+		index.record(alice.clone(), 2, 0);
+		index.record(alice.clone(), 3, 2);
+		assert_eq!(index.history(&alice), [(1, 0), (2, 0), (3, 2)]);
+		assert_eq!(index.history(&bob), [(1, 1)]);
+		assert_eq!(index.history(&"charlie".to_string()), []);
+
+		// Only the entries of the caller from before the given block are removed.
+		assert_eq!(index.prune_history(alice.clone(), 3), Ok(()));
+		assert_eq!(index.history(&alice), [(3, 2)]);
+		assert_eq!(index.history(&bob), [(1, 1)]);
+		assert!(matches!(index.take_events()[..], [Event::Pruned { before: 3, removed: 2, .. }]));
+
+		// An account whose whole history is pruned is removed from storage.
+		assert_eq!(index.prune_history(bob.clone(), 2), Ok(()));
+		assert_eq!(index.history(&bob), []);
+		assert!(!index.history.contains_key(&bob));
+	}
+}
@@ -0,0 +1,385 @@
+use crate::{
+	balances,
+	support::{write_snapshot_value, DispatchResult, Get, PalletHooks, Randomness},
+};
+use num::traits::{CheckedAdd, Zero};
+
+/// The configuration trait for the Lottery Module.
+pub trait Config: balances::Config {
+	/// The price of a ticket, which is paid into the pot by every entrant.
+	type TicketPrice: Get<Self::Balance>;
+	/// The number of blocks between draws. A winner is drawn at the end of every `DrawPeriod`
+	/// blocks.
+	type DrawPeriod: Get<Self::BlockNumber>;
+	/// The well known account which holds the funds of the pot.
+	type PotAccount: Get<Self::AccountId>;
+}
+
+/// The events which can be emitted by the Lottery Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `who` bought a ticket for the next draw.
+	Entered { who: T::AccountId },
+	/// `winner` was drawn, and won the whole pot of `amount`.
+	Won { winner: T::AccountId, amount: T::Balance },
+	/// Nobody won the draw, so the pot of `amount` rolls over to the next one.
+	RolledOver { amount: T::Balance },
+}
+
+/// This is the Lottery Module.
+/// It allows any account to buy a ticket for the next draw, by paying the ticket price into the
+/// pot. At the end of every draw period, one of the tickets is drawn at random, and its owner wins
+/// the whole pot. An account can buy more than one ticket, to improve its odds.
+///
+/// The winner is picked with the randomness of the runtime, which is NOT secure: see
+/// `system::Pallet::random`. The pot is the balance of the pot account in the Balances Module.
+#[derive(Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The owner of every ticket for the next draw, in the order they were bought.
+	participants: Vec<T::AccountId>,
+	/// The block number of the last draw, or zero if there has not been one.
+	last_draw: T::BlockNumber,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+// We cannot derive `Debug`, since that would only require `T` to implement it, and not our
+// storage.
+impl<T: Config> core::fmt::Debug for Pallet<T>
+where
+	T::AccountId: core::fmt::Debug,
+	T::BlockNumber: core::fmt::Debug,
+	Event<T>: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("Pallet")
+			.field("participants", &self.participants)
+			.field("last_draw", &self.last_draw)
+			.field("events", &self.events)
+			.finish()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Lottery Module.
+	pub fn new() -> Self {
+		Self { participants: Vec::new(), last_draw: T::BlockNumber::zero(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Lottery Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "lottery", "participants", &self.participants)?;
+		write_snapshot_value(f, "lottery", "last_draw", &self.last_draw)
+	}
+
+	/// Get the well known account which holds the funds of the pot.
+	pub fn account_id() -> T::AccountId {
+		T::PotAccount::get()
+	}
+
+	/// Get the owner of every ticket for the next draw, in the order they were bought.
+	pub fn participants(&self) -> &[T::AccountId] {
+		&self.participants
+	}
+
+	/// Give all of the tickets of `from` for the next draw to `to`. See
+	/// `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		for participant in self.participants.iter_mut().filter(|participant| *participant == from) {
+			*participant = to.clone();
+		}
+	}
+
+	/// Get the amount of balance which the winner of the next draw receives, which is the
+	/// balance of the pot account in `balances`.
+	pub fn pot(balances: &balances::Pallet<T>) -> T::Balance {
+		balances.balance(&Self::account_id())
+	}
+}
+
+// Tickets are paid for with the Balances Module, so our calls need access to it. Our hooks are
+// implemented by hand below, since they also need access to the randomness of the runtime.
+#[macros::call(custom_hooks, R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Buy a ticket for the next draw, by transferring the ticket price from the `caller` to the
+	/// pot account. The caller names the most they are willing to pay, so they never pay more than
+	/// they expect if the runtime changes the ticket price.
+	/// This function will return an error if the ticket price is more than `max_price`, or if the
+	/// transfer fails. See `balances::Pallet::transfer`.
+	pub fn enter<R>(runtime: &mut R, caller: T::AccountId, max_price: T::Balance) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let price = T::TicketPrice::get();
+		if price > max_price {
+			return Err("ticket price too high".into());
+		}
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.transfer(caller.clone(), Self::account_id(), price)?;
+
+		let lottery: &mut Self = runtime.as_mut();
+		lottery.participants.push(caller.clone());
+		lottery.deposit_event(Event::Entered { who: caller });
+		Ok(())
+	}
+}
+
+// At the end of every draw period, we draw the winner of the pot, which is transferred to them.
+// This needs access to the Balances Module and to the randomness of the runtime, so we implement
+// `PalletHooks` by hand, instead of implementing `Hooks` for the pallet.
+impl<T: Config, R> PalletHooks<Pallet<T>, T::BlockNumber> for R
+where
+	R: AsMut<Pallet<T>> + AsMut<balances::Pallet<T>> + Randomness<T::Hash>,
+	T::Hash: Into<u64>,
+{
+	// If the block number of the next draw overflows, there are no more draws.
+	fn on_finalize(&mut self, block_number: T::BlockNumber) {
+		let lottery: &mut Pallet<T> = self.as_mut();
+		let next_draw = lottery.last_draw.checked_add(&T::DrawPeriod::get());
+		if next_draw.is_none_or(|next| block_number < next) {
+			return;
+		}
+		lottery.last_draw = block_number;
+		let participants = core::mem::take(&mut lottery.participants);
+		let balances: &mut balances::Pallet<T> = self.as_mut();
+		let amount = Pallet::<T>::pot(balances);
+
+		// Without any participants, or if the winner cannot receive the pot, for example if it is
+		// below the existential deposit, the pot rolls over to the next draw.
+		let event = if participants.is_empty() {
+			Event::RolledOver { amount }
+		} else {
+			let random: u64 = self.random(b"lottery").into();
+			let winner = participants[(random % participants.len() as u64) as usize].clone();
+			let balances: &mut balances::Pallet<T> = self.as_mut();
+			match balances.transfer(Pallet::<T>::account_id(), winner.clone(), amount) {
+				Ok(()) => Event::Won { winner, amount },
+				Err(_) => Event::RolledOver { amount },
+			}
+		};
+		let lottery: &mut Pallet<T> = self.as_mut();
+		lottery.deposit_event(event);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext, PalletHooks},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the lottery pallet, and whose
+	// randomness is a fixed value, so the tests know who wins.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		lottery: super::Pallet<TestRuntime>,
+		random: u64,
+	}
+
+	struct ExistentialDeposit;
+	impl crate::support::Get<u128> for ExistentialDeposit {
+		fn get() -> u128 {
+			5
+		}
+	}
+
+	struct TicketPrice;
+	impl crate::support::Get<u128> for TicketPrice {
+		fn get() -> u128 {
+			10
+		}
+	}
+
+	struct DrawPeriod;
+	impl crate::support::Get<u32> for DrawPeriod {
+		fn get() -> u32 {
+			3
+		}
+	}
+
+	struct PotAccount;
+	impl crate::support::Get<String> for PotAccount {
+		fn get() -> String {
+			"pot".to_string()
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ExistentialDeposit;
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type TicketPrice = TicketPrice;
+		type DrawPeriod = DrawPeriod;
+		type PotAccount = PotAccount;
+	}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.lottery
+		}
+	}
+
+	impl crate::support::Randomness<u64> for TestRuntime {
+		fn random(&self, _subject: &[u8]) -> u64 {
+			self.random
+		}
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	fn enter() -> Call<TestRuntime> {
+		Call::enter { max_price: 10 }
+	}
+
+	// The pot of the next draw.
+	fn pot(runtime: &TestRuntime) -> u128 {
+		super::Pallet::<TestRuntime>::pot(&runtime.balances)
+	}
+
+	// Run the hooks of the lottery pallet at the end of block `block_number`.
+	fn finalize(runtime: &mut TestRuntime, block_number: u32) {
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_finalize(runtime, block_number);
+	}
+
+	// A runtime where alice, bob and charlie can buy tickets with 100 each.
+	fn new_runtime() -> TestRuntime {
+		let mut runtime = TestRuntime {
+			balances: balances::Pallet::new(),
+			lottery: super::Pallet::new(),
+			random: 0,
+		};
+		for who in ["alice", "bob", "charlie"] {
+			runtime.balances.set_balance(&who.to_string(), 100);
+		}
+		runtime
+	}
+
+	#[test]
+	fn tickets_pay_into_the_pot() {
+		let mut runtime = new_runtime();
+
+		assert_eq!(runtime.dispatch_call(context("alice"), enter()), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("bob"), enter()), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("alice"), enter()), Ok(()));
+		assert_eq!(
+			runtime.dispatch_call(context("dave"), enter()),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("bob"), Call::enter { max_price: 9 }),
+			Err("ticket price too high".into())
+		);
+		assert_eq!(runtime.lottery.participants(), ["alice", "bob", "alice"]);
+		assert_eq!(pot(&runtime), 30);
+		assert_eq!(runtime.balances.balance(&"pot".to_string()), 30);
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 80);
+		assert_eq!(runtime.lottery.take_events().len(), 3);
+	}
+
+	#[test]
+	fn winner_takes_the_pot() {
+		let mut runtime = new_runtime();
+		for who in ["alice", "bob", "charlie"] {
+			assert_eq!(runtime.dispatch_call(context(who), enter()), Ok(()));
+		}
+
+		// Nothing happens before the end of the draw period.
+		finalize(&mut runtime, 2);
+		assert_eq!(pot(&runtime), 30);
+
+		// The randomness picks the second ticket, which is bob's.
+		runtime.random = 4;
+		finalize(&mut runtime, 3);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 120);
+		assert_eq!(runtime.balances.balance(&"pot".to_string()), 0);
+		assert_eq!(pot(&runtime), 0);
+		assert!(runtime.lottery.participants().is_empty());
+		assert!(matches!(
+			runtime.lottery.take_events().last(),
+			Some(Event::Won { winner, amount: 30 }) if winner == "bob"
+		));
+
+		// The next draw is a whole draw period later.
+		assert_eq!(runtime.dispatch_call(context("charlie"), enter()), Ok(()));
+		finalize(&mut runtime, 5);
+		assert_eq!(runtime.lottery.participants(), ["charlie"]);
+		// Charlie bought a ticket for both draws, and won back the price of the second one.
+		finalize(&mut runtime, 6);
+		assert_eq!(runtime.balances.balance(&"charlie".to_string()), 90);
+	}
+
+	#[test]
+	fn pot_rolls_over() {
+		let mut runtime = new_runtime();
+
+		// A draw without any participants does nothing, except roll the pot over.
+		finalize(&mut runtime, 3);
+		assert!(matches!(runtime.lottery.take_events()[..], [Event::RolledOver { amount: 0 }]));
+
+		// A winner who cannot receive the pot does not get it, so it rolls over to the next draw.
+		// We write storage directly, since a pot below the existential deposit is never paid.
+		runtime.balances.set_balance(&"pot".to_string(), 4);
+		runtime.lottery.participants.push("dave".to_string());
+		finalize(&mut runtime, 6);
+		assert_eq!(runtime.balances.balance(&"dave".to_string()), 0);
+		assert!(runtime.lottery.participants().is_empty());
+		assert!(matches!(runtime.lottery.take_events()[..], [Event::RolledOver { amount: 4 }]));
+
+		assert_eq!(runtime.dispatch_call(context("alice"), enter()), Ok(()));
+		assert_eq!(pot(&runtime), 14);
+		finalize(&mut runtime, 9);
+		assert_eq!(runtime.balances.balance(&"alice".to_string()), 104);
+		assert!(matches!(
+			runtime.lottery.take_events().last(),
+			Some(Event::Won { amount: 14, .. })
+		));
+	}
+}
@@ -1,9 +1,27 @@
+mod assets;
+mod auction;
 mod balances;
+#[cfg(feature = "bench")]
+mod bench;
+mod democracy;
+mod escrow;
+mod identity;
+mod index;
+mod lottery;
+mod multisig;
 mod proof_of_existence;
+mod scheduler;
+mod session;
+mod staking;
+mod sudo;
 mod support;
 mod system;
-
-use crate::support::Dispatch;
+mod timestamp;
+mod tips;
+mod treasury;
+mod uniques;
+mod utility;
+mod vesting;
 
 // These are the concrete types we will use in our simple state machine.
 // Modules are configured for these types directly, and they satisfy all of our
@@ -13,111 +31,2553 @@ mod types {
 	pub type Balance = u128;
 	pub type BlockNumber = u32;
 	pub type Nonce = u32;
-	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall>;
-	pub type Header = crate::support::Header<BlockNumber>;
+	pub type Hash = u64;
+	pub type Moment = u64;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall, Nonce>;
+	pub type Header = crate::support::Header<BlockNumber, Hash>;
 	pub type Block = crate::support::Block<Header, Extrinsic>;
-	pub type Content = &'static str;
+	pub type Content = String;
+	pub type ItemId = u32;
+	pub type AssetId = u32;
 }
 
 // This is our main Runtime.
 // It accumulates all of the different pallets we want to use,
 // functions implemented on the Runtime allow us to access those pallets and execute blocks of
 // transactions.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[macros::runtime]
 pub struct Runtime {
 	system: system::Pallet<Self>,
+	timestamp: timestamp::Pallet<Self>,
 	balances: balances::Pallet<Self>,
 	proof_of_existence: proof_of_existence::Pallet<Self>,
+	sudo: sudo::Pallet<Self>,
+	staking: staking::Pallet<Self>,
+	vesting: vesting::Pallet<Self>,
+	utility: utility::Pallet<Self>,
+	treasury: treasury::Pallet<Self>,
+	democracy: democracy::Pallet<Self>,
+	identity: identity::Pallet<Self>,
+	uniques: uniques::Pallet<Self>,
+	escrow: escrow::Pallet<Self>,
+	scheduler: scheduler::Pallet<Self>,
+	session: session::Pallet<Self>,
+	multisig: multisig::Pallet<Self>,
+	tips: tips::Pallet<Self>,
+	auction: auction::Pallet<Self>,
+	lottery: lottery::Pallet<Self>,
+	index: index::Pallet<Self>,
+	assets: assets::Pallet<Self>,
+	// The states before the most recent blocks, so they can be reverted. This is not a pallet.
+	#[history]
+	history: support::BlockHistory<Self, 8>,
+}
+
+parameter_types! {
+	// The maximum total weight of the extrinsics in a block.
+	pub MaxBlockWeight: support::Weight = 10;
+	// The number of past blocks whose events are kept in the system pallet.
+	pub EventHistoryDepth: u32 = 10;
+	// The maximum number of extrinsics in a block.
+	pub MaxExtrinsicsPerBlock: u32 = 4;
 }
 
 impl system::Config for Runtime {
 	type AccountId = types::AccountId;
 	type BlockNumber = types::BlockNumber;
 	type Nonce = types::Nonce;
+	type Hash = types::Hash;
+	type RuntimeEvent = RuntimeEvent;
+	type MaxBlockWeight = MaxBlockWeight;
+	type EventHistoryDepth = EventHistoryDepth;
+	type MaxExtrinsicsPerBlock = MaxExtrinsicsPerBlock;
+}
+
+impl timestamp::Config for Runtime {
+	type Moment = types::Moment;
+}
+
+parameter_types! {
+	// The minimum balance needed to keep an account alive.
+	pub ExistentialDeposit: types::Balance = 1;
+
+	// The smallest amount which can be transferred, so empty transfers are rejected.
+	pub MinimumTransfer: types::Balance = 1;
+
+	// The fee paid for each unit of weight used by an extrinsic.
+	pub FeeMultiplier: types::Balance = 1;
+
+	// The account which collects the fees paid for extrinsics.
+	pub Treasury: types::AccountId = "treasury".to_string();
+
+	// The fees paid for extrinsics are collected by the treasury, instead of being burned.
+	pub FeeDestination: balances::FeeDestination = balances::FeeDestination::Treasury;
+
+	// The dust of reaped accounts is also collected by the treasury.
+	pub DustDestination: balances::DustDestination = balances::DustDestination::Treasury;
+}
+
+// Every successful transfer is logged at the `debug` level, which is more detail than the log of
+// each extrinsic, since a single extrinsic can make many transfers.
+pub struct LogTransfers;
+
+impl balances::OnTransfer<types::AccountId, types::Balance> for LogTransfers {
+	fn on_transfer(from: &types::AccountId, to: &types::AccountId, amount: types::Balance) {
+		log::debug!("Transfer\n\tFrom: {from}\n\tTo: {to}\n\tAmount: {amount}");
+	}
 }
 
 impl balances::Config for Runtime {
 	type Balance = types::Balance;
+	type ExistentialDeposit = ExistentialDeposit;
+	type MinimumTransfer = MinimumTransfer;
+	type FeeMultiplier = FeeMultiplier;
+	type Treasury = Treasury;
+	type FeeDestination = FeeDestination;
+	type DustDestination = DustDestination;
+	type OnTransfer = LogTransfers;
+}
+
+// The fee of an extrinsic is proportional to its weight, and is paid by the caller. Unless it is
+// burned, it goes to the treasury, where it can be spent.
+impl support::ChargeFee<types::AccountId> for Runtime {
+	fn charge_fee(
+		&mut self,
+		who: &types::AccountId,
+		weight: support::Weight,
+	) -> support::DispatchResult {
+		let fee = types::Balance::from(weight)
+			.checked_mul(<FeeMultiplier as support::Get<_>>::get())
+			.ok_or(balances::BalancesError::CannotPayFee)?;
+		self.balances.withdraw_fee(who, fee)?;
+		Ok(())
+	}
+}
+
+parameter_types! {
+	// The number of blocks a proof of existence claim is valid for.
+	pub ClaimValidity: types::BlockNumber = 100;
+
+	// The maximum number of proof of existence claims a single account can own.
+	pub MaxClaims: u32 = 10;
+
+	// The maximum length in bytes of the content of a proof of existence claim.
+	pub MaxContentLen: u32 = 256;
 }
 
 impl proof_of_existence::Config for Runtime {
 	type Content = types::Content;
+	type ClaimValidity = ClaimValidity;
+	type MaxClaims = MaxClaims;
+	type MaxContentLen = MaxContentLen;
+}
+
+impl sudo::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+}
+
+// Privileged calls can be made by the root origin, which `sudo` dispatches its call with, or
+// directly by the sudo key.
+impl support::EnsurePrivileged<types::AccountId> for Runtime {
+	fn ensure_privileged(
+		&self,
+		origin: &support::Origin<types::AccountId>,
+	) -> support::DispatchResult {
+		match origin {
+			support::Origin::Signed(caller) => self.sudo.ensure_sudo(caller),
+			_ => origin.ensure_root(),
+		}
+	}
+}
+
+impl staking::Config for Runtime {}
+
+impl vesting::Config for Runtime {}
+
+impl utility::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+}
+
+impl treasury::Config for Runtime {}
+
+parameter_types! {
+	// The number of aye votes a democracy proposal needs before it is dispatched.
+	pub VoteThreshold: u32 = 2;
+	// The number of blocks a conviction vote locks the balance of the voter for, per conviction.
+	pub LockPeriod: types::BlockNumber = 2;
+}
+
+impl democracy::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	type VoteThreshold = VoteThreshold;
+	type LockPeriod = LockPeriod;
+}
+
+parameter_types! {
+	// The maximum length of the display name of an account, in bytes.
+	pub MaxNameLength: u32 = 32;
+}
+
+impl identity::Config for Runtime {
+	type Content = types::Content;
+	type MaxNameLength = MaxNameLength;
+}
+
+impl uniques::Config for Runtime {
+	type ItemId = types::ItemId;
+}
+
+impl assets::Config for Runtime {
+	type AssetId = types::AssetId;
+	type Balance = types::Balance;
+}
+
+parameter_types! {
+	// The number of blocks during which the beneficiary of an escrow can claim it, once it unlocks.
+	pub ClaimPeriod: types::BlockNumber = 10;
+}
+
+impl escrow::Config for Runtime {
+	type ClaimPeriod = ClaimPeriod;
+}
+
+impl scheduler::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+}
+
+parameter_types! {
+	// The number of blocks in each session, after which the validator set can change.
+	pub SessionLength: types::BlockNumber = 10;
+}
+
+impl session::Config for Runtime {
+	type SessionLength = SessionLength;
+}
+
+// The account of a multisig is named after its id, so it cannot be confused with the account of
+// a user.
+pub struct MultisigAccount;
+
+impl multisig::MultisigAccount<types::AccountId> for MultisigAccount {
+	fn multisig_account(id: multisig::MultisigId) -> types::AccountId {
+		format!("multisig-{id:016x}")
+	}
+}
+
+impl multisig::Config for Runtime {
+	type RuntimeCall = RuntimeCall;
+	type MultisigAccount = MultisigAccount;
+}
+
+impl tips::Config for Runtime {}
+
+impl auction::Config for Runtime {}
+
+parameter_types! {
+	// The price of a lottery ticket.
+	pub TicketPrice: types::Balance = 5;
+
+	// The number of blocks between lottery draws.
+	pub DrawPeriod: types::BlockNumber = 10;
+
+	// The account which holds the lottery pot.
+	pub LotteryPot: types::AccountId = "lottery".to_string();
+}
+
+impl lottery::Config for Runtime {
+	type TicketPrice = TicketPrice;
+	type DrawPeriod = DrawPeriod;
+	type PotAccount = LotteryPot;
+}
+
+// The randomness of the runtime comes from the recent block hashes, which is NOT secure. See
+// `system::Pallet::random`.
+impl support::Randomness<types::Hash> for Runtime {
+	fn random(&self, subject: &[u8]) -> types::Hash {
+		self.system.random(subject)
+	}
+}
+
+impl index::Config for Runtime {}
+
+// Every extrinsic in a block is recorded in the transaction history of its caller.
+impl support::OnExtrinsic<types::AccountId, types::BlockNumber> for Runtime {
+	fn on_extrinsic(
+		&mut self,
+		caller: &types::AccountId,
+		block_number: types::BlockNumber,
+		index: u32,
+	) {
+		self.index.record(caller.clone(), block_number, index);
+	}
+}
+
+/// The initial state of the chain, which is used to create the runtime before the first block.
+#[derive(Debug, Default)]
+pub struct GenesisConfig {
+	/// The block number of the genesis state, which is zero by default. The first block executed
+	/// has the block number after it.
+	pub block_number: types::BlockNumber,
+	/// The accounts which start with some free balance, and how much they have.
+	pub balances: Vec<(types::AccountId, types::Balance)>,
+	/// The proof of existence claims which exist from the start, and the accounts which own them.
+	pub claims: Vec<(types::AccountId, types::Content)>,
+	/// The account which starts as the sudo key, if any.
+	pub sudo_key: Option<types::AccountId>,
+}
+
+impl Runtime {
+	/// Create a new runtime, with every pallet seeded from the `genesis` config.
+	///
+	/// This will panic if the genesis config is invalid, for example if the same content is claimed
+	/// twice, since the chain cannot start from an invalid state.
+	pub fn from_genesis(genesis: GenesisConfig) -> Self {
+		let mut runtime = Self::new();
+		runtime.system = system::Pallet::new_at(genesis.block_number);
+		for (who, amount) in &genesis.balances {
+			runtime.balances.set_balance(who, *amount);
+		}
+		// The genesis claims are created at the genesis block number, and expire relative to it.
+		for (owner, claim) in genesis.claims {
+			let context = support::DispatchContext::signed(owner, genesis.block_number);
+			runtime
+				.proof_of_existence
+				.create_claim(context, claim, types::Content::new())
+				.expect("invalid genesis claim");
+		}
+		if let Some(key) = genesis.sudo_key {
+			runtime.sudo.initialize_key(key);
+		}
+		// The genesis state is not created by any block, so it does not emit any events.
+		runtime.proof_of_existence.take_events();
+		runtime
+	}
+
+	/// Get a deep copy of the runtime, including its history, which can be changed without
+	/// affecting this one. For example, a block can be executed on a fork to see what it would do,
+	/// without committing to it.
+	pub fn fork(&self) -> Self {
+		self.clone()
+	}
+
+	/// Move the state of the account `from` to the account `to`, in every pallet which keeps state
+	/// about accounts: its balance, locks and allowances, its nonce and whether it is frozen, its
+	/// proof of existence claims, identity, items, assets, tips and transaction history, its slots
+	/// in the validator sets, the sudo key, its lottery tickets, and the escrows it can claim.
+	/// This is a tool for demos, and is not part of any block. Calls which mention `from`, like
+	/// scheduled calls, proposals and multisig operations, are not changed.
+	///
+	/// This function will return an error if `to` already exists, which means it has any state in
+	/// any of those pallets, since its state would be overwritten. It will also return an error if
+	/// `from` has any reserved or locked balance, or owns any locked item, since those are held by
+	/// other pallets, like the Staking, Vesting, Democracy, Escrow and Auction Modules, which would
+	/// still expect to find them at `from`.
+	pub fn migrate_account(
+		&mut self,
+		from: types::AccountId,
+		to: types::AccountId,
+	) -> Result<(), &'static str> {
+		let account = self.balances.account(&to);
+		let exists = account.free > 0
+			|| account.reserved > 0
+			|| self.balances.has_allowances(&to)
+			|| self.system.nonce(&to) > 0
+			|| self.system.is_frozen(&to)
+			|| self.proof_of_existence.claim_count_of(&to) > 0
+			|| self.identity.identity_of(&to).is_some()
+			|| self.uniques.items_of(&to).next().is_some()
+			|| self.assets.has_assets(&to)
+			|| self.tips.total_tipped(&to) > 0
+			|| !self.index.history(&to).is_empty()
+			|| self.session.validators().contains(&to)
+			|| self.session.queued_validators().is_some_and(|queued| queued.contains(&to))
+			|| self.sudo.key() == Some(&to)
+			|| self.lottery.participants().contains(&to)
+			|| self.escrow.has_escrows(&to);
+		if exists {
+			return Err("account already exists");
+		}
+		let account = self.balances.account(&from);
+		if account.reserved > 0 || account.frozen > 0 {
+			return Err("account has reserved or locked balance");
+		}
+		if self.uniques.items_of(&from).any(|item| self.uniques.is_locked(item)) {
+			return Err("account has locked items");
+		}
+		self.system.migrate_account(&from, &to);
+		self.balances.migrate_account(&from, &to);
+		self.proof_of_existence.migrate_account(&from, &to);
+		self.identity.migrate_account(&from, &to);
+		self.uniques.migrate_account(&from, &to);
+		self.assets.migrate_account(&from, &to);
+		self.tips.migrate_account(&from, &to);
+		self.index.migrate_account(&from, &to);
+		self.session.migrate_account(&from, &to);
+		self.sudo.migrate_account(&from, &to);
+		self.lottery.migrate_account(&from, &to);
+		self.escrow.migrate_account(&from, &to);
+		Ok(())
+	}
+
+	/// Write a human friendly summary of the state of the runtime to `f`: the current block number,
+	/// the balances of every account, the total issuance, and the proof of existence claims.
+	///
+	/// This is much easier to read than the `Debug` format of the runtime, which shows everything.
+	pub fn write_state(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+		writeln!(f, "Block number: {}", self.system.block_number())?;
+		writeln!(f, "Accounts:")?;
+		for who in self.balances.accounts() {
+			let free = self.balances.balance(who);
+			let reserved = self.balances.reserved_balance(who);
+			writeln!(f, "  {who}: free {free}, reserved {reserved}")?;
+		}
+		writeln!(f, "Total issuance: {}", self.balances.total_issuance())?;
+		writeln!(f, "Claims:")?;
+		for (claim, owner) in self.proof_of_existence.claims() {
+			writeln!(f, "  {claim:?} owned by {owner}")?;
+		}
+		Ok(())
+	}
+
+	/// Get a canonical snapshot of all of the storage of the runtime: one line per storage value or
+	/// storage map entry, like `balances.free["alice"] = 100`, in the order of the pallets in the
+	/// runtime, with map entries sorted by key.
+	///
+	/// Unlike `write_state`, which is meant to be read by humans, the snapshot contains everything
+	/// which makes up the state root, and is stable across runs, so it can be diffed against an
+	/// expected snapshot in tests.
+	pub fn snapshot(&self) -> String {
+		let mut snapshot = String::new();
+		self.write_snapshot(&mut snapshot).expect("writing to a string cannot fail");
+		snapshot
+	}
+
+	// Write the snapshot of every pallet to `f`. The utility and treasury pallets have no storage,
+	// so they do not appear in the snapshot.
+	fn write_snapshot(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+		self.system.write_snapshot(f)?;
+		self.timestamp.write_snapshot(f)?;
+		self.balances.write_snapshot(f)?;
+		self.proof_of_existence.write_snapshot(f)?;
+		self.sudo.write_snapshot(f)?;
+		self.staking.write_snapshot(f)?;
+		self.vesting.write_snapshot(f)?;
+		self.democracy.write_snapshot(f)?;
+		self.identity.write_snapshot(f)?;
+		self.uniques.write_snapshot(f)?;
+		self.escrow.write_snapshot(f)?;
+		self.scheduler.write_snapshot(f)?;
+		self.session.write_snapshot(f)?;
+		self.multisig.write_snapshot(f)?;
+		self.tips.write_snapshot(f)?;
+		self.auction.write_snapshot(f)?;
+		self.lottery.write_snapshot(f)?;
+		self.index.write_snapshot(f)?;
+		self.assets.write_snapshot(f)
+	}
+
+	/// Encode all of the state of the runtime into bytes, in a compact binary encoding, so a node
+	/// can save it and load it later with `decode_state`.
+	///
+	/// Unlike the state root, this covers all of the storage of every pallet, including the block
+	/// hashes of the system pallet. The events and the history of the runtime are not part of its
+	/// state, so they are not encoded.
+	#[cfg(feature = "codec")]
+	pub fn encode_state(&self) -> Vec<u8> {
+		// The pallets are encoded one after the other, in the order of the pallets in the runtime.
+		fn encode(bytes: &mut Vec<u8>, pallet: &impl serde::Serialize) {
+			bincode::serialize_into(bytes, pallet).expect("the state can always be encoded");
+		}
+		let mut bytes = Vec::new();
+		encode(&mut bytes, &self.system);
+		encode(&mut bytes, &self.timestamp);
+		encode(&mut bytes, &self.balances);
+		encode(&mut bytes, &self.proof_of_existence);
+		encode(&mut bytes, &self.sudo);
+		encode(&mut bytes, &self.staking);
+		encode(&mut bytes, &self.vesting);
+		encode(&mut bytes, &self.utility);
+		encode(&mut bytes, &self.treasury);
+		encode(&mut bytes, &self.democracy);
+		encode(&mut bytes, &self.identity);
+		encode(&mut bytes, &self.uniques);
+		encode(&mut bytes, &self.escrow);
+		encode(&mut bytes, &self.scheduler);
+		encode(&mut bytes, &self.session);
+		encode(&mut bytes, &self.multisig);
+		encode(&mut bytes, &self.tips);
+		encode(&mut bytes, &self.auction);
+		encode(&mut bytes, &self.lottery);
+		encode(&mut bytes, &self.index);
+		encode(&mut bytes, &self.assets);
+		bytes
+	}
+
+	/// Create a runtime from the state encoded with `encode_state`. The runtime has no events and
+	/// no history, so blocks from before its state cannot be reverted.
+	///
+	/// This function will return an error if the bytes are not a valid encoding of a state.
+	#[cfg(feature = "codec")]
+	pub fn decode_state(mut bytes: &[u8]) -> Result<Self, &'static str> {
+		fn decode<P: serde::de::DeserializeOwned>(bytes: &mut &[u8]) -> Result<P, &'static str> {
+			bincode::deserialize_from(bytes).map_err(|_| "invalid state encoding")
+		}
+		let mut runtime = Self::new();
+		runtime.system = decode(&mut bytes)?;
+		runtime.timestamp = decode(&mut bytes)?;
+		runtime.balances = decode(&mut bytes)?;
+		runtime.proof_of_existence = decode(&mut bytes)?;
+		runtime.sudo = decode(&mut bytes)?;
+		runtime.staking = decode(&mut bytes)?;
+		runtime.vesting = decode(&mut bytes)?;
+		runtime.utility = decode(&mut bytes)?;
+		runtime.treasury = decode(&mut bytes)?;
+		runtime.democracy = decode(&mut bytes)?;
+		runtime.identity = decode(&mut bytes)?;
+		runtime.uniques = decode(&mut bytes)?;
+		runtime.escrow = decode(&mut bytes)?;
+		runtime.scheduler = decode(&mut bytes)?;
+		runtime.session = decode(&mut bytes)?;
+		runtime.multisig = decode(&mut bytes)?;
+		runtime.tips = decode(&mut bytes)?;
+		runtime.auction = decode(&mut bytes)?;
+		runtime.lottery = decode(&mut bytes)?;
+		runtime.index = decode(&mut bytes)?;
+		runtime.assets = decode(&mut bytes)?;
+		if !bytes.is_empty() {
+			return Err("invalid state encoding");
+		}
+		Ok(runtime)
+	}
+
+	/// Print the summary of the state of the runtime. See `write_state`.
+	pub fn print_state(&self) {
+		let mut summary = String::new();
+		self.write_state(&mut summary).expect("writing to a string cannot fail");
+		print!("{summary}");
+	}
 }
 
 // The main entry point for our simple state machine.
 fn main() {
-	// Create a new instance of the Runtime.
-	// It will instantiate with it all the modules it uses.
-	let mut runtime = Runtime::new();
+	// The runtime logs every extrinsic it executes. Set `RUST_LOG=info` to see the ones which
+	// succeed too, not just the ones which fail.
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
 	let alice = "alice".to_string();
 	let bob = "bob".to_string();
 	let charlie = "charlie".to_string();
 
-	// Initialize the system with some initial balance.
-	runtime.balances.set_balance(&alice, 100);
+	// Create a new instance of the Runtime, starting with some initial balance, and alice as the
+	// sudo key. It will instantiate with it all the modules it uses.
+	let mut runtime = Runtime::from_genesis(GenesisConfig {
+		balances: vec![(alice.clone(), 100)],
+		sudo_key: Some(alice.clone()),
+		..Default::default()
+	});
 
-	// Here are the extrinsics in our block.
+	// With the `bench` feature, `cargo run --features bench -- bench 1000` runs each benchmark for
+	// the given number of iterations, and prints a report instead.
+	#[cfg(feature = "bench")]
+	if std::env::args().nth(1).as_deref() == Some("bench") {
+		let iterations =
+			std::env::args().nth(2).map_or(1000, |n| n.parse().expect("invalid number"));
+		bench::print_report(&bench::run(iterations));
+		return;
+	}
+
+	// If a path to a JSON file of blocks is given, for example `cargo run -- fixtures/blocks.json`,
+	// we execute those blocks instead of the ones below.
+	#[cfg(feature = "serde")]
+	if let Some(path) = std::env::args().nth(1) {
+		runtime.import_blocks_from_file(&path).expect("invalid blocks file");
+		runtime.print_state();
+		return;
+	}
+
+	// Here are the extrinsics in each of our blocks.
 	// You can add or remove these based on the modules and calls you have set up.
-	let block_1 = types::Block {
-		header: support::Header { block_number: 1 },
-		extrinsics: vec![
-			support::Extrinsic {
-				caller: alice.clone(),
-				call: RuntimeCall::balances(balances::Call::transfer {
-					to: bob.clone(),
-					amount: 20,
-				}),
-			},
-			support::Extrinsic {
-				caller: alice.clone(),
-				call: RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 20 }),
-			},
-		],
+	let block_1 = vec![
+		support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 20 }),
+			0,
+		),
+		support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: charlie, amount: 20 }),
+			1,
+		),
+	];
+
+	let block_2 = vec![
+		support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: "Hello, world!".to_string(),
+				metadata: "The first program.".to_string(),
+			}),
+			2,
+		),
+		support::Extrinsic::new_signed(
+			bob.clone(),
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: "Hello, world!".to_string(),
+				metadata: "The first program.".to_string(),
+			}),
+			0,
+		),
+	];
+
+	let block_3 = vec![
+		support::Extrinsic::new_signed(
+			alice,
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
+				claim: "Hello, world!".to_string(),
+			}),
+			3,
+		),
+		support::Extrinsic::new_signed(
+			bob,
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: "Hello, world!".to_string(),
+				metadata: "The first program.".to_string(),
+			}),
+			1,
+		),
+	];
+
+	// Author blocks out of our extrinsics, which executes them and fills in the block headers.
+	runtime.author_block(block_1);
+	runtime.author_block(block_2);
+	runtime.author_block(block_3);
+
+	// Print a summary of our runtime state. The full state is also available with `{:#?}`.
+	runtime.print_state();
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		assets, auction, balances, democracy, escrow, lottery, multisig, proof_of_existence,
+		scheduler, session, staking, sudo, support, system, tips, treasury, types, utility,
+		GenesisConfig, Runtime, RuntimeCall, RuntimeEvent,
 	};
+	use std::collections::BTreeSet;
+
+	#[test]
+	fn replayed_extrinsic_fails() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = || {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				0,
+			)
+		};
+		let (_, results) = runtime.author_block(vec![transfer(), transfer()]);
+		assert_eq!(results, vec![Ok(()), Err(support::DispatchError::Duplicate)]);
+		// Only the first transfer is executed, and pays a fee, since the second is the same extrinsic.
+		assert_eq!(runtime.balances.balance(&alice), 89);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.system.nonce(&alice), 1);
+
+		// Replaying it in a later block fails, since it reuses the same nonce.
+		let (_, results) = runtime.author_block(vec![transfer()]);
+		assert_eq!(results, vec![Err(support::DispatchError::BadNonce)]);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+	}
+
+	#[test]
+	fn block_hash_is_recorded() {
+		let mut author = Runtime::new();
+		let mut runtime = Runtime::new();
+
+		assert_eq!(author.system.block_hash(1), None);
+		let (block, _) = author.author_block(vec![]);
+		let expected_hash = support::hash(&block.header);
+		assert_eq!(author.system.block_hash(1), Some(expected_hash));
+
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(0));
+		assert_eq!(runtime.system.block_hash(1), Some(expected_hash));
+	}
+
+	#[test]
+	fn transfer_emits_event() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			0,
+		)]);
+		assert_eq!(results, vec![Ok(())]);
+
+		let events = runtime.system.events();
+		assert_eq!(events.len(), 2);
+		assert!(matches!(
+			&events[0].event,
+			RuntimeEvent::balances(balances::Event::FeePaid { who, fee: 1 }) if *who == alice
+		));
+		assert!(matches!(
+			&events[1].event,
+			RuntimeEvent::balances(balances::Event::Transferred { from, to, amount: 10 })
+				if *from == alice && *to == bob
+		));
+	}
+
+	#[test]
+	fn extrinsic_results_are_returned() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				0,
+			),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: alice.clone(), amount: 100 }),
+				0,
+			),
+		]);
+		assert_eq!(results, vec![Ok(()), Err("Not enough funds.".into())]);
+		// Both extrinsics pay a fee, even though the second one fails.
+		assert_eq!(runtime.balances.balance(&alice), 89);
+		assert_eq!(runtime.balances.balance(&bob), 9);
+	}
+
+	#[test]
+	fn invalid_block_number_fails() {
+		let mut runtime = Runtime::new();
+		let block = types::Block {
+			header: support::Header { block_number: 2, parent_hash: 0, state_root: [0; 32] },
+			extrinsics: vec![],
+		};
+		assert_eq!(runtime.execute_block(&block).err(), Some("block number mismatch"));
+	}
+
+	#[test]
+	fn forged_extrinsic_fails() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		// Bob signs a transfer, and then claims it was made by alice.
+		let call =
+			|| RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 });
+		let signed_by_bob = support::Extrinsic::new_signed(bob.clone(), call(), 0);
+		let forged = support::Extrinsic { caller: alice.clone(), ..signed_by_bob };
+		let unsigned =
+			support::Extrinsic { caller: alice.clone(), call: call(), nonce: 0, signature: None };
+		let (_, results) = runtime.author_block(vec![forged, unsigned]);
+		assert_eq!(
+			results,
+			vec![Err(support::DispatchError::BadOrigin), Err(support::DispatchError::BadOrigin)]
+		);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		// A forged extrinsic does not use up the nonce of the account it pretends to be.
+		assert_eq!(runtime.system.nonce(&alice), 0);
+	}
+
+	#[test]
+	fn frozen_accounts_are_rejected() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		// A frozen account does not pay a fee, or use up its nonce.
+		runtime.system.freeze(alice.clone());
+		let (_, results) = runtime.author_block(vec![transfer(0)]);
+		assert_eq!(results, vec![Err(support::DispatchError::Frozen)]);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.system.nonce(&alice), 0);
+
+		// Once thawed, the same extrinsic succeeds.
+		runtime.system.thaw(&alice);
+		let (_, results) = runtime.author_block(vec![transfer(0)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+	}
+
+	#[test]
+	fn fatal_errors_abort_the_block() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |amount, nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount }),
+				nonce,
+			)
+		};
+		let forged = support::Extrinsic { caller: bob.clone(), ..transfer(10, 0) };
+
+		// A transfer which fails is recoverable, so the block still executes the rest.
+		let (block, results) =
+			runtime.fork().author_block(vec![transfer(1000, 0), transfer(10, 1)]);
+		assert_eq!(results, vec![Err("Not enough funds.".into()), Ok(())]);
+		assert_eq!(runtime.clone().execute_block(&block).map(|events| events.len()), Ok(3));
+
+		// A forged extrinsic is fatal, so an author leaves it out of the block.
+		let (block, results) = runtime.fork().author_block(vec![forged.clone(), transfer(10, 0)]);
+		assert_eq!(results, vec![Err(support::DispatchError::BadOrigin), Ok(())]);
+		assert_eq!(block.extrinsics, vec![transfer(10, 0)]);
+
+		// A block which includes it anyway is aborted, and the transfer after it is not executed.
+		let block = types::Block { extrinsics: vec![forged.clone(), transfer(10, 0)], ..block };
+		assert_eq!(runtime.execute_block(&block).err(), Some("Invalid signature."));
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.system.nonce(&alice), 0);
+		assert_eq!(runtime.system.block_number(), 0);
+
+		// The extrinsics before it are reverted too, including their fees.
+		let block = types::Block { extrinsics: vec![transfer(10, 0), forged], ..block };
+		assert_eq!(runtime.execute_block(&block).err(), Some("Invalid signature."));
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.system.nonce(&alice), 0);
+		assert_eq!(runtime.system.block_number(), 0);
+		assert!(runtime.system.events().is_empty());
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn block_json_round_trip() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let block = types::Block {
+			header: support::Header { block_number: 1, parent_hash: 0, state_root: [7; 32] },
+			extrinsics: vec![
+				support::Extrinsic::new_signed(
+					alice.clone(),
+					RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+					0,
+				),
+				support::Extrinsic::new_signed(
+					bob,
+					RuntimeCall::proof_of_existence(
+						crate::proof_of_existence::Call::create_claim {
+							claim: "Hello, world!".to_string(),
+							metadata: "The first program.".to_string(),
+						},
+					),
+					0,
+				),
+				support::Extrinsic {
+					caller: alice,
+					call: RuntimeCall::balances(balances::Call::transfer_batch {
+						transfers: vec![],
+					}),
+					nonce: 1,
+					signature: None,
+				},
+			],
+		};
+
+		let json = block.to_json();
+		let parsed = types::Block::from_json(&json).expect("valid json");
+		assert_eq!(parsed, block);
+		// The signatures survive the round trip.
+		assert!(parsed.extrinsics[0].verify_signed());
+		assert!(types::Block::from_json("{}").is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn unknown_calls() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		// A call of a pallet which this runtime does not have still decodes, and encodes again.
+		let json = r#"{"lending":{"borrow":{"amount":10}}}"#;
+		let call: RuntimeCall = serde_json::from_str(json).expect("valid json");
+		assert_eq!(
+			call,
+			RuntimeCall::Unknown {
+				pallet: "lending".to_string(),
+				call: r#"{"borrow":{"amount":10}}"#.to_string()
+			}
+		);
+		assert_eq!(serde_json::to_string(&call).unwrap(), json);
+
+		// Dispatching it fails like any other call, and the rest of the block is still executed.
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: "bob".to_string(), amount: 10 });
+		let (block, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), call, 0),
+			support::Extrinsic::new_signed(alice.clone(), transfer, 1),
+		]);
+		assert_eq!(results, vec![Err("unknown call".into()), Ok(())]);
+		assert_eq!(types::Block::from_json(&block.to_json()).expect("valid json"), block);
+
+		// Unknown calls of a pallet which the runtime does have are still rejected.
+		let json = r#"{"balances":{"borrow":{"amount":10}}}"#;
+		assert!(serde_json::from_str::<RuntimeCall>(json).is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn import_blocks_from_file() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		runtime.sudo.initialize_key(alice.clone());
+
+		// The blocks in this file were authored starting from the same state as in `main`.
+		let path = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/blocks.json");
+		assert_eq!(runtime.import_blocks_from_file(path), Ok(()));
+		assert_eq!(runtime.system.block_number(), 3);
+		// Alice and bob also paid the fees of their extrinsics to the treasury.
+		assert_eq!(runtime.balances.balance(&alice), 52);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.balances.balance(&charlie), 20);
+		assert_eq!(runtime.balances.balance(&"treasury".to_string()), 18);
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello, world!".to_string()), Some(&bob));
+	}
+
+	#[test]
+	#[cfg(feature = "serde")]
+	fn import_stops_at_invalid_block() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		// The second block skips a block number, so the third block is never executed.
+		let mut author = Runtime::new();
+		author.balances.set_balance(&alice, 100);
+		let mut blocks = (0..3)
+			.map(|nonce| author.author_block(vec![transfer(nonce)]).0)
+			.collect::<Vec<_>>();
+		blocks[1].header.block_number = 3;
+		blocks[2].header.block_number = 4;
+		let path = std::env::temp_dir().join("import_stops_at_invalid_block.json");
+		std::fs::write(&path, serde_json::to_string(&blocks).unwrap()).unwrap();
+
+		assert_eq!(
+			runtime.import_blocks_from_file(path.to_str().unwrap()),
+			Err(support::ImportError::Block { index: 1, error: "block number mismatch" })
+		);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+
+		assert_eq!(
+			runtime.import_blocks_from_file("does/not/exist.json"),
+			Err(support::ImportError::Read)
+		);
+		std::fs::write(&path, "not json").unwrap();
+		assert_eq!(
+			runtime.import_blocks_from_file(path.to_str().unwrap()),
+			Err(support::ImportError::Parse)
+		);
+	}
+
+	#[test]
+	fn state_root_changes_with_state() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let state_root = runtime.state_root();
+		assert_eq!(runtime.state_root(), state_root);
+		assert_eq!(Runtime::new().state_root(), Runtime::new().state_root());
+
+		// Tampering with a balance changes the state root.
+		runtime.balances.set_balance(&alice, 101);
+		assert_ne!(runtime.state_root(), state_root);
+		runtime.balances.set_balance(&alice, 100);
+		assert_eq!(runtime.state_root(), state_root);
+
+		// The phase and the block hashes are kept in storage, but are not part of the state root.
+		runtime.system.set_phase(system::Phase::Finalization);
+		runtime.system.set_block_hash(0, 42);
+		assert_eq!(runtime.state_root(), state_root);
+	}
+
+	#[test]
+	fn state_root_is_checked() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let transfer = support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			0,
+		);
+
+		let mut author = Runtime::new();
+		author.balances.set_balance(&alice, 100);
+		let (block, _) = author.author_block(vec![transfer]);
+
+		// A runtime with the same state accepts the block, and ends up with the same state root.
+		let mut runtime = Runtime::new();
+		runtime.balances.set_balance(&alice, 100);
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.state_root(), block.header.state_root);
+
+		// A runtime with a different state rejects the block, and none of it is applied.
+		let mut runtime = Runtime::new();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 1);
+		let state_root = runtime.state_root();
+		assert_eq!(runtime.execute_block(&block).err(), Some("state root mismatch"));
+		assert_eq!(runtime.system.block_hash(1), None);
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.system.nonce(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 1);
+		assert_eq!(runtime.state_root(), state_root);
+
+		// The block is not recorded in the history either, so there is nothing to revert.
+		assert_eq!(runtime.revert_last_block(), Err("no block to revert"));
+	}
+
+	#[test]
+	fn parent_hash_is_checked() {
+		let mut author = Runtime::new();
+		let (block_1, _) = author.author_block(vec![]);
+		let (block_2, _) = author.author_block(vec![]);
+		assert_eq!(block_1.header.parent_hash, 0);
+		assert_eq!(block_2.header.parent_hash, support::hash(&block_1.header));
+
+		let mut runtime = Runtime::new();
+		assert_eq!(runtime.execute_block(&block_1).map(|events| events.len()), Ok(0));
+		let mut wrong_parent = block_2.clone();
+		wrong_parent.header.parent_hash = 1234;
+		assert_eq!(runtime.execute_block(&wrong_parent).err(), Some("parent hash mismatch"));
 
-	let block_2 = types::Block {
-		header: support::Header { block_number: 2 },
-		extrinsics: vec![
-			support::Extrinsic {
-				caller: alice.clone(),
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+		// The first block must have the zero hash as its parent.
+		let mut runtime = Runtime::new();
+		let mut wrong_genesis = author.author_block(vec![]).0;
+		wrong_genesis.header.block_number = 1;
+		assert_eq!(runtime.execute_block(&wrong_genesis).err(), Some("parent hash mismatch"));
+	}
+
+	#[test]
+	fn header_is_validated() {
+		use support::HeaderError;
+
+		let mut author = Runtime::new();
+		let (block_1, _) = author.author_block(vec![]);
+		let (block_2, _) = author.author_block(vec![]);
+		let mut runtime = Runtime::new();
+		assert_eq!(runtime.execute_block(&block_1).map(|events| events.len()), Ok(0));
+		assert_eq!(runtime.validate_header(&block_2.header), Ok(()));
+
+		// Each field of the header is checked on its own, with its own error.
+		let mut wrong_number = block_2.clone();
+		wrong_number.header.block_number = 3;
+		assert_eq!(
+			runtime.validate_header(&wrong_number.header),
+			Err(HeaderError::BlockNumberMismatch)
+		);
+		assert_eq!(runtime.execute_block(&wrong_number).err(), Some("block number mismatch"));
+
+		let mut wrong_parent = block_2.clone();
+		wrong_parent.header.parent_hash = 1234;
+		assert_eq!(
+			runtime.validate_header(&wrong_parent.header),
+			Err(HeaderError::ParentHashMismatch)
+		);
+		assert_eq!(runtime.execute_block(&wrong_parent).err(), Some("parent hash mismatch"));
+
+		// The state root is only known after executing the block, so only `execute_block` checks it.
+		let mut wrong_root = block_2.clone();
+		wrong_root.header.state_root = [1; 32];
+		assert_eq!(runtime.validate_header(&wrong_root.header), Ok(()));
+		assert_eq!(runtime.clone().execute_block(&wrong_root).err(), Some("state root mismatch"));
+
+		// A block with an invalid header is rejected before it changes any state.
+		assert_eq!(runtime.system.block_number(), 1);
+		assert_eq!(runtime.execute_block(&block_2).map(|events| events.len()), Ok(0));
+		assert_eq!(runtime.state_root(), author.state_root());
+	}
+
+	#[test]
+	fn sudo_dispatches_nested_call() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let sudo_transfer = |caller: &String, nonce| {
+			support::Extrinsic::new_signed(
+				caller.clone(),
+				RuntimeCall::sudo(sudo::Call::sudo {
+					call: Box::new(RuntimeCall::balances(balances::Call::force_transfer {
+						source: alice.clone(),
+						dest: bob.clone(),
+						amount: 10,
+					})),
 				}),
-			},
-			support::Extrinsic {
-				caller: bob.clone(),
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+				nonce,
+			)
+		};
+		let (_, results) =
+			runtime.author_block(vec![sudo_transfer(&alice, 0), sudo_transfer(&bob, 0)]);
+		assert_eq!(results, vec![Ok(()), Err("sender must be the sudo key".into())]);
+		// The fee of a sudo call includes the weight of the inner call.
+		assert_eq!(runtime.balances.balance(&alice), 88);
+		assert_eq!(runtime.balances.balance(&bob), 8);
+
+		// The inner call is made by the root origin, so it cannot be a call which must be signed.
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 1 });
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(transfer) }),
+			1,
+		)]);
+		assert_eq!(results, vec![Err("call must be signed".into())]);
+		assert_eq!(runtime.balances.balance(&bob), 8);
+	}
+
+	#[test]
+	fn force_revoke_claim_requires_sudo() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let claim = "Abusive content".to_string();
+		let force_revoke_claim = |claim: &String| {
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::force_revoke_claim {
+				claim: claim.clone(),
+			})
+		};
+		let sudo = |call| RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(call) });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+					claim: claim.clone(),
+					metadata: String::new(),
 				}),
-			},
-		],
-	};
+				0,
+			),
+			// Bob cannot make a privileged call, even to revoke his own claim.
+			support::Extrinsic::new_signed(bob.clone(), force_revoke_claim(&claim), 1),
+			// Alice revokes the claim of bob through sudo, but only once.
+			support::Extrinsic::new_signed(alice.clone(), sudo(force_revoke_claim(&claim)), 0),
+			support::Extrinsic::new_signed(alice.clone(), sudo(force_revoke_claim(&claim)), 1),
+		]);
+		assert_eq!(
+			results,
+			vec![
+				Ok(()),
+				Err("sender must be the sudo key".into()),
+				Ok(()),
+				Err("claim does not exist".into())
+			]
+		);
+		assert_eq!(runtime.proof_of_existence.get_claim(&claim), None);
+	}
+
+	#[test]
+	fn force_transfer_requires_sudo() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let force_transfer = || {
+			RuntimeCall::balances(balances::Call::force_transfer {
+				source: bob.clone(),
+				dest: charlie.clone(),
+				amount: 50,
+			})
+		};
+		let (_, results) = runtime.author_block(vec![
+			// Bob cannot make a privileged call, even to move his own funds.
+			support::Extrinsic::new_signed(bob.clone(), force_transfer(), 0),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(force_transfer()) }),
+				1,
+			),
+			// Alice moves the funds of bob through sudo.
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::sudo(sudo::Call::sudo { call: Box::new(force_transfer()) }),
+				0,
+			),
+		]);
+		assert_eq!(
+			results,
+			vec![
+				Err("sender must be the sudo key".into()),
+				Err("sender must be the sudo key".into()),
+				Ok(())
+			]
+		);
+		// Bob only paid the fees of his two extrinsics.
+		assert_eq!(runtime.balances.balance(&bob), 47);
+		assert_eq!(runtime.balances.balance(&charlie), 50);
+	}
+
+	#[test]
+	fn block_weight_is_limited() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let create_claim = |claim: &str, nonce| {
+			let call = proof_of_existence::Call::create_claim {
+				claim: claim.to_string(),
+				metadata: String::new(),
+			};
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::proof_of_existence(call),
+				nonce,
+			)
+		};
+		assert_eq!(support::GetWeight::weight(&create_claim("a", 0).call), 5);
+
+		// Only two claims fit in the weight of a block, so the third is not executed, or included in
+		// the block.
+		let (block, results) = runtime.author_block(vec![
+			create_claim("a", 0),
+			create_claim("b", 1),
+			create_claim("c", 2),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(()), Err(support::DispatchError::Exhausted)]);
+		assert_eq!(block.extrinsics.len(), 2);
+		assert_eq!(runtime.proof_of_existence.get_claim(&"c".to_string()), None);
+		assert_eq!(runtime.system.nonce(&alice), 2);
+
+		// It can be executed in the next block instead.
+		let (_, results) = runtime.author_block(vec![create_claim("c", 2)]);
+		assert_eq!(results, vec![Ok(())]);
+	}
 
-	let block_3 = types::Block {
-		header: support::Header { block_number: 3 },
-		extrinsics: vec![
-			support::Extrinsic {
-				caller: alice,
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim {
-					claim: &"Hello, world!",
+	#[test]
+	fn batch_weight_depends_on_size() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer_batch = |n: usize, nonce| {
+			let call =
+				balances::Call::transfer_batch { transfers: vec![("bob".to_string(), 1); n] };
+			support::Extrinsic::new_signed(alice.clone(), RuntimeCall::balances(call), nonce)
+		};
+		assert_eq!(support::GetWeight::weight(&transfer_batch(1, 0).call), 2);
+		assert_eq!(support::GetWeight::weight(&transfer_batch(10, 0).call), 11);
+
+		// A batch of ten transfers is heavier than a whole block, so it can never be included, while
+		// a batch of nine fills the block on its own.
+		let (block, results) = runtime.author_block(vec![transfer_batch(10, 0)]);
+		assert_eq!(results, vec![Err(support::DispatchError::Exhausted)]);
+		assert!(block.extrinsics.is_empty());
+		let (block, results) =
+			runtime.author_block(vec![transfer_batch(9, 0), transfer_batch(1, 1)]);
+		assert_eq!(results, vec![Ok(()), Err(support::DispatchError::Exhausted)]);
+		assert_eq!(block.extrinsics.len(), 1);
+		assert_eq!(runtime.balances.balance(&"bob".to_string()), 9);
+	}
+
+	#[test]
+	fn max_extrinsics_per_block() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer {
+					to: "bob".to_string(),
+					amount: 1,
 				}),
-			},
-			support::Extrinsic {
-				caller: bob,
-				call: RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
-					claim: &"Hello, world!",
+				nonce,
+			)
+		};
+		assert_eq!(<crate::MaxExtrinsicsPerBlock as support::Get<u32>>::get(), 4);
+
+		// A block at the limit can be imported, even though it only uses part of the block weight.
+		let mut author = runtime.clone();
+		let (block, results) = author.author_block((0..4).map(transfer).collect());
+		assert_eq!(results, vec![Ok(()); 4]);
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(8));
+
+		// A block over the limit is rejected without changing any state.
+		let mut full = block.clone();
+		full.header.block_number = 2;
+		full.header.parent_hash = runtime.system.parent_hash();
+		full.extrinsics = (4..9).map(transfer).collect();
+		assert_eq!(runtime.execute_block(&full).err(), Some("too many extrinsics"));
+		assert_eq!(runtime.system.block_number(), 1);
+		assert_eq!(runtime.system.nonce(&alice), 4);
+
+		// A block author leaves the extrinsics over the limit out of the block instead.
+		let (block, results) = runtime.author_block((4..9).map(transfer).collect());
+		assert_eq!(results[4], Err(support::DispatchError::Full));
+		assert_eq!(block.extrinsics.len(), 4);
+		assert_eq!(runtime.system.nonce(&alice), 8);
+	}
+
+	#[test]
+	fn fee_must_be_paid() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let treasury = "treasury".to_string();
+		runtime.balances.set_balance(&alice, 10);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		// Alice has exactly enough for the transfer, but the fee is paid first, so the transfer fails.
+		let (_, results) = runtime.author_block(vec![transfer(0)]);
+		assert_eq!(results, vec![Err("Not enough funds.".into())]);
+		assert_eq!(runtime.balances.balance(&alice), 9);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.balances.balance(&treasury), 1);
+
+		// Bob cannot pay any fee, so his extrinsic is not executed at all.
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			bob.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: alice.clone(), amount: 0 }),
+			0,
+		)]);
+		assert_eq!(results, vec![Err(support::DispatchError::CannotPayFee)]);
+		assert_eq!(runtime.system.nonce(&bob), 0);
+
+		// With enough for the fee too, the transfer is executed.
+		runtime.balances.set_balance(&alice, 11);
+		let (_, results) = runtime.author_block(vec![transfer(1)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.balances.balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.balances.balance(&treasury), 2);
+		assert_eq!(runtime.balances.total_issuance(), 12);
+	}
+
+	#[test]
+	fn state_summary() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 50);
+		runtime.balances.reserve(&bob, 20).unwrap();
+		runtime.system.inc_block_number().unwrap();
+		let claim = "Hello, world!".to_string();
+		let context = support::DispatchContext::signed(alice, 1);
+		runtime.proof_of_existence.create_claim(context, claim, String::new()).unwrap();
+
+		let mut summary = String::new();
+		runtime.write_state(&mut summary).unwrap();
+		assert!(summary.contains("Block number: 1"));
+		assert!(summary.contains("alice: free 100, reserved 0"));
+		assert!(summary.contains("bob: free 30, reserved 20"));
+		assert!(summary.contains("Total issuance: 150"));
+		assert!(summary.contains("\"Hello, world!\" owned by alice"));
+		// The derived `Debug` format is still available, and shows everything.
+		assert!(format!("{runtime:?}").contains("Hello, world!"));
+	}
+
+	#[test]
+	fn from_genesis() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100), (bob.clone(), 50)],
+			claims: vec![(bob.clone(), "Hello, world!".to_string())],
+			sudo_key: Some(alice.clone()),
+			..Default::default()
+		});
+
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.balance(&bob), 50);
+		assert_eq!(runtime.balances.total_issuance(), 150);
+		assert_eq!(runtime.proof_of_existence.get_claim(&"Hello, world!".to_string()), Some(&bob));
+		assert_eq!(runtime.proof_of_existence.claim_count_of(&bob), 1);
+		assert_eq!(runtime.sudo.key(), Some(&alice));
+		// Seeding the claims does not emit any events in the first block.
+		runtime.author_block(vec![]);
+		assert!(runtime.system.events().is_empty());
+
+		// An empty genesis is the same as a new runtime.
+		let runtime = Runtime::from_genesis(GenesisConfig::default());
+		assert_eq!(runtime.state_root(), Runtime::new().state_root());
+	}
+
+	#[test]
+	fn fork() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			..Default::default()
+		});
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		runtime.author_block(vec![transfer(0)]);
+		let snapshot = runtime.snapshot();
+
+		// Reverting and replacing a block on the fork does not change the original.
+		let mut fork = runtime.fork();
+		assert_eq!(fork.snapshot(), snapshot);
+		fork.revert_last_block().unwrap();
+		let (block, results) = fork.author_block(vec![transfer(0)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(fork.balances.balance(&bob), 10);
+		assert_eq!(runtime.snapshot(), snapshot);
+		assert_eq!(runtime.system.block_number(), 1);
+
+		// The original still has its own history, and can execute the same block after reverting.
+		runtime.revert_last_block().unwrap();
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.snapshot(), fork.snapshot());
+	}
+
+	#[test]
+	fn revert_last_block() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		assert_eq!(runtime.revert_last_block(), Err("no block to revert"));
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		runtime.author_block(vec![transfer(0)]);
+		let state_root = runtime.state_root();
+		let (block_2, _) = runtime.author_block(vec![transfer(1)]);
+		assert_eq!(runtime.balances.balance(&bob), 20);
+
+		// Reverting the second block takes us back to the state after the first one.
+		assert_eq!(runtime.revert_last_block(), Ok(()));
+		assert_eq!(runtime.system.block_number(), 1);
+		assert_eq!(runtime.balances.balance(&alice), 89);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.system.block_hash(2), None);
+		assert_eq!(runtime.state_root(), state_root);
+
+		// The reverted block can be executed again.
+		assert_eq!(runtime.execute_block(&block_2).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.balances.balance(&bob), 20);
+		assert_eq!(runtime.revert_last_block(), Ok(()));
+		assert_eq!(runtime.revert_last_block(), Ok(()));
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.revert_last_block(), Err("no block to revert"));
+	}
+
+	#[test]
+	fn fees_are_spent_from_the_treasury() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let spend = |amount| {
+			RuntimeCall::sudo(sudo::Call::sudo {
+				call: Box::new(RuntimeCall::treasury(treasury::Call::spend {
+					to: bob.clone(),
+					amount,
+				})),
+			})
+		};
+		// Bob pays a fee for his extrinsic, even though he cannot spend from the treasury.
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			bob.clone(),
+			RuntimeCall::treasury(treasury::Call::spend { to: bob.clone(), amount: 1 }),
+			0,
+		)]);
+		assert_eq!(results, vec![Err("sender must be the sudo key".into())]);
+		assert_eq!(treasury::Pallet::<Runtime>::pot(&runtime.balances), 1);
+
+		// The sudo call of alice pays a fee of 2, so the pot has 3, which is not enough for 4.
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), spend(4), 0),
+			support::Extrinsic::new_signed(alice.clone(), spend(3), 1),
+		]);
+		assert_eq!(results, vec![Err("not enough funds in the pot".into()), Ok(())]);
+		assert_eq!(treasury::Pallet::<Runtime>::pot(&runtime.balances), 2);
+		assert_eq!(runtime.balances.balance(&treasury::Pallet::<Runtime>::account_id()), 2);
+		assert_eq!(runtime.balances.balance(&bob), 102);
+	}
+
+	#[test]
+	fn utility_batches_calls() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer =
+			|amount| RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount });
+		let calls = vec![transfer(10), transfer(1000), transfer(10)];
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::utility(utility::Call::batch { calls }),
+			0,
+		)]);
+		// The batch succeeds, but stops at the second transfer.
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert!(runtime.system.events().iter().any(|record| matches!(
+			&record.event,
+			RuntimeEvent::utility(utility::Event::BatchInterrupted { index: 1, .. })
+		)));
+	}
+
+	#[test]
+	fn utility_batch_all_reverts_on_failure() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer =
+			|amount| RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount });
+		let call = RuntimeCall::utility(utility::Call::batch_all {
+			calls: vec![transfer(10), transfer(1000), transfer(10)],
+		});
+		let fee = types::Balance::from(support::GetWeight::weight(&call));
+		let (_, results) =
+			runtime.author_block(vec![support::Extrinsic::new_signed(alice.clone(), call, 0)]);
+		// The second transfer fails the whole batch, so the first one is undone, and alice only
+		// pays the fee.
+		assert_eq!(results, vec![Err(balances::BalancesError::InsufficientBalance.into())]);
+		assert_eq!(runtime.balances.balance(&alice), 100 - fee);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+	}
+
+	#[test]
+	fn democracy_dispatches_passed_proposals() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: charlie.clone(), amount: 10 });
+		let vote = |index| RuntimeCall::democracy(democracy::Call::vote { index, aye: true });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::democracy(democracy::Call::propose { call: Box::new(transfer) }),
+				0,
+			),
+			support::Extrinsic::new_signed(alice.clone(), vote(0), 1),
+			support::Extrinsic::new_signed(bob.clone(), vote(0), 0),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+
+		// The transfer is dispatched on behalf of alice at the end of the block, after she paid the
+		// fees of her two extrinsics.
+		assert_eq!(runtime.balances.balance(&charlie), 10);
+		assert_eq!(runtime.balances.balance(&alice), 88);
+		assert!(runtime.democracy.proposal(0).is_none());
+		assert!(runtime.system.events().iter().any(|record| matches!(
+			&record.event,
+			RuntimeEvent::democracy(democracy::Event::Executed { index: 0, result: Ok(()) })
+		)));
+	}
+
+	#[test]
+	fn scheduler_dispatches_calls_at_their_block() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 });
+		let (_, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::scheduler(scheduler::Call::schedule { call: Box::new(transfer), when: 3 }),
+			0,
+		)]);
+		assert_eq!(results, vec![Ok(())]);
+
+		// The transfer is not dispatched before block 3.
+		runtime.author_block(vec![]);
+		assert_eq!(runtime.balances.balance(&bob), 0);
+
+		// At the start of block 3, the transfer is dispatched on behalf of alice, who only paid the
+		// fee of scheduling it.
+		runtime.author_block(vec![]);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.balances.balance(&alice), 89);
+		assert!(runtime.system.events().iter().any(|record| matches!(
+			&record.event,
+			RuntimeEvent::scheduler(scheduler::Event::Dispatched { when: 3, result: Ok(()) })
+		)));
+
+		// It is only dispatched once.
+		runtime.author_block(vec![]);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+	}
+
+	#[test]
+	fn session_rotates_validators() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.sudo.initialize_key(alice.clone());
+
+		let set_validators =
+			|validators| RuntimeCall::session(session::Call::set_validators { validators });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(bob.clone(), set_validators(vec![bob.clone()]), 0),
+			support::Extrinsic::new_signed(alice.clone(), set_validators(vec![alice.clone()]), 0),
+		]);
+		assert_eq!(results, vec![Err("sender must be the sudo key".into()), Ok(())]);
+
+		// The queued validators become active when the second session starts, at block 10.
+		for _ in 2..10 {
+			runtime.author_block(vec![]);
+		}
+		assert_eq!(runtime.session.current_index(), 0);
+		assert!(runtime.session.validators().is_empty());
+		runtime.author_block(vec![]);
+		assert_eq!(runtime.session.current_index(), 1);
+		assert_eq!(runtime.session.validators(), [alice]);
+	}
+
+	#[test]
+	fn multisig_dispatches_approved_calls() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let signatories = vec![alice.clone(), bob.clone(), charlie.clone()];
+		let account = multisig::Pallet::<Runtime>::multisig_account(
+			&signatories.iter().cloned().collect(),
+			2,
+		);
+		runtime.balances.set_balance(&account, 100);
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: charlie.clone(), amount: 10 });
+		let approve = || {
+			RuntimeCall::multisig(multisig::Call::approve {
+				signatories: signatories.clone(),
+				threshold: 2,
+				call: Box::new(transfer.clone()),
+			})
+		};
+
+		// The first approval only records the operation.
+		let (_, results) =
+			runtime.author_block(vec![support::Extrinsic::new_signed(alice.clone(), approve(), 0)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.balances.balance(&charlie), 0);
+
+		// The second approval reaches the threshold, and the transfer is made from the account of
+		// the multisig.
+		let (_, results) =
+			runtime.author_block(vec![support::Extrinsic::new_signed(bob.clone(), approve(), 0)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.balances.balance(&charlie), 10);
+		assert_eq!(runtime.balances.balance(&account), 90);
+		assert!(runtime.system.events().iter().any(|record| matches!(
+			&record.event,
+			RuntimeEvent::multisig(multisig::Event::Executed { result: Ok(()), .. })
+		)));
+	}
+
+	#[test]
+	fn tips_are_transferred() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let tip = |amount| RuntimeCall::tips(tips::Call::tip { beneficiary: bob.clone(), amount });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), tip(10), 0),
+			support::Extrinsic::new_signed(alice.clone(), tip(15), 1),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+		assert_eq!(runtime.balances.balance(&bob), 25);
+		assert_eq!(runtime.tips.total_tipped(&bob), 25);
+	}
+
+	#[test]
+	fn auctions_are_settled() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 10);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.balances.set_balance(&charlie, 100);
+		assert_eq!(runtime.uniques.mint(alice.clone(), 7), Ok(()));
+
+		let start = RuntimeCall::auction(auction::Call::start_auction { item: 7, end_block: 2 });
+		let bid = |amount| RuntimeCall::auction(auction::Call::bid { id: 0, amount });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), start, 0),
+			support::Extrinsic::new_signed(bob.clone(), bid(30), 0),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+		assert_eq!(runtime.balances.reserved_balance(&bob), 30);
+
+		// Charlie outbids bob in the last block of the auction, pays alice, and gets the item.
+		let (_, results) =
+			runtime.author_block(vec![support::Extrinsic::new_signed(charlie.clone(), bid(50), 0)]);
+		assert_eq!(results, vec![Ok(())]);
+		assert_eq!(runtime.auction.auction(0), None);
+		// Everyone also paid the fees of their extrinsics.
+		assert_eq!(runtime.balances.balance(&alice), 59);
+		assert_eq!(runtime.balances.account(&bob).reserved, 0);
+		assert_eq!(runtime.balances.balance(&bob), 99);
+		assert_eq!(runtime.balances.balance(&charlie), 49);
+		assert_eq!(runtime.uniques.owner_of(&7), Some(&charlie));
+	}
+
+	#[test]
+	fn lottery_draws_a_winner() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let enter = || RuntimeCall::lottery(lottery::Call::enter { max_price: 5 });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), enter(), 0),
+			support::Extrinsic::new_signed(bob.clone(), enter(), 0),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+		assert_eq!(lottery::Pallet::<Runtime>::pot(&runtime.balances), 10);
+
+		// The winner is drawn at the end of the draw period, and receives the whole pot.
+		for _ in 2..=10 {
+			runtime.author_block(vec![]);
+		}
+		assert_eq!(lottery::Pallet::<Runtime>::pot(&runtime.balances), 0);
+		assert_eq!(runtime.balances.balance(&lottery::Pallet::<Runtime>::account_id()), 0);
+		let balances = (runtime.balances.balance(&alice), runtime.balances.balance(&bob));
+		assert!(balances == (104, 94) || balances == (94, 104));
+	}
+
+	#[test]
+	fn transaction_history() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let transfer = |from: &String, to: &String, amount, nonce| {
+			support::Extrinsic::new_signed(
+				from.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: to.clone(), amount }),
+				nonce,
+			)
+		};
+		let (_, results) = runtime.author_block(vec![
+			transfer(&alice, &bob, 10, 0),
+			transfer(&bob, &alice, 10, 0),
+			transfer(&alice, &bob, 10, 1),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+
+		// An extrinsic whose call fails is still in the history of its caller.
+		let (_, results) = runtime.author_block(vec![transfer(&alice, &bob, 1_000, 2)]);
+		assert!(results[0].is_err());
+
+		assert_eq!(runtime.index.history(&alice), [(1, 0), (1, 2), (2, 0)]);
+		assert_eq!(runtime.index.history(&bob), [(1, 1)]);
+		assert_eq!(runtime.index.history(&"charlie".to_string()), []);
+	}
+
+	#[test]
+	fn replayed_extrinsics_are_not_in_history() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		let (_, results) = runtime.author_block(vec![transfer(0)]);
+		assert_eq!(results, vec![Ok(())]);
+
+		// A replayed extrinsic is left out of the block, so the next extrinsic takes its index.
+		let (block, results) = runtime.fork().author_block(vec![transfer(0), transfer(1)]);
+		assert_eq!(results, vec![Err(support::DispatchError::BadNonce), Ok(())]);
+		assert_eq!(block.extrinsics, vec![transfer(1)]);
+
+		// A block which includes it anyway is rejected.
+		let block = types::Block { extrinsics: vec![transfer(0), transfer(1)], ..block };
+		assert_eq!(runtime.execute_block(&block).err(), Some("Invalid nonce."));
+		assert_eq!(runtime.index.history(&alice), [(1, 0)]);
+
+		let (_, results) = runtime.author_block(vec![transfer(0), transfer(1)]);
+		assert_eq!(results, vec![Err(support::DispatchError::BadNonce), Ok(())]);
+		assert_eq!(runtime.index.history(&alice), [(1, 0), (2, 0)]);
+		assert_eq!(runtime.balances.balance(&bob), 20);
+	}
+
+	#[test]
+	fn assets() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let call = |call| RuntimeCall::assets(call);
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				call(assets::Call::create { asset_id: 0 }),
+				0,
+			),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				call(assets::Call::create { asset_id: 1 }),
+				0,
+			),
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				call(assets::Call::mint { asset_id: 0, who: bob.clone(), amount: 20 }),
+				1,
+			),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				call(assets::Call::transfer { asset_id: 0, to: alice.clone(), amount: 5 }),
+				1,
+			),
+		]);
+		assert_eq!(results, vec![Ok(()); 4]);
+
+		// Only the creator of an asset can mint it, and the native balances only pay the fees.
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				call(assets::Call::mint { asset_id: 1, who: alice.clone(), amount: 20 }),
+				2,
+			),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				call(assets::Call::mint { asset_id: 1, who: alice.clone(), amount: 7 }),
+				2,
+			),
+		]);
+		assert_eq!(results, vec![Err("caller is not the asset admin".into()), Ok(())]);
+		assert_eq!((runtime.assets.balance(&0, &alice), runtime.assets.balance(&0, &bob)), (5, 15));
+		assert_eq!((runtime.assets.balance(&1, &alice), runtime.assets.balance(&1, &bob)), (7, 0));
+		assert_eq!(runtime.balances.balance(&alice), 97);
+		assert_eq!(runtime.balances.balance(&bob), 97);
+	}
+
+	#[test]
+	fn events_record_their_extrinsic() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		let (_, results) = runtime.author_block(vec![transfer(0), transfer(1)]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+
+		// Each transfer emits a fee event and a transfer event, tagged with its index in the block.
+		let transfers = runtime
+			.system
+			.events()
+			.iter()
+			.filter(|record| {
+				matches!(record.event, RuntimeEvent::balances(balances::Event::Transferred { .. }))
+			})
+			.map(|record| record.phase)
+			.collect::<Vec<_>>();
+		assert_eq!(
+			transfers,
+			vec![system::Phase::ApplyExtrinsic(0), system::Phase::ApplyExtrinsic(1)]
+		);
+		assert_eq!(runtime.system.events().len(), 4);
+		assert_eq!(runtime.system.phase(), system::Phase::Finalization);
+	}
+
+	#[test]
+	fn execute_block_returns_events() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		let (block, _) = runtime.clone().author_block(vec![transfer(0), transfer(1)]);
+		let events = runtime.execute_block(&block).expect("the block is valid");
+
+		// Along with the fee of each transfer, the block emits exactly one event per transfer.
+		let transfers = events
+			.iter()
+			.filter_map(|record| match &record.event {
+				RuntimeEvent::balances(balances::Event::Transferred { amount, .. }) => {
+					Some((record.phase, *amount))
+				},
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			transfers,
+			vec![(system::Phase::ApplyExtrinsic(0), 10), (system::Phase::ApplyExtrinsic(1), 10)]
+		);
+		assert_eq!(events.len(), 4);
+
+		// The same events are kept in the system pallet, so they can still be queried once the next
+		// block has been executed.
+		assert_eq!(runtime.system.events().len(), 4);
+		let (next, _) = runtime.clone().author_block(vec![]);
+		runtime.execute_block(&next).expect("the block is valid");
+		let kept = runtime.system.events_at(1).expect("the block is in the event history");
+		assert_eq!(kept.len(), events.len());
+		assert!(kept.iter().zip(&events).all(|(kept, event)| kept.phase == event.phase));
+	}
+
+	#[test]
+	fn events_of_past_blocks() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			let call = balances::Call::transfer { to: "bob".to_string(), amount: 1 };
+			support::Extrinsic::new_signed(alice.clone(), RuntimeCall::balances(call), nonce)
+		};
+		runtime.author_block(vec![transfer(0)]);
+		let first_block_events = runtime.system.events().len();
+		assert!(first_block_events > 0);
+
+		// The events of the first block can still be queried while it is in the history window.
+		for nonce in 1..=<crate::EventHistoryDepth as support::Get<u32>>::get() {
+			runtime.author_block(vec![transfer(nonce)]);
+		}
+		assert_eq!(runtime.system.events_at(1).map(Vec::len), Some(first_block_events));
+
+		// Once the window has passed, they are pruned.
+		runtime.author_block(vec![]);
+		assert!(runtime.system.events_at(1).is_none());
+		assert!(runtime.system.events_at(2).is_some());
+	}
+
+	#[test]
+	fn snapshot_of_fixed_blocks() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			sudo_key: Some(alice.clone()),
+			..Default::default()
+		});
+
+		runtime.author_block(vec![
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 }),
+				0,
+			),
+			support::Extrinsic::new_signed(
+				bob.clone(),
+				RuntimeCall::staking(staking::Call::bond { amount: 10 }),
+				0,
+			),
+		]);
+		runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: "Hello, world!".to_string(),
+				metadata: "The first program.".to_string(),
+			}),
+			1,
+		)]);
+
+		// The fees of the three extrinsics were 1, 1 and 5, and were all paid to the treasury.
+		let expected = [
+			"system.block_number = 2",
+			"system.nonce[\"alice\"] = 2",
+			"system.nonce[\"bob\"] = 1",
+			"timestamp.now = 0",
+			"timestamp.did_update = false",
+			"balances.free[\"alice\"] = 64",
+			"balances.free[\"bob\"] = 19",
+			"balances.free[\"treasury\"] = 7",
+			"balances.reserved[\"bob\"] = 10",
+			"balances.total_issuance = 100",
+			"proof_of_existence.claims[\"Hello, world!\"] = (\"alice\", 2)",
+			"proof_of_existence.metadata[\"Hello, world!\"] = \"The first program.\"",
+			"proof_of_existence.claim_count[\"alice\"] = 1",
+			"sudo.key = Some(\"alice\")",
+			"staking.bonded[\"bob\"] = 10",
+			"democracy.next_index = 0",
+			"uniques.locked = {}",
+			"escrow.next_id = 0",
+			"session.validators = []",
+			"session.queued = None",
+			"session.current_index = 0",
+			"session.session_start = 0",
+			"auction.next_id = 0",
+			"lottery.participants = []",
+			"lottery.last_draw = 0",
+			"index.history[\"alice\"] = [(1, 0), (2, 0)]",
+			"index.history[\"bob\"] = [(1, 1)]",
+		];
+		assert_eq!(runtime.snapshot().lines().collect::<Vec<_>>(), expected);
+	}
+
+	#[test]
+	fn migrate_account() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		let dave = "dave".to_string();
+		let claim = "Hello, world!".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100), (charlie.clone(), 20)],
+			claims: vec![(alice.clone(), claim.clone())],
+			sudo_key: Some(alice.clone()),
+			..Default::default()
+		});
+		runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: charlie.clone(), amount: 5 }),
+			0,
+		)]);
+		// Alice has some state in every pallet which keeps state about accounts.
+		assert_eq!(runtime.balances.approve(alice.clone(), charlie.clone(), 10), Ok(()));
+		assert_eq!(runtime.identity.set_identity(alice.clone(), "Alice".to_string()), Ok(()));
+		assert_eq!(runtime.uniques.mint(alice.clone(), 1), Ok(()));
+		assert_eq!(runtime.uniques.mint(alice.clone(), 2), Ok(()));
+		assert_eq!(runtime.assets.create(alice.clone(), 7), Ok(()));
+		assert_eq!(runtime.assets.mint(alice.clone(), 7, alice.clone(), 50), Ok(()));
+		assert_eq!(tips::Pallet::tip(&mut runtime, charlie.clone(), alice.clone(), 5), Ok(()));
+		assert_eq!(runtime.session.set_validators(vec![alice.clone(), charlie.clone()]), Ok(()));
+		assert_eq!(lottery::Pallet::enter(&mut runtime, alice.clone(), 5), Ok(()));
+		assert_eq!(
+			escrow::Pallet::lock(&mut runtime, charlie.clone(), alice.clone(), 5, 10),
+			Ok(())
+		);
+		runtime.system.freeze(alice.clone());
+		let before = runtime.balances.account(&alice);
+
+		// An account which already exists cannot be overwritten, even if it only has an identity.
+		assert_eq!(
+			runtime.migrate_account(alice.clone(), charlie.clone()),
+			Err("account already exists")
+		);
+		assert_eq!(runtime.identity.set_identity(dave.clone(), "Dave".to_string()), Ok(()));
+		assert_eq!(runtime.migrate_account(alice.clone(), dave), Err("account already exists"));
+
+		// Reserved and locked balance belongs to other pallets, so it cannot be moved.
+		runtime.balances.reserve(&alice, 30).unwrap();
+		assert_eq!(
+			runtime.migrate_account(alice.clone(), bob.clone()),
+			Err("account has reserved or locked balance")
+		);
+		runtime.balances.unreserve(&alice, 30);
+		runtime.balances.set_lock(*b"testlock", &alice, 20);
+		assert_eq!(
+			runtime.migrate_account(alice.clone(), bob.clone()),
+			Err("account has reserved or locked balance")
+		);
+		runtime.balances.remove_lock(*b"testlock", &alice);
+		assert_eq!(runtime.balances.account(&alice), before);
+		// So are locked items.
+		assert_eq!(runtime.uniques.lock(&alice, &2), Ok(()));
+		assert_eq!(
+			runtime.migrate_account(alice.clone(), bob.clone()),
+			Err("account has locked items")
+		);
+		runtime.uniques.unlock(&2);
+
+		let snapshot = runtime.snapshot();
+		assert_eq!(runtime.migrate_account(alice.clone(), bob.clone()), Ok(()));
+		// Nothing is left of alice in any pallet: the state is the same, with bob instead of alice.
+		let expected = snapshot.replace("\"alice\"", "\"bob\"");
+		let sorted = |snapshot: &str| snapshot.lines().map(str::to_string).collect::<BTreeSet<_>>();
+		assert_eq!(sorted(&runtime.snapshot()), sorted(&expected));
+		assert_eq!(runtime.balances.account(&bob), before);
+		assert_eq!(runtime.balances.account(&alice), balances::AccountData::default());
+		assert_eq!(runtime.balances.allowance(&bob, &charlie), 10);
+		assert_eq!(runtime.system.nonce(&bob), 1);
+		assert!(runtime.system.is_frozen(&bob));
+		assert_eq!(runtime.proof_of_existence.get_claim(&claim), Some(&bob));
+		assert_eq!(runtime.proof_of_existence.claim_block(&claim), Some(0));
+		assert_eq!(runtime.proof_of_existence.claim_count_of(&bob), 1);
+		assert_eq!(runtime.identity.identity_of(&bob), Some("Alice".to_string()));
+		assert_eq!(runtime.uniques.items_of(&bob).collect::<Vec<_>>(), [&1, &2]);
+		assert_eq!(runtime.assets.balance(&7, &bob), 50);
+		assert_eq!(runtime.assets.asset(&7).map(|details| &details.admin), Some(&bob));
+		assert_eq!(runtime.tips.total_tipped(&bob), 5);
+		assert_eq!(runtime.index.history(&bob), [(1, 0)]);
+		assert_eq!(runtime.session.queued_validators(), Some(&[bob.clone(), charlie][..]));
+		assert_eq!(runtime.sudo.key(), Some(&bob));
+		assert_eq!(runtime.escrow.escrow(0).map(|escrow| &escrow.beneficiary), Some(&bob));
+		assert_eq!(runtime.lottery.participants(), [bob]);
+	}
+
+	#[test]
+	fn errors_are_unified() {
+		use support::{Dispatch, DispatchError};
+
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let context = |caller: &String| support::DispatchContext::signed(caller.clone(), 1);
+		let claim = "Hello, world!".to_string();
+		let create_claim =
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: claim.clone(),
+				metadata: String::new(),
+			});
+		runtime.sudo.initialize_key(alice.clone());
+		assert_eq!(runtime.dispatch(context(&alice), create_claim.clone()), Ok(()));
+
+		// The errors of every pallet, including the typed errors of the Balances Module, are the
+		// same `DispatchError`.
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: alice.clone(), amount: 10 });
+		assert!(matches!(
+			runtime.dispatch(context(&bob), transfer),
+			Err(DispatchError::Other("Not enough funds."))
+		));
+		assert!(matches!(
+			runtime.dispatch(context(&bob), create_claim),
+			Err(DispatchError::Other("this content is already claimed"))
+		));
+		let revoke_claim =
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::revoke_claim { claim });
+		assert!(matches!(
+			runtime.dispatch(context(&bob), revoke_claim),
+			Err(DispatchError::Other("this content is owned by someone else"))
+		));
+		let set_key = RuntimeCall::sudo(sudo::Call::set_key { new: bob.clone() });
+		assert!(matches!(
+			runtime.dispatch(context(&bob), set_key),
+			Err(DispatchError::Other("sender must be the sudo key"))
+		));
+	}
+
+	#[test]
+	fn block_builder() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&bob, 100);
+
+		let transfer = |to: &String| {
+			RuntimeCall::balances(balances::Call::transfer { to: to.clone(), amount: 10 })
+		};
+		let builder = support::BlockBuilder::new()
+			.push_extrinsic(alice.clone(), transfer(&bob))
+			.push_extrinsic(bob.clone(), transfer(&alice))
+			.push_extrinsic(alice.clone(), transfer(&bob));
+		let block: types::Block = builder.clone().build();
+		assert_eq!(block.extrinsics.iter().map(|e| e.nonce).collect::<Vec<_>>(), vec![0, 0, 1]);
+		assert_eq!(runtime.validate_block(&block), Ok(()));
+
+		// The state root is only known after executing the block, so we take it from a copy of the
+		// runtime which authors the same extrinsics.
+		let (authored, _) = runtime.clone().author_block(block.extrinsics);
+		let block = builder.state_root(authored.header.state_root).build();
+		assert_eq!(block, authored);
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(6));
+		assert_eq!(runtime.balances.balance(&bob), 109);
+		assert_eq!(runtime.system.nonce(&alice), 2);
+	}
+
+	#[test]
+	fn block_number_overflow() {
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			block_number: types::BlockNumber::MAX,
+			..Default::default()
+		});
+
+		// There is no block after the last block number, and wrapping around is not allowed.
+		let block = support::BlockBuilder::new().block_number(0).build();
+		assert_eq!(runtime.validate_block(&block), Err("block number overflow".to_string()));
+		assert_eq!(runtime.execute_block(&block).err(), Some("block number overflow"));
+		assert_eq!(runtime.system.block_number(), types::BlockNumber::MAX);
+	}
+
+	#[test]
+	fn genesis_at_nonzero_block_number() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			block_number: 99,
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![(alice.clone(), "Hello, world!".to_string())],
+			..Default::default()
+		});
+		assert_eq!(runtime.system.block_number(), 99);
+		let claim = "Hello, world!".to_string();
+		assert_eq!(runtime.proof_of_existence.claim_block(&claim), Some(99));
+
+		// The block numbers are still checked relative to the genesis block number, and a block
+		// which is rejected changes nothing.
+		let before = runtime.snapshot();
+		let block = |block_number| support::BlockBuilder::new().block_number(block_number).build();
+		assert_eq!(runtime.execute_block(&block(1)).err(), Some("block number mismatch"));
+		assert_eq!(runtime.execute_block(&block(101)).err(), Some("block number mismatch"));
+		assert_eq!(runtime.snapshot(), before);
+		assert_eq!(runtime.system.block_number(), 99);
+
+		let (block, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+			0,
+		)]);
+		assert_eq!(block.header.block_number, 100);
+		assert_eq!(results, vec![Ok(())]);
+		let (block, _) = runtime.author_block(vec![]);
+		assert_eq!(block.header.block_number, 101);
+		assert_eq!(runtime.system.block_number(), 101);
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.proof_of_existence.get_claim(&claim), Some(&alice));
+	}
+
+	#[test]
+	fn validate_block_checks_nonce_order() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |nonce| {
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 }),
+				nonce,
+			)
+		};
+		// Author the block on a copy, so the original runtime can still import it.
+		let (block, results) = runtime.clone().author_block(vec![transfer(0), transfer(1)]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+		assert_eq!(runtime.validate_block(&block), Ok(()));
+
+		// Each transfer is valid on its own, but not in the wrong order.
+		let mut reordered = block.clone();
+		reordered.extrinsics.reverse();
+		assert_eq!(
+			runtime.validate_block(&reordered),
+			Err("extrinsic 0: Invalid nonce.".to_string())
+		);
+		let mut replayed = block.clone();
+		replayed.extrinsics[1] = transfer(0);
+		assert_eq!(
+			runtime.validate_block(&replayed),
+			Err("extrinsic 1: duplicate extrinsic".to_string())
+		);
+		let mut forged = block.clone();
+		forged.extrinsics[1].signature = None;
+		assert_eq!(
+			runtime.validate_block(&forged),
+			Err("extrinsic 1: Invalid signature.".to_string())
+		);
+		let mut heavy = block.clone();
+		let transfers = vec![(bob.clone(), 1); 9];
+		let batch = RuntimeCall::balances(balances::Call::transfer_batch { transfers });
+		heavy.extrinsics.push(support::Extrinsic::new_signed(alice.clone(), batch, 2));
+		assert_eq!(
+			runtime.validate_block(&heavy),
+			Err("extrinsic 2: block weight exceeded".to_string())
+		);
+		let mut full = block.clone();
+		full.extrinsics.extend((2..5).map(transfer));
+		assert_eq!(runtime.validate_block(&full), Err("too many extrinsics".to_string()));
+
+		// Validating does not change the state, so the valid block can still be executed.
+		assert_eq!(runtime.system.block_number(), 0);
+		assert_eq!(runtime.system.nonce(&alice), 0);
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(4));
+		assert_eq!(runtime.balances.balance(&bob), 20);
+		assert_eq!(runtime.validate_block(&block), Err("block number mismatch".to_string()));
+	}
+
+	#[test]
+	fn duplicate_extrinsics() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let call = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 });
+		let transfer = support::Extrinsic::new_signed(alice.clone(), call, 0);
+
+		// A block author leaves the duplicate out of the block.
+		let (block, results) =
+			runtime.clone().author_block(vec![transfer.clone(), transfer.clone()]);
+		assert_eq!(results, vec![Ok(()), Err(support::DispatchError::Duplicate)]);
+		assert_eq!(block.extrinsics, vec![transfer.clone()]);
+
+		// A block which includes it anyway is invalid, and the duplicate is never executed.
+		let mut duplicated = block.clone();
+		duplicated.extrinsics.push(transfer);
+		assert_eq!(
+			runtime.validate_block(&duplicated),
+			Err("extrinsic 1: duplicate extrinsic".to_string())
+		);
+		assert_eq!(runtime.clone().execute_block(&duplicated).err(), Some("duplicate extrinsic"));
+
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.balances.balance(&bob), 10);
+	}
+
+	#[test]
+	fn execute_block_by_reference() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut author = Runtime::new();
+		author.balances.set_balance(&alice, 100);
+		let mut runtime = author.clone();
+
+		let call = RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 10 });
+		let (block, _) = author.author_block(vec![support::Extrinsic::new_signed(alice, call, 0)]);
+
+		// The same block is validated, executed, and executed again after reverting it.
+		assert_eq!(runtime.validate_block(&block), Ok(()));
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.revert_last_block(), Ok(()));
+		assert_eq!(runtime.balances.balance(&bob), 0);
+		assert_eq!(runtime.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(runtime.balances.balance(&bob), 10);
+		assert_eq!(runtime.state_root(), author.state_root());
+	}
+
+	#[test]
+	#[cfg(feature = "codec")]
+	fn encode_and_decode_state() {
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let mut runtime = Runtime::from_genesis(GenesisConfig {
+			balances: vec![(alice.clone(), 100)],
+			claims: vec![(bob.clone(), "Hello, world!".to_string())],
+			sudo_key: Some(alice.clone()),
+			..Default::default()
+		});
+
+		// Change the storage of a few pallets, including a call scheduled for a later block.
+		let transfer =
+			RuntimeCall::balances(balances::Call::transfer { to: bob.clone(), amount: 30 });
+		let schedule =
+			RuntimeCall::scheduler(scheduler::Call::schedule { call: Box::new(transfer), when: 3 });
+		let (_, results) = runtime.author_block(vec![
+			support::Extrinsic::new_signed(alice.clone(), schedule, 0),
+			support::Extrinsic::new_signed(
+				alice.clone(),
+				RuntimeCall::staking(staking::Call::bond { amount: 10 }),
+				1,
+			),
+		]);
+		assert_eq!(results, vec![Ok(()), Ok(())]);
+		runtime.author_block(vec![]);
+
+		let bytes = runtime.encode_state();
+		let mut decoded = Runtime::decode_state(&bytes).unwrap();
+		assert_eq!(decoded.snapshot(), runtime.snapshot());
+		assert_eq!(decoded.state_root(), runtime.state_root());
+		assert_eq!(decoded.encode_state(), bytes);
+
+		// The decoded runtime carries on from the same state: it knows the hash of the last block,
+		// and dispatches the scheduled call.
+		let (block, _) = runtime.author_block(vec![]);
+		assert_eq!(decoded.execute_block(&block).map(|events| events.len()), Ok(2));
+		assert_eq!(decoded.balances.balance(&bob), 30);
+		assert_eq!(decoded.state_root(), runtime.state_root());
+
+		// Bytes which are cut short, or have more after the state, are rejected.
+		let truncated = &bytes[..bytes.len() - 1];
+		assert!(matches!(Runtime::decode_state(truncated), Err("invalid state encoding")));
+		let mut extended = bytes.clone();
+		extended.push(0);
+		assert!(matches!(Runtime::decode_state(&extended), Err("invalid state encoding")));
+	}
+
+	// A logger which keeps the records emitted by the runtime, so tests can check them.
+	struct TestLogger(std::sync::Mutex<Vec<(log::Level, String)>>);
+
+	impl log::Log for TestLogger {
+		fn enabled(&self, _metadata: &log::Metadata) -> bool {
+			true
+		}
+
+		fn log(&self, record: &log::Record) {
+			self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+		}
+
+		fn flush(&self) {}
+	}
+
+	static LOGGER: TestLogger = TestLogger(std::sync::Mutex::new(Vec::new()));
+
+	#[test]
+	fn extrinsics_are_logged() {
+		// Other tests run at the same time and log their extrinsics too, so we use callers which are
+		// unique to this test, and only look at their records.
+		let _ = log::set_logger(&LOGGER);
+		log::set_max_level(log::LevelFilter::Info);
+
+		let mut runtime = Runtime::new();
+		let alice = "logged-alice".to_string();
+		let bob = "logged-bob".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		let transfer = |caller: &String, nonce| {
+			support::Extrinsic::new_signed(
+				caller.clone(),
+				RuntimeCall::balances(balances::Call::transfer {
+					to: "charlie".to_string(),
+					amount: 10,
 				}),
-			},
-		],
-	};
+				nonce,
+			)
+		};
+		// The failed extrinsic does not stop the block, but is left out of it.
+		let (_, results) = runtime.author_block(vec![transfer(&bob, 0), transfer(&alice, 0)]);
+		assert_eq!(results, vec![Err(support::DispatchError::CannotPayFee), Ok(())]);
+
+		let records = LOGGER
+			.0
+			.lock()
+			.unwrap()
+			.iter()
+			.filter(|(_, message)| message.contains("logged-"))
+			.cloned()
+			.collect::<Vec<_>>();
+		assert_eq!(records.len(), 2);
+		assert_eq!(records[0].0, log::Level::Warn);
+		assert!(records[0].1.contains("Caller: \"logged-bob\""));
+		assert!(records[0].1.contains("Call: balances::transfer"));
+		assert!(records[0].1.contains("Error: cannot pay fee"));
+		assert_eq!(records[1].0, log::Level::Info);
+		assert!(records[1].1.contains("Extrinsic Number: 0"));
+		assert!(records[1].1.contains("Caller: \"logged-alice\""));
+		assert!(records[1].1.contains("Call: balances::transfer"));
+	}
+
+	#[test]
+	fn calls_are_named() {
+		use support::GetCallName;
+
+		let call =
+			RuntimeCall::balances(balances::Call::transfer { to: "bob".to_string(), amount: 1 });
+		assert_eq!((call.pallet_name(), call.call_name()), ("balances", "transfer"));
+		let call = RuntimeCall::utility(utility::Call::batch { calls: vec![call] });
+		assert_eq!((call.pallet_name(), call.call_name()), ("utility", "batch"));
+	}
 
-	// Execute the extrinsics which make up our blocks.
-	// If there are any errors, our system panics, since we should not execute invalid blocks.
-	runtime.execute_block(block_1).expect("invalid block");
-	runtime.execute_block(block_2).expect("invalid block");
-	runtime.execute_block(block_3).expect("invalid block");
+	#[test]
+	fn claims_record_the_block_of_their_context() {
+		let mut runtime = Runtime::new();
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.author_block(vec![]);
+		runtime.author_block(vec![]);
 
-	// Simply print the debug format of our runtime state.
-	println!("{:#?}", runtime);
+		let create_claim = |claim: &str| {
+			RuntimeCall::proof_of_existence(proof_of_existence::Call::create_claim {
+				claim: claim.to_string(),
+				metadata: String::new(),
+			})
+		};
+		// The runtime passes the number of the block being executed to the pallet, including to the
+		// calls dispatched by other calls.
+		let batch = RuntimeCall::utility(utility::Call::batch { calls: vec![create_claim("b")] });
+		let (block, results) = runtime.author_block(vec![support::Extrinsic::new_signed(
+			alice.clone(),
+			create_claim("a"),
+			0,
+		)]);
+		assert_eq!((block.header.block_number, results), (3, vec![Ok(())]));
+		let (block, results) =
+			runtime.author_block(vec![support::Extrinsic::new_signed(alice.clone(), batch, 1)]);
+		assert_eq!((block.header.block_number, results), (4, vec![Ok(())]));
+		assert_eq!(runtime.proof_of_existence.claim_block(&"a".to_string()), Some(3));
+		assert_eq!(runtime.proof_of_existence.claim_block(&"b".to_string()), Some(4));
+	}
 }
@@ -0,0 +1,416 @@
+use crate::support::{
+	self, write_snapshot_map, Dispatch, DispatchContext, DispatchError, DispatchResult, Encode,
+	GetWeight, Hooks, DEFAULT_WEIGHT,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The configuration trait for the Multisig Module.
+pub trait Config: crate::system::Config {
+	/// The overarching call type of the runtime, which can be approved by a set of signatories.
+	type RuntimeCall;
+	/// How the id of a multisig is turned into the account which makes the calls its signatories
+	/// approve.
+	type MultisigAccount: MultisigAccount<Self::AccountId>;
+}
+
+/// The id of a multisig, which is a hash of its signatories and its threshold. See
+/// `Pallet::multisig_account`.
+pub type MultisigId = u64;
+
+/// A way to turn the id of a multisig into its account, so the same signatories and threshold
+/// always act as the same account. See `Config::MultisigAccount`.
+pub trait MultisigAccount<AccountId> {
+	/// Get the account of the multisig `id`.
+	fn multisig_account(id: MultisigId) -> AccountId;
+}
+
+/// The id of a multisig operation, which is a hash of its call, its signatories and its threshold.
+/// See `Pallet::operation_id`.
+pub type OperationId = u64;
+
+/// A call which is waiting for the approval of enough of its signatories.
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+// The approvals are a set, so the accounts must be ordered to load them.
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(bound(
+		deserialize = "AccountId: Ord + serde::Deserialize<'de>, Call: serde::Deserialize<'de>"
+	))
+)]
+pub struct Operation<AccountId, Call> {
+	/// The call which is dispatched once `threshold` signatories approve it.
+	pub call: Call,
+	/// The number of approvals needed before the call is dispatched.
+	pub threshold: u32,
+	/// The signatories which have approved the call so far.
+	pub approvals: BTreeSet<AccountId>,
+}
+
+/// A multisig operation of the runtime `T`.
+pub type OperationOf<T> =
+	Operation<<T as crate::system::Config>::AccountId, <T as Config>::RuntimeCall>;
+
+/// The events which can be emitted by the Multisig Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `who` approved the operation `id`, which now has `approvals` approvals.
+	Approved { id: OperationId, who: T::AccountId, approvals: u32 },
+	/// The operation `id` reached its threshold, and its call was dispatched with the given
+	/// `result`.
+	Executed { id: OperationId, result: DispatchResult },
+}
+
+/// This is the Multisig Module.
+/// It allows a set of signatories to collectively authorize a call, which is dispatched once a
+/// threshold of them approve it. The call is made from the account of the multisig, which is
+/// derived from the signatories and the threshold, so no single signatory can spend its funds.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from the id of an operation to the operation, for the operations which have
+	/// not yet reached their threshold.
+	operations: BTreeMap<OperationId, Operation<T::AccountId, T::RuntimeCall>>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Multisig Module.
+	pub fn new() -> Self {
+		Self { operations: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Multisig Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::RuntimeCall: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "multisig", "operations", &self.operations)
+	}
+
+	/// Get the account which makes the calls approved by `threshold` of the `signatories`. The
+	/// order of the signatories does not matter.
+	pub fn multisig_account(signatories: &BTreeSet<T::AccountId>, threshold: u32) -> T::AccountId
+	where
+		T::AccountId: Encode,
+	{
+		T::MultisigAccount::multisig_account(support::hash(&(signatories, threshold)))
+	}
+
+	/// Get the id of the operation which dispatches `call` once `threshold` of the `signatories`
+	/// approve it. The order of the signatories does not matter.
+	pub fn operation_id(
+		signatories: &BTreeSet<T::AccountId>,
+		threshold: u32,
+		call: &T::RuntimeCall,
+	) -> OperationId
+	where
+		T::AccountId: Encode,
+		T::RuntimeCall: Encode,
+	{
+		support::hash(&(signatories, threshold, call))
+	}
+
+	/// Get the operation `id`, if it has not yet reached its threshold.
+	pub fn operation(&self, id: OperationId) -> Option<&OperationOf<T>> {
+		self.operations.get(&id)
+	}
+
+	// Record the approval of the operation `id` by `who`, creating the operation if this is its
+	// first approval. Returns the operation once it has reached its threshold, removing it from
+	// storage.
+	fn record_approval(
+		&mut self,
+		id: OperationId,
+		who: T::AccountId,
+		threshold: u32,
+		call: T::RuntimeCall,
+	) -> Result<Option<OperationOf<T>>, DispatchError> {
+		let operation = self.operations.entry(id).or_insert_with(|| Operation {
+			call,
+			threshold,
+			approvals: BTreeSet::new(),
+		});
+		// The same signatory approving twice must not count towards the threshold again.
+		if !operation.approvals.insert(who.clone()) {
+			return Err("already approved".into());
+		}
+
+		let approvals = operation.approvals.len() as u32;
+		let reached = approvals >= operation.threshold;
+		self.deposit_event(Event::Approved { id, who, approvals });
+		Ok(if reached { self.operations.remove(&id) } else { None })
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Our calls need access to the whole runtime in order to dispatch the approved calls. Any approval
+// may be the one which dispatches the call, so it is only a little heavier than the call it
+// approves.
+#[macros::call(
+	R: Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	T::AccountId: Encode,
+	T::RuntimeCall: Encode + GetWeight,
+)]
+impl<T: Config> Pallet<T> {
+	/// Approve that `call` is dispatched once `threshold` of the `signatories` approve it. Every
+	/// approval must be made with the same signatories, threshold and call, which together
+	/// identify the operation. See `operation_id`.
+	///
+	/// The first approval creates the operation. The approval which reaches the threshold
+	/// dispatches the call straight away, from the account of the multisig, and its result is
+	/// reported with an `Executed` event, so the approval itself still succeeds. See
+	/// `multisig_account`.
+	///
+	/// This function will return an error if the caller is not one of the signatories, if the
+	/// signatories have a duplicate, if the threshold is zero or more than the number of
+	/// signatories, or if the caller has already approved the operation.
+	#[weight(DEFAULT_WEIGHT.saturating_add(call.weight()))]
+	pub fn approve<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		signatories: Vec<T::AccountId>,
+		threshold: u32,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult
+	where
+		T::AccountId: Encode,
+		T::RuntimeCall: Encode,
+		R: AsMut<Self>
+			+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		let count = signatories.len();
+		let signatories = signatories.into_iter().collect::<BTreeSet<_>>();
+		if signatories.len() != count {
+			return Err("duplicate signatory".into());
+		}
+		if threshold == 0 || threshold as usize > signatories.len() {
+			return Err("invalid threshold".into());
+		}
+		if !signatories.contains(&caller) {
+			return Err("caller is not a signatory".into());
+		}
+
+		let id = Self::operation_id(&signatories, threshold, &call);
+		let multisig = runtime.as_mut();
+		if let Some(operation) = multisig.record_approval(id, caller, threshold, *call)? {
+			let account = Self::multisig_account(&signatories, threshold);
+			let context = DispatchContext::signed(account, block_number);
+			let result = runtime.dispatch(context, operation.call);
+			runtime.as_mut().deposit_event(Event::Executed { id, result });
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::support::{
+		Dispatch, DispatchCall, DispatchContext, DispatchError, DispatchResult, GetWeight, Weight,
+	};
+
+	// A minimal runtime which only contains the multisig pallet, and can dispatch `TestCall`s.
+	struct TestRuntime {
+		multisig: super::Pallet<TestRuntime>,
+		// Every caller of a successfully dispatched `TestCall`.
+		dispatched: Vec<String>,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+	enum TestCall {
+		Succeed(u32),
+		Fail,
+	}
+
+	impl GetWeight for TestCall {
+		fn weight(&self) -> Weight {
+			10
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	// The account of a multisig is named after its id.
+	struct MultisigAccount;
+	impl super::MultisigAccount<String> for MultisigAccount {
+		fn multisig_account(id: super::MultisigId) -> String {
+			format!("multisig {id}")
+		}
+	}
+
+	impl super::Config for TestRuntime {
+		type RuntimeCall = TestCall;
+		type MultisigAccount = MultisigAccount;
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.multisig
+		}
+	}
+
+	impl Dispatch for TestRuntime {
+		type Caller = String;
+		type BlockNumber = u32;
+		type Call = TestCall;
+
+		fn dispatch(
+			&mut self,
+			context: DispatchContext<String, u32>,
+			call: TestCall,
+		) -> DispatchResult {
+			match call {
+				TestCall::Succeed(_) => {
+					self.dispatched.push(context.origin.ensure_signed()?);
+					Ok(())
+				},
+				TestCall::Fail => Err("test call failed".into()),
+			}
+		}
+	}
+
+	fn new_runtime() -> TestRuntime {
+		TestRuntime { multisig: super::Pallet::new(), dispatched: Vec::new() }
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	fn signatories(names: &[&str]) -> Vec<String> {
+		names.iter().map(|name| name.to_string()).collect()
+	}
+
+	fn approve(names: &[&str], threshold: u32, call: TestCall) -> Call<TestRuntime> {
+		Call::approve { signatories: signatories(names), threshold, call: Box::new(call) }
+	}
+
+	#[test]
+	fn two_of_three_reaches_threshold() {
+		let mut runtime = new_runtime();
+		let call = TestCall::Succeed(1);
+		let set = signatories(&["alice", "bob", "charlie"]).into_iter().collect();
+		let id = super::Pallet::<TestRuntime>::operation_id(&set, 2, &call);
+		let account = super::Pallet::<TestRuntime>::multisig_account(&set, 2);
+
+		// The first approval creates the operation, but does not dispatch it.
+		let approval = approve(&["alice", "bob", "charlie"], 2, call.clone());
+		assert_eq!(runtime.dispatch_call(context("bob"), approval), Ok(()));
+		assert!(runtime.dispatched.is_empty());
+		let operation = runtime.multisig.operation(id).expect("the operation was created");
+		assert_eq!(operation.approvals.len(), 1);
+
+		// The same signatory approving again is not counted twice.
+		let approval = approve(&["alice", "bob", "charlie"], 2, call.clone());
+		assert_eq!(runtime.dispatch_call(context("bob"), approval), Err("already approved".into()));
+		assert!(runtime.dispatched.is_empty());
+		assert_eq!(runtime.multisig.operation(id).map(|op| op.approvals.len()), Some(1));
+
+		// The second signatory reaches the threshold, even with the signatories in another order,
+		// and the call is dispatched from the account of the multisig.
+		let approval = approve(&["charlie", "alice", "bob"], 2, call);
+		assert_eq!(runtime.dispatch_call(context("charlie"), approval), Ok(()));
+		assert_eq!(runtime.dispatched, [account]);
+		assert_eq!(runtime.multisig.operation(id), None);
+		let events = runtime.multisig.take_events();
+		assert!(matches!(
+			&events[..],
+			[
+				Event::Approved { approvals: 1, .. },
+				Event::Approved { approvals: 2, .. },
+				Event::Executed { result: Ok(()), .. },
+			]
+		));
+	}
+
+	#[test]
+	fn failed_calls_are_reported() {
+		let mut runtime = new_runtime();
+
+		// A threshold of one dispatches the call with the first approval.
+		let approval = approve(&["alice", "bob"], 1, TestCall::Fail);
+		assert_eq!(runtime.dispatch_call(context("alice"), approval), Ok(()));
+		let events = runtime.multisig.take_events();
+		assert!(matches!(
+			events.last(),
+			Some(Event::Executed { result: Err(DispatchError::Other("test call failed")), .. })
+		));
+	}
+
+	#[test]
+	fn multisig_accounts_are_deterministic() {
+		let account = |names: &[&str], threshold| {
+			let set = signatories(names).into_iter().collect();
+			super::Pallet::<TestRuntime>::multisig_account(&set, threshold)
+		};
+
+		// The order of the signatories does not matter, but who they are and the threshold do.
+		assert_eq!(account(&["alice", "bob"], 1), account(&["bob", "alice"], 1));
+		assert_ne!(account(&["alice", "bob"], 1), account(&["alice", "bob"], 2));
+		assert_ne!(account(&["alice", "bob"], 1), account(&["alice", "charlie"], 1));
+	}
+
+	#[test]
+	fn approve_is_checked() {
+		let mut runtime = new_runtime();
+		let call = || TestCall::Succeed(1);
+
+		assert_eq!(
+			runtime.dispatch_call(context("dave"), approve(&["alice", "bob"], 1, call())),
+			Err("caller is not a signatory".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), approve(&["alice", "alice"], 1, call())),
+			Err("duplicate signatory".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), approve(&["alice", "bob"], 0, call())),
+			Err("invalid threshold".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), approve(&["alice", "bob"], 3, call())),
+			Err("invalid threshold".into())
+		);
+
+		// Operations with a different call or threshold are kept apart.
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), approve(&["alice", "bob"], 2, call())),
+			Ok(())
+		);
+		let other = approve(&["alice", "bob"], 2, TestCall::Succeed(2));
+		assert_eq!(runtime.dispatch_call(context("bob"), other), Ok(()));
+		let other = approve(&["alice", "bob", "charlie"], 2, call());
+		assert_eq!(runtime.dispatch_call(context("bob"), other), Ok(()));
+		assert!(runtime.dispatched.is_empty());
+	}
+}
@@ -1,45 +1,281 @@
-use crate::support::DispatchResult;
+use crate::support::{
+	write_snapshot_map, DispatchContext, DispatchError, DispatchResult, Get, Hooks, StorageMap,
+};
 use core::fmt::Debug;
+use num::traits::CheckedAdd;
 use std::collections::BTreeMap;
 
 pub trait Config: crate::system::Config {
 	/// The type which represents the content that can be claimed using this pallet.
 	/// Could be the content directly as bytes, or better yet the hash of that content.
 	/// We leave that decision to the runtime developer.
-	type Content: Debug + Ord;
+	type Content: Debug + Ord + Clone + AsRef<[u8]>;
+	/// The number of blocks a claim is valid for. Once a claim expires, anyone can claim that
+	/// content again.
+	type ClaimValidity: Get<Self::BlockNumber>;
+	/// The maximum number of claims a single account can own at once.
+	type MaxClaims: Get<u32>;
+	/// The maximum length of the content of a claim, in bytes.
+	type MaxContentLen: Get<u32>;
 }
 
+/// The events which can be emitted by the Proof of Existence Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `owner` has claimed `claim`.
+	Created { owner: T::AccountId, claim: T::Content },
+	/// `owner` has revoked their claim on `claim`.
+	Revoked { owner: T::AccountId, claim: T::Content },
+	/// The ownership of `claim` was transferred from `from` to `to`.
+	Transferred { from: T::AccountId, to: T::AccountId, claim: T::Content },
+	/// The claim of `owner` on `claim` has expired, and was removed.
+	Expired { owner: T::AccountId, claim: T::Content },
+}
+
+/// The default storage backend of the claims: an in memory map from content to the owner of that
+/// content, and the block number when the claim was created.
+pub type ClaimMap<T> = BTreeMap<
+	<T as Config>::Content,
+	(<T as crate::system::Config>::AccountId, <T as crate::system::Config>::BlockNumber),
+>;
+
 /// This is the Proof of Existence Module.
 /// It is a simple module that allows accounts to claim existence of some data.
-#[derive(Debug)]
-pub struct Pallet<T: Config> {
-	/// A simple storage map from content to the owner of that content.
+///
+/// The claims are kept in the storage backend `S`, which is a `ClaimMap` by default. See
+/// `StorageMap`.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config, S = ClaimMap<T>> {
+	/// A simple storage map from content to the owner of that content, and the block number when
+	/// the claim was created.
 	/// Accounts can make multiple different claims, but each claim can only have one owner.
-	claims: BTreeMap<T::Content, T::AccountId>,
+	claims: S,
+	/// A storage map from content to a short description of that content, given by its owner.
+	metadata: BTreeMap<T::Content, T::Content>,
+	/// A storage map from an account to the number of claims it owns.
+	claim_count: BTreeMap<T::AccountId, u32>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
 }
 
-impl<T: Config> Pallet<T> {
+impl<T: Config, S: StorageMap<T::Content, (T::AccountId, T::BlockNumber)>> Pallet<T, S> {
 	/// Create a new instance of the Proof of Existence Module.
 	pub fn new() -> Self {
-		Self { claims: BTreeMap::new() }
+		Self {
+			claims: S::default(),
+			metadata: BTreeMap::new(),
+			claim_count: BTreeMap::new(),
+			events: Vec::new(),
+		}
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Proof of Existence Module to a snapshot of the state, one line per
+	/// storage value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "proof_of_existence", "claims", self.claims.iter())?;
+		write_snapshot_map(f, "proof_of_existence", "metadata", &self.metadata)?;
+		write_snapshot_map(f, "proof_of_existence", "claim_count", &self.claim_count)
 	}
 
 	/// Get the owner (if any) of a claim.
 	pub fn get_claim(&self, claim: &T::Content) -> Option<&T::AccountId> {
-		self.claims.get(&claim)
+		self.claims.get(claim).map(|(owner, _)| owner)
+	}
+
+	/// Check if `claim` is owned by exactly `expected_owner`, without changing any state, so a
+	/// client can verify a proof of existence. Claims which do not exist are never verified.
+	pub fn verify_claim(&self, claim: &T::Content, expected_owner: &T::AccountId) -> bool {
+		self.get_claim(claim) == Some(expected_owner)
+	}
+
+	/// Get all of the claims, in order, together with their owners.
+	pub fn claims(&self) -> impl Iterator<Item = (&T::Content, &T::AccountId)> {
+		self.claims.iter().map(|(claim, (owner, _))| (claim, owner))
+	}
+
+	/// Iterate over all of the claims in order, together with their owners and the block number
+	/// when they were created.
+	pub fn iter_claims(
+		&self,
+	) -> impl Iterator<Item = (&T::Content, &T::AccountId, T::BlockNumber)> {
+		self.claims
+			.iter()
+			.map(|(claim, (owner, block_number))| (claim, owner, *block_number))
+	}
+
+	/// Get the block number (if any) when a claim was created.
+	pub fn claim_block(&self, claim: &T::Content) -> Option<T::BlockNumber> {
+		self.claims.get(claim).map(|(_, block_number)| *block_number)
+	}
+
+	/// Check if a claim has expired at block number `now`.
+	/// Claims which do not exist are never expired.
+	pub fn is_expired(&self, claim: &T::Content, now: T::BlockNumber) -> bool {
+		match self.claim_block(claim) {
+			// If the expiry block overflows, the claim can never expire.
+			Some(created) => created
+				.checked_add(&T::ClaimValidity::get())
+				.is_some_and(|expiry| now >= expiry),
+			None => false,
+		}
+	}
+
+	/// Get the metadata (if any) of a claim.
+	pub fn claim_metadata(&self, claim: &T::Content) -> Option<T::Content> {
+		self.metadata.get(claim).cloned()
+	}
+
+	/// Get the total number of claims, across all owners.
+	pub fn claim_count(&self) -> usize {
+		self.claims.iter().count()
+	}
+
+	/// Get the number of claims owned by `who`.
+	pub fn claim_count_of(&self, who: &T::AccountId) -> u32 {
+		*self.claim_count.get(who).unwrap_or(&0)
+	}
+
+	/// Get every account which owns a claim, along with its number of claims, from the most claims
+	/// to the fewest. Accounts with the same number of claims are in account order.
+	pub fn leaderboard(&self) -> Vec<(T::AccountId, u32)> {
+		let mut leaderboard = self
+			.claim_count
+			.iter()
+			.map(|(who, count)| (who.clone(), *count))
+			.collect::<Vec<_>>();
+		// The counts are already in account order, and the sort is stable, so ties keep that order.
+		leaderboard.sort_by(|(_, a), (_, b)| b.cmp(a));
+		leaderboard
+	}
+
+	// Increase the number of claims owned by `who` by one, returning the new number.
+	fn inc_claim_count(&mut self, who: &T::AccountId) -> u32 {
+		let count = self.claim_count_of(who) + 1;
+		self.claim_count.insert(who.clone(), count);
+		count
+	}
+
+	// Decrease the number of claims owned by `who` by one.
+	fn dec_claim_count(&mut self, who: &T::AccountId) {
+		match self.claim_count_of(who) {
+			0 | 1 => self.claim_count.remove(who),
+			count => self.claim_count.insert(who.clone(), count - 1),
+		};
+	}
+
+	/// Create a new claim like `create_claim`, returning the number of claims the caller owns
+	/// afterwards, including the new one.
+	pub fn create_claim_counted(
+		&mut self,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		claim: T::Content,
+		metadata: T::Content,
+	) -> Result<u32, DispatchError> {
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		if claim.as_ref().len() > T::MaxContentLen::get() as usize {
+			return Err("content too long".into());
+		}
+		// An expired claim would be removed at the end of the block anyway, so we do it now.
+		if self.is_expired(&claim, block_number) {
+			self.expire_claim(claim.clone());
+		}
+		if self.claims.get(&claim).is_some() {
+			return Err("this content is already claimed".into());
+		}
+		if self.claim_count_of(&caller) >= T::MaxClaims::get() {
+			return Err("claim limit reached".into());
+		}
+		self.claims.insert(claim.clone(), (caller.clone(), block_number));
+		self.metadata.insert(claim.clone(), metadata);
+		let count = self.inc_claim_count(&caller);
+		self.deposit_event(Event::Created { owner: caller, claim });
+		Ok(count)
+	}
+
+	/// Give all of the claims owned by `from` to `to`, keeping the block numbers they were created
+	/// in. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		let owned = self
+			.claims
+			.iter()
+			.filter(|(_, (owner, _))| owner == from)
+			.map(|(claim, (_, block_number))| (claim.clone(), *block_number))
+			.collect::<Vec<_>>();
+		for (claim, block_number) in owned {
+			self.claims.insert(claim, (to.clone(), block_number));
+		}
+		if let Some(count) = self.claim_count.remove(from) {
+			self.claim_count.insert(to.clone(), self.claim_count_of(to) + count);
+		}
+	}
+
+	// Remove a claim and everything stored about it, returning the owner of the claim.
+	fn remove_claim(&mut self, claim: &T::Content) -> Option<T::AccountId> {
+		let (owner, _) = self.claims.remove(claim)?;
+		self.metadata.remove(claim);
+		self.dec_claim_count(&owner);
+		Some(owner)
+	}
+
+	// Remove a claim because it has expired.
+	fn expire_claim(&mut self, claim: T::Content) {
+		if let Some(owner) = self.remove_claim(&claim) {
+			self.deposit_event(Event::Expired { owner, claim });
+		}
+	}
+}
+
+impl<T: Config, S: StorageMap<T::Content, (T::AccountId, T::BlockNumber)>> Hooks<T::BlockNumber>
+	for Pallet<T, S>
+{
+	// Remove all of the claims which have expired by the end of this block.
+	fn on_finalize(&mut self, block_number: T::BlockNumber) {
+		let expired = self
+			.claims
+			.iter()
+			.map(|(claim, _)| claim)
+			.filter(|claim| self.is_expired(claim, block_number))
+			.cloned()
+			.collect::<Vec<_>>();
+		for claim in expired {
+			self.expire_claim(claim);
+		}
 	}
 }
 
 #[macros::call]
-impl<T: Config> Pallet<T> {
-	/// Create a new claim on behalf of the `caller`.
-	/// This function will return an error if someone already has claimed that content.
-	pub fn create_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
-		if self.claims.contains_key(&claim) {
-			return Err(&"this content is already claimed");
-		}
-		self.claims.insert(claim, caller);
-		Ok(())
+impl<T: Config, S: StorageMap<T::Content, (T::AccountId, T::BlockNumber)>> Pallet<T, S> {
+	/// Create a new claim on behalf of the caller, along with some `metadata` describing it. The
+	/// claim records the block number of the `context` it was created in.
+	/// This function will return an error if someone already has claimed that content, unless
+	/// that claim has expired, or if the caller already owns the maximum number of claims.
+	/// Creating a claim stores both the content and its metadata, so it is heavier than other calls.
+	#[weight(5)]
+	pub fn create_claim(
+		&mut self,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		claim: T::Content,
+		metadata: T::Content,
+	) -> DispatchResult {
+		self.create_claim_counted(context, claim, metadata).map(|_| ())
 	}
 
 	/// Revoke an existing claim on some content.
@@ -48,9 +284,50 @@ impl<T: Config> Pallet<T> {
 	pub fn revoke_claim(&mut self, caller: T::AccountId, claim: T::Content) -> DispatchResult {
 		let owner = self.get_claim(&claim).ok_or("claim does not exist")?;
 		if caller != *owner {
-			return Err(&"this content is owned by someone else");
+			return Err("this content is owned by someone else".into());
+		}
+		self.remove_claim(&claim);
+		self.deposit_event(Event::Revoked { owner: caller, claim });
+		Ok(())
+	}
+
+	/// Revoke an existing claim on some content, no matter who owns it, for example to moderate
+	/// abusive content.
+	/// This is a privileged call, which the runtime only allows privileged origins, like the root
+	/// origin of the sudo key, to make. Unlike `revoke_claim`, nobody needs to own the claim, but
+	/// it will still return an error if the claim does not exist.
+	#[privileged]
+	pub fn force_revoke_claim(&mut self, claim: T::Content) -> DispatchResult {
+		let owner = self.remove_claim(&claim).ok_or("claim does not exist")?;
+		self.deposit_event(Event::Revoked { owner, claim });
+		Ok(())
+	}
+
+	/// Transfer the ownership of an existing claim from `caller` to `new_owner`.
+	/// This function will return an error if the claim does not exist, or if the caller is not the
+	/// owner, or if `new_owner` already owns the maximum number of claims. Transferring a claim to
+	/// yourself is allowed, and changes nothing.
+	pub fn transfer_claim(
+		&mut self,
+		caller: T::AccountId,
+		claim: T::Content,
+		new_owner: T::AccountId,
+	) -> DispatchResult {
+		let owner = self.get_claim(&claim).ok_or("this content is not claimed")?;
+		if caller != *owner {
+			return Err("this content is owned by someone else".into());
 		}
-		self.claims.remove(&claim);
+		if caller != new_owner {
+			if self.claim_count_of(&new_owner) >= T::MaxClaims::get() {
+				return Err("claim limit reached".into());
+			}
+			self.dec_claim_count(&caller);
+			self.inc_claim_count(&new_owner);
+		}
+		if let Some((_, block_number)) = self.claims.remove(&claim) {
+			self.claims.insert(claim.clone(), (new_owner.clone(), block_number));
+		}
+		self.deposit_event(Event::Transferred { from: caller, to: new_owner, claim });
 		Ok(())
 	}
 }
@@ -59,27 +336,340 @@ impl<T: Config> Pallet<T> {
 mod test {
 	struct TestConfig;
 
+	struct ClaimValidity;
+	impl crate::support::Get<u32> for ClaimValidity {
+		fn get() -> u32 {
+			10
+		}
+	}
+
+	struct MaxClaims;
+	impl crate::support::Get<u32> for MaxClaims {
+		fn get() -> u32 {
+			2
+		}
+	}
+
+	struct MaxContentLen;
+	impl crate::support::Get<u32> for MaxContentLen {
+		fn get() -> u32 {
+			16
+		}
+	}
+
 	impl super::Config for TestConfig {
 		type Content = &'static str;
+		type ClaimValidity = ClaimValidity;
+		type MaxClaims = MaxClaims;
+		type MaxContentLen = MaxContentLen;
 	}
 
 	impl crate::system::Config for TestConfig {
 		type AccountId = &'static str;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	// Like `TestConfig`, but an account can own one more claim.
+	struct LargerLimitConfig;
+
+	struct LargerMaxClaims;
+	impl crate::support::Get<u32> for LargerMaxClaims {
+		fn get() -> u32 {
+			3
+		}
+	}
+
+	impl super::Config for LargerLimitConfig {
+		type Content = &'static str;
+		type ClaimValidity = ClaimValidity;
+		type MaxClaims = LargerMaxClaims;
+		type MaxContentLen = MaxContentLen;
+	}
+
+	impl crate::system::Config for LargerLimitConfig {
+		type AccountId = &'static str;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	use crate::support::{DispatchContext, Hooks};
+
+	// The context of a call made by `caller` in the block `block_number`.
+	fn context(caller: &'static str, block_number: u32) -> DispatchContext<&'static str, u32> {
+		DispatchContext::signed(caller, block_number)
 	}
 
 	#[test]
 	fn basic_proof_of_existence() {
 		let mut poe = super::Pallet::<TestConfig>::new();
 		assert_eq!(poe.get_claim(&"Hello, world!"), None);
-		assert_eq!(poe.create_claim(&"alice", &"Hello, world!"), Ok(()));
+		assert_eq!(poe.create_claim(context("alice", 0), "Hello, world!", ""), Ok(()));
 		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
 		assert_eq!(
-			poe.create_claim(&"bob", &"Hello, world!"),
-			Err("this content is already claimed")
+			poe.create_claim(context("bob", 0), "Hello, world!", ""),
+			Err("this content is already claimed".into())
+		);
+		assert_eq!(poe.revoke_claim("alice", "Hello, world!"), Ok(()));
+		assert_eq!(poe.create_claim(context("bob", 0), "Hello, world!", ""), Ok(()));
+		// Two claims were created, and one was revoked.
+		assert_eq!(poe.take_events().len(), 3);
+	}
+
+	#[test]
+	fn transfer_claim() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(
+			poe.transfer_claim("alice", "Hello, world!", "bob"),
+			Err("this content is not claimed".into())
+		);
+
+		assert_eq!(poe.create_claim(context("alice", 0), "Hello, world!", ""), Ok(()));
+		assert_eq!(
+			poe.transfer_claim("bob", "Hello, world!", "bob"),
+			Err("this content is owned by someone else".into())
 		);
-		assert_eq!(poe.revoke_claim(&"alice", &"Hello, world!"), Ok(()));
-		assert_eq!(poe.create_claim(&"bob", &"Hello, world!"), Ok(()));
+
+		// Transferring to yourself succeeds, and changes nothing.
+		assert_eq!(poe.transfer_claim("alice", "Hello, world!", "alice"), Ok(()));
+		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
+
+		assert_eq!(poe.transfer_claim("alice", "Hello, world!", "bob"), Ok(()));
+		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"bob"));
+		// The old owner can no longer revoke the claim, but the new owner can.
+		assert_eq!(
+			poe.revoke_claim("alice", "Hello, world!"),
+			Err("this content is owned by someone else".into())
+		);
+		assert_eq!(poe.revoke_claim("bob", "Hello, world!"), Ok(()));
+	}
+
+	#[test]
+	fn force_revoke_claim() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.force_revoke_claim("Hello, world!"), Err("claim does not exist".into()));
+
+		// The claim of alice can be revoked by anyone who can make the call.
+		assert_eq!(poe.create_claim(context("alice", 0), "Hello, world!", ""), Ok(()));
+		assert_eq!(poe.force_revoke_claim("Hello, world!"), Ok(()));
+		assert_eq!(poe.get_claim(&"Hello, world!"), None);
+		assert_eq!(poe.claim_count_of(&"alice"), 0);
+		assert!(matches!(
+			poe.take_events().last(),
+			Some(super::Event::Revoked { owner: "alice", claim: "Hello, world!" })
+		));
+		assert_eq!(poe.force_revoke_claim("Hello, world!"), Err("claim does not exist".into()));
+	}
+
+	#[test]
+	fn claim_metadata() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.claim_metadata(&"Hello, world!"), None);
+		assert_eq!(
+			poe.create_claim(context("alice", 0), "Hello, world!", "A friendly greeting."),
+			Ok(())
+		);
+		assert_eq!(poe.claim_metadata(&"Hello, world!"), Some("A friendly greeting."));
+
+		// Revoking a claim also removes its metadata.
+		assert_eq!(poe.revoke_claim("alice", "Hello, world!"), Ok(()));
+		assert_eq!(poe.claim_metadata(&"Hello, world!"), None);
+	}
+
+	#[test]
+	fn claim_block() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 1), "Hello, world!", ""), Ok(()));
+		assert_eq!(poe.create_claim(context("bob", 2), "Goodbye, world!", ""), Ok(()));
+
+		assert_eq!(poe.claim_block(&"Hello, world!"), Some(1));
+		assert_eq!(poe.claim_block(&"Goodbye, world!"), Some(2));
+		assert_eq!(poe.claim_block(&"Unknown"), None);
+	}
+
+	#[test]
+	fn claim_expiry() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 1), "Hello, world!", ""), Ok(()));
+		assert!(!poe.is_expired(&"Hello, world!", 10));
+		assert!(poe.is_expired(&"Hello, world!", 11));
+		assert!(!poe.is_expired(&"Unknown", 11));
+
+		// Before the claim expires, no one else can claim it.
+		assert_eq!(
+			poe.create_claim(context("bob", 10), "Hello, world!", ""),
+			Err("this content is already claimed".into())
+		);
+
+		// Once it expires, someone else can claim it.
+		assert_eq!(poe.create_claim(context("bob", 11), "Hello, world!", ""), Ok(()));
+		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"bob"));
+		assert_eq!(poe.claim_block(&"Hello, world!"), Some(11));
+	}
+
+	#[test]
+	fn expired_claims_are_removed() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 1), "Hello, world!", "A greeting."), Ok(()));
+		poe.on_finalize(10);
+		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
+		poe.on_finalize(11);
+		assert_eq!(poe.get_claim(&"Hello, world!"), None);
+		assert_eq!(poe.claim_metadata(&"Hello, world!"), None);
+	}
+
+	#[test]
+	fn get_claim() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 0), "Hello, world!", ""), Ok(()));
+		assert_eq!(poe.create_claim(context("bob", 0), "Goodbye, world!", ""), Ok(()));
+		assert_eq!(poe.get_claim(&"Hello, world!"), Some(&"alice"));
+		assert_eq!(poe.get_claim(&"Goodbye, world!"), Some(&"bob"));
+		assert_eq!(poe.get_claim(&"Unknown"), None);
+	}
+
+	#[test]
+	fn verify_claim() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 0), "Hello, world!", ""), Ok(()));
+		assert!(poe.verify_claim(&"Hello, world!", &"alice"));
+		assert!(!poe.verify_claim(&"Hello, world!", &"bob"));
+		assert!(!poe.verify_claim(&"Unknown", &"alice"));
+
+		// Only the current owner is verified.
+		assert_eq!(poe.transfer_claim("alice", "Hello, world!", "bob"), Ok(()));
+		assert!(!poe.verify_claim(&"Hello, world!", &"alice"));
+		assert!(poe.verify_claim(&"Hello, world!", &"bob"));
+	}
+
+	#[test]
+	fn content_length() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		// Content of exactly the maximum length can be claimed, but not a single byte more.
+		assert_eq!(poe.create_claim(context("alice", 0), "sixteen bytes!!!", ""), Ok(()));
+		assert_eq!(
+			poe.create_claim(context("alice", 0), "seventeen bytes!!", ""),
+			Err("content too long".into())
+		);
+		assert_eq!(poe.get_claim(&"seventeen bytes!!"), None);
+		assert_eq!(poe.claim_count_of(&"alice"), 1);
+	}
+
+	#[test]
+	fn claim_limit() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(poe.create_claim(context("alice", 0), "one", ""), Ok(()));
+		assert_eq!(poe.create_claim(context("alice", 0), "two", ""), Ok(()));
+		assert_eq!(poe.claim_count_of(&"alice"), 2);
+		assert_eq!(
+			poe.create_claim(context("alice", 0), "three", ""),
+			Err("claim limit reached".into())
+		);
+
+		// Revoking a claim frees up space for a new one.
+		assert_eq!(poe.revoke_claim("alice", "two"), Ok(()));
+		assert_eq!(poe.claim_count_of(&"alice"), 1);
+		assert_eq!(poe.create_claim(context("alice", 0), "three", ""), Ok(()));
+
+		// Transferring a claim moves it from one count to the other.
+		assert_eq!(poe.create_claim(context("bob", 0), "four", ""), Ok(()));
+		assert_eq!(poe.transfer_claim("alice", "one", "bob"), Ok(()));
+		assert_eq!(poe.claim_count_of(&"alice"), 1);
+		assert_eq!(poe.claim_count_of(&"bob"), 2);
+		assert_eq!(poe.transfer_claim("alice", "three", "bob"), Err("claim limit reached".into()));
+		assert_eq!(poe.get_claim(&"three"), Some(&"alice"));
+
+		// Transferring to yourself does not change your count.
+		assert_eq!(poe.transfer_claim("bob", "one", "bob"), Ok(()));
+		assert_eq!(poe.claim_count_of(&"bob"), 2);
+	}
+
+	#[test]
+	fn create_claim_counted() {
+		let mut poe = super::Pallet::<LargerLimitConfig>::new();
+
+		assert_eq!(poe.create_claim_counted(context("alice", 0), "one", ""), Ok(1));
+		assert_eq!(poe.create_claim_counted(context("alice", 0), "two", ""), Ok(2));
+		// The count is only of the claims of the caller.
+		assert_eq!(poe.create_claim_counted(context("bob", 0), "three", ""), Ok(1));
+		assert_eq!(poe.create_claim_counted(context("alice", 0), "four", ""), Ok(3));
+		assert_eq!(
+			poe.create_claim_counted(context("alice", 0), "five", ""),
+			Err("claim limit reached".into())
+		);
+		assert_eq!(poe.claim_count_of(&"alice"), 3);
+	}
+
+	#[test]
+	fn total_claim_count() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+		assert_eq!(poe.claim_count(), 0);
+
+		assert_eq!(poe.create_claim(context("alice", 0), "one", ""), Ok(()));
+		assert_eq!(poe.create_claim(context("bob", 0), "two", ""), Ok(()));
+		assert_eq!(poe.claim_count(), 2);
+
+		// Transferring a claim does not change the total, but revoking one does.
+		assert_eq!(poe.transfer_claim("alice", "one", "bob"), Ok(()));
+		assert_eq!(poe.claim_count(), 2);
+		assert_eq!(poe.revoke_claim("bob", "one"), Ok(()));
+		assert_eq!(poe.claim_count(), 1);
+	}
+
+	#[test]
+	fn iter_claims() {
+		let mut poe = super::Pallet::<TestConfig>::new();
+		assert_eq!(poe.iter_claims().count(), 0);
+
+		assert_eq!(poe.create_claim(context("bob", 2), "two", ""), Ok(()));
+		assert_eq!(poe.create_claim(context("alice", 1), "one", ""), Ok(()));
+		assert_eq!(
+			poe.iter_claims().collect::<Vec<_>>(),
+			[(&"one", &"alice", 1), (&"two", &"bob", 2)]
+		);
+	}
+
+	#[test]
+	fn leaderboard() {
+		let mut poe = super::Pallet::<LargerLimitConfig>::new();
+		assert_eq!(poe.leaderboard(), []);
+
+		for (who, claim) in [
+			("charlie", "one"),
+			("bob", "two"),
+			("dave", "three"),
+			("bob", "four"),
+			("alice", "five"),
+			("charlie", "six"),
+			("bob", "seven"),
+		] {
+			assert_eq!(poe.create_claim(context(who, 0), claim, ""), Ok(()));
+		}
+		assert_eq!(poe.leaderboard(), [("bob", 3), ("charlie", 2), ("alice", 1), ("dave", 1)]);
+
+		// Accounts which no longer own a claim are left out.
+		assert_eq!(poe.revoke_claim("dave", "three"), Ok(()));
+		assert_eq!(poe.transfer_claim("bob", "two", "alice"), Ok(()));
+		assert_eq!(poe.leaderboard(), [("alice", 2), ("bob", 2), ("charlie", 2)]);
 	}
 }
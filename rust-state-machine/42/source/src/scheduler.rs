@@ -0,0 +1,279 @@
+use crate::support::{
+	write_snapshot_map, Dispatch, DispatchContext, DispatchResult, Origin, PalletHooks,
+};
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Scheduler Module.
+pub trait Config: crate::system::Config {
+	/// The overarching call type of the runtime, which can be scheduled.
+	type RuntimeCall;
+}
+
+/// A call which was scheduled by `origin`, on whose behalf it is dispatched.
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scheduled<AccountId, Call> {
+	/// The origin which scheduled the call.
+	pub origin: Origin<AccountId>,
+	/// The call which is dispatched.
+	pub call: Call,
+}
+
+/// A scheduled call of the runtime `T`.
+pub type ScheduledOf<T> =
+	Scheduled<<T as crate::system::Config>::AccountId, <T as Config>::RuntimeCall>;
+
+/// The calls which are scheduled for a single block, in the order they were scheduled.
+pub type Agenda<AccountId, Call> = Vec<Scheduled<AccountId, Call>>;
+
+/// The events which can be emitted by the Scheduler Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `origin` scheduled a call to be dispatched at the start of block `when`.
+	Scheduled { when: T::BlockNumber, origin: Origin<T::AccountId> },
+	/// A call scheduled for block `when` was dispatched with the given `result`.
+	Dispatched { when: T::BlockNumber, result: DispatchResult },
+}
+
+/// This is the Scheduler Module.
+/// It allows a privileged origin, like the root origin of the sudo key, to schedule a call to be
+/// dispatched at the start of a future block, on its behalf.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from a block number to the calls which are dispatched at the start of that
+	/// block.
+	agenda: BTreeMap<T::BlockNumber, Agenda<T::AccountId, T::RuntimeCall>>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Scheduler Module.
+	pub fn new() -> Self {
+		Self { agenda: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Scheduler Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+		T::RuntimeCall: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "scheduler", "agenda", &self.agenda)
+	}
+
+	/// Get the calls which are scheduled for block `when`, in the order they are dispatched.
+	pub fn agenda(&self, when: T::BlockNumber) -> &[ScheduledOf<T>] {
+		self.agenda.get(&when).map(Vec::as_slice).unwrap_or_default()
+	}
+}
+
+// Our hooks need access to the whole runtime in order to dispatch the scheduled calls, so they are
+// implemented by hand below.
+#[macros::call(custom_hooks)]
+impl<T: Config> Pallet<T> {
+	/// Schedule `call` to be dispatched on behalf of the origin of the `context` at the start of
+	/// block `when`. This is a privileged call, which the runtime only allows privileged origins,
+	/// like the root origin of the sudo key, to make. This function will return an error if `when`
+	/// is not after the block of the `context`, since the start of that block has already passed.
+	#[privileged]
+	pub fn schedule(
+		&mut self,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		call: Box<T::RuntimeCall>,
+		when: T::BlockNumber,
+	) -> DispatchResult {
+		if when <= context.block_number {
+			return Err("cannot schedule a call in the past".into());
+		}
+		let origin = context.origin;
+		let scheduled = Scheduled { origin: origin.clone(), call: *call };
+		self.agenda.entry(when).or_default().push(scheduled);
+		self.deposit_event(Event::Scheduled { when, origin });
+		Ok(())
+	}
+}
+
+// At the start of each block, we dispatch the calls which were scheduled for it. This needs access
+// to the whole runtime, so we implement `PalletHooks` by hand, instead of implementing `Hooks` for
+// the pallet.
+//
+// Note that the weight of these calls is not part of any extrinsic, so it is not charged to anyone.
+impl<T: Config, R> PalletHooks<Pallet<T>, T::BlockNumber> for R
+where
+	R: AsMut<Pallet<T>>
+		+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+{
+	fn on_initialize(&mut self, block_number: T::BlockNumber) {
+		let agenda = self.as_mut().agenda.remove(&block_number).unwrap_or_default();
+		for Scheduled { origin, call } in agenda {
+			let context = DispatchContext { origin, block_number };
+			let result = self.dispatch(context, call);
+			self.as_mut().deposit_event(Event::Dispatched { when: block_number, result });
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::support::{
+		Dispatch, DispatchCall, DispatchContext, DispatchError, DispatchResult, EnsurePrivileged,
+		Origin, PalletHooks,
+	};
+
+	// A minimal runtime which only contains the scheduler pallet, and can dispatch `TestCall`s.
+	struct TestRuntime {
+		scheduler: super::Pallet<TestRuntime>,
+		// Every origin and block number of a successfully dispatched `TestCall`.
+		dispatched: Vec<(Origin<String>, u32)>,
+	}
+
+	#[derive(Debug, PartialEq)]
+	enum TestCall {
+		Succeed,
+		Fail,
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type RuntimeCall = TestCall;
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.scheduler
+		}
+	}
+
+	// Only the root origin can make privileged calls.
+	impl EnsurePrivileged<String> for TestRuntime {
+		fn ensure_privileged(&self, origin: &Origin<String>) -> DispatchResult {
+			origin.ensure_root()
+		}
+	}
+
+	impl Dispatch for TestRuntime {
+		type Caller = String;
+		type BlockNumber = u32;
+		type Call = TestCall;
+
+		fn dispatch(
+			&mut self,
+			context: DispatchContext<String, u32>,
+			call: TestCall,
+		) -> DispatchResult {
+			match call {
+				TestCall::Succeed => {
+					self.dispatched.push((context.origin, context.block_number));
+					Ok(())
+				},
+				TestCall::Fail => Err("test call failed".into()),
+			}
+		}
+	}
+
+	fn new_runtime() -> TestRuntime {
+		TestRuntime { scheduler: super::Pallet::new(), dispatched: Vec::new() }
+	}
+
+	fn schedule(call: TestCall, when: u32) -> Call<TestRuntime> {
+		Call::schedule { call: Box::new(call), when }
+	}
+
+	fn initialize(runtime: &mut TestRuntime, block_number: u32) {
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_initialize(runtime, block_number);
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	// The context of a call made by the root origin in the first block.
+	fn root() -> DispatchContext<String, u32> {
+		DispatchContext { origin: Origin::Root, block_number: 1 }
+	}
+
+	#[test]
+	fn scheduled_calls_are_dispatched_at_their_block() {
+		let mut runtime = new_runtime();
+
+		assert_eq!(runtime.dispatch_call(root(), schedule(TestCall::Succeed, 3)), Ok(()));
+		assert_eq!(runtime.dispatch_call(root(), schedule(TestCall::Fail, 3)), Ok(()));
+		assert_eq!(
+			runtime.scheduler.agenda(3),
+			[
+				super::Scheduled { origin: Origin::Root, call: TestCall::Succeed },
+				super::Scheduled { origin: Origin::Root, call: TestCall::Fail },
+			]
+		);
+
+		// Nothing is dispatched before the block arrives.
+		initialize(&mut runtime, 2);
+		assert!(runtime.dispatched.is_empty());
+
+		// Both calls are dispatched at the start of block 3, in the order they were scheduled.
+		initialize(&mut runtime, 3);
+		assert_eq!(runtime.dispatched, vec![(Origin::Root, 3)]);
+		assert!(runtime.scheduler.agenda(3).is_empty());
+		let events = runtime.scheduler.take_events();
+		assert!(matches!(
+			&events[..],
+			[
+				Event::Scheduled { when: 3, .. },
+				Event::Scheduled { when: 3, .. },
+				Event::Dispatched { when: 3, result: Ok(()) },
+				Event::Dispatched {
+					when: 3,
+					result: Err(DispatchError::Other("test call failed"))
+				},
+			]
+		));
+
+		// Each call is only dispatched once.
+		initialize(&mut runtime, 3);
+		assert_eq!(runtime.dispatched.len(), 1);
+	}
+
+	#[test]
+	fn schedule_is_checked() {
+		let mut runtime = new_runtime();
+
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), schedule(TestCall::Succeed, 3)),
+			Err("call must be made by root".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(root(), schedule(TestCall::Succeed, 1)),
+			Err("cannot schedule a call in the past".into())
+		);
+		assert!(runtime.scheduler.agenda(1).is_empty());
+		assert!(runtime.scheduler.agenda(3).is_empty());
+	}
+}
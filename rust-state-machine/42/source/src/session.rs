@@ -0,0 +1,232 @@
+use crate::support::{write_snapshot_value, DispatchResult, Get, Hooks};
+use num::traits::{CheckedAdd, Zero};
+use std::collections::BTreeSet;
+
+/// The configuration trait for the Session Module.
+pub trait Config: crate::system::Config {
+	/// The number of blocks in each session. A new session starts every `SessionLength` blocks.
+	type SessionLength: Get<Self::BlockNumber>;
+}
+
+/// The events which can be emitted by the Session Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// A new validator set was queued, which becomes active at the start of the next session.
+	ValidatorsQueued { validators: Vec<T::AccountId> },
+	/// The session `index` started, with the given `validators`.
+	NewSession { index: u32, validators: Vec<T::AccountId> },
+}
+
+/// This is the Session Module.
+/// It keeps track of the validators of the chain, which can only change at the start of a session.
+/// Sessions are a fixed number of blocks long, so the validator set rotates at regular block
+/// heights.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The validators of the current session, in order.
+	validators: Vec<T::AccountId>,
+	/// The validators of the next session, if a new set was queued during this session.
+	queued: Option<Vec<T::AccountId>>,
+	/// The index of the current session, which starts at zero.
+	current_index: u32,
+	/// The block number at which the current session started.
+	session_start: T::BlockNumber,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Session Module.
+	pub fn new() -> Self {
+		Self {
+			validators: Vec::new(),
+			queued: None,
+			current_index: 0,
+			session_start: T::BlockNumber::zero(),
+			events: Vec::new(),
+		}
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Session Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "session", "validators", &self.validators)?;
+		write_snapshot_value(f, "session", "queued", &self.queued)?;
+		write_snapshot_value(f, "session", "current_index", &self.current_index)?;
+		write_snapshot_value(f, "session", "session_start", &self.session_start)
+	}
+
+	/// Get the validators of the current session, in order.
+	pub fn validators(&self) -> &[T::AccountId] {
+		&self.validators
+	}
+
+	/// Get the validators which will become active at the start of the next session, if a new set
+	/// was queued.
+	pub fn queued_validators(&self) -> Option<&[T::AccountId]> {
+		self.queued.as_deref()
+	}
+
+	/// Replace `from` with `to` in the validators of the current session, and in the queued
+	/// validators. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		let validators = self.validators.iter_mut().chain(self.queued.iter_mut().flatten());
+		for validator in validators.filter(|validator| *validator == from) {
+			*validator = to.clone();
+		}
+	}
+
+	/// Get the index of the current session.
+	pub fn current_index(&self) -> u32 {
+		self.current_index
+	}
+
+	// Start the next session at `block_number`, activating the queued validators, if any.
+	fn rotate_session(&mut self, block_number: T::BlockNumber) {
+		if let Some(validators) = self.queued.take() {
+			self.validators = validators;
+		}
+		// Sessions are long enough that the index will not overflow in practice.
+		self.current_index = self.current_index.saturating_add(1);
+		self.session_start = block_number;
+		let event =
+			Event::NewSession { index: self.current_index, validators: self.validators.clone() };
+		self.deposit_event(event);
+	}
+}
+
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+	// A new session starts once the current one has lasted `SessionLength` blocks. If that block
+	// number overflows, the current session never ends.
+	fn on_initialize(&mut self, block_number: T::BlockNumber) {
+		let session_end = self.session_start.checked_add(&T::SessionLength::get());
+		if session_end.is_some_and(|end| block_number >= end) {
+			self.rotate_session(block_number);
+		}
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Queue `validators` as the validator set of the next session, replacing any set which was
+	/// already queued. The current validators keep their place until the session ends.
+	/// This is a privileged call, which the runtime only allows privileged origins, like the root
+	/// origin of the sudo key, to make. This function will return an error if the new set is
+	/// empty, or if it has the same validator more than once.
+	#[privileged]
+	pub fn set_validators(&mut self, validators: Vec<T::AccountId>) -> DispatchResult {
+		if validators.is_empty() {
+			return Err("validator set is empty".into());
+		}
+		if validators.iter().collect::<BTreeSet<_>>().len() != validators.len() {
+			return Err("duplicate validator".into());
+		}
+		self.queued = Some(validators.clone());
+		self.deposit_event(Event::ValidatorsQueued { validators });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Event;
+	use crate::support::Hooks;
+
+	struct TestConfig;
+
+	struct SessionLength;
+	impl crate::support::Get<u32> for SessionLength {
+		fn get() -> u32 {
+			3
+		}
+	}
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = &'static str;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {
+		type SessionLength = SessionLength;
+	}
+
+	// Run the `on_initialize` hook of every block from `from` to `to`.
+	fn initialize_blocks(session: &mut super::Pallet<TestConfig>, from: u32, to: u32) {
+		for block_number in from..=to {
+			session.on_initialize(block_number);
+		}
+	}
+
+	#[test]
+	fn session_index_increments() {
+		let mut session = super::Pallet::<TestConfig>::new();
+		assert_eq!(session.current_index(), 0);
+
+		// A new session starts every third block.
+		initialize_blocks(&mut session, 1, 2);
+		assert_eq!(session.current_index(), 0);
+		initialize_blocks(&mut session, 3, 3);
+		assert_eq!(session.current_index(), 1);
+		initialize_blocks(&mut session, 4, 5);
+		assert_eq!(session.current_index(), 1);
+		initialize_blocks(&mut session, 6, 9);
+		assert_eq!(session.current_index(), 3);
+
+		let events = session.take_events();
+		assert_eq!(events.len(), 3);
+		assert!(matches!(&events[2], Event::NewSession { index: 3, .. }));
+	}
+
+	#[test]
+	fn validator_set_updates() {
+		let mut session = super::Pallet::<TestConfig>::new();
+		assert!(session.validators().is_empty());
+
+		assert_eq!(session.set_validators(vec![]), Err("validator set is empty".into()));
+		assert_eq!(session.set_validators(vec!["bob", "bob"]), Err("duplicate validator".into()));
+		assert_eq!(session.queued_validators(), None);
+
+		// The new set only becomes active at the start of the next session.
+		assert_eq!(session.set_validators(vec!["bob", "charlie"]), Ok(()));
+		assert_eq!(session.queued_validators(), Some(&["bob", "charlie"][..]));
+		initialize_blocks(&mut session, 1, 2);
+		assert!(session.validators().is_empty());
+		initialize_blocks(&mut session, 3, 3);
+		assert_eq!(session.validators(), ["bob", "charlie"]);
+		assert_eq!(session.queued_validators(), None);
+
+		// A queued set replaces the one queued before it, and the order is kept.
+		assert_eq!(session.set_validators(vec!["dave"]), Ok(()));
+		assert_eq!(session.set_validators(vec!["charlie", "bob"]), Ok(()));
+		initialize_blocks(&mut session, 4, 6);
+		assert_eq!(session.validators(), ["charlie", "bob"]);
+
+		// Without a queued set, the validators stay the same in the next session.
+		initialize_blocks(&mut session, 7, 9);
+		assert_eq!(session.current_index(), 3);
+		assert_eq!(session.validators(), ["charlie", "bob"]);
+	}
+}
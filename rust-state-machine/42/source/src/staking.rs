@@ -0,0 +1,230 @@
+use crate::{
+	balances::{self, BalancesError},
+	support::{write_snapshot_map, DispatchResult, Hooks},
+};
+use num::traits::{CheckedAdd, CheckedSub, Zero};
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Staking Module.
+pub trait Config: balances::Config {}
+
+/// The events which can be emitted by the Staking Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `who` bonded `amount` of their balance.
+	Bonded { who: T::AccountId, amount: T::Balance },
+	/// `who` unbonded `amount` of their balance.
+	Unbonded { who: T::AccountId, amount: T::Balance },
+}
+
+/// This is the Staking Module.
+/// It keeps track of how much balance each account has bonded.
+///
+/// Bonded balance is reserved in the Balances Module, so this module needs access to the storage
+/// of another pallet. Each pallet is a separate field of the runtime, so instead of storing a
+/// handle to the Balances Module, the calls of this pallet are given access to the whole runtime,
+/// which must implement `AsMut` for both this pallet and the Balances Module. The `macros::runtime`
+/// macro implements `AsMut` for every pallet in the runtime.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an account to the amount of balance it has bonded.
+	bonded: BTreeMap<T::AccountId, T::Balance>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Staking Module.
+	pub fn new() -> Self {
+		Self { bonded: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Staking Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "staking", "bonded", &self.bonded)
+	}
+
+	/// Get the amount of balance bonded by `who`.
+	pub fn bonded(&self, who: &T::AccountId) -> T::Balance {
+		*self.bonded.get(who).unwrap_or(&T::Balance::zero())
+	}
+
+	// Set the amount of balance bonded by `who`, removing the entry when nothing is bonded.
+	fn set_bonded(&mut self, who: &T::AccountId, amount: T::Balance) {
+		if amount.is_zero() {
+			self.bonded.remove(who);
+		} else {
+			self.bonded.insert(who.clone(), amount);
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// The calls of this pallet need access to the Balances Module, so the runtime must give us access to
+// it as well.
+#[macros::call(R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Bond `amount` of the free balance of the `caller`, by reserving it in the Balances Module.
+	/// This function will return an error if the caller does not have enough free balance.
+	pub fn bond<R>(runtime: &mut R, caller: T::AccountId, amount: T::Balance) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let staking: &mut Self = runtime.as_mut();
+		let new_bonded =
+			staking.bonded(&caller).checked_add(&amount).ok_or(BalancesError::Overflow)?;
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.reserve(&caller, amount)?;
+
+		let staking: &mut Self = runtime.as_mut();
+		staking.set_bonded(&caller, new_bonded);
+		staking.deposit_event(Event::Bonded { who: caller, amount });
+		Ok(())
+	}
+
+	/// Unbond `amount` of the balance bonded by the `caller`, unreserving it in the Balances Module.
+	/// This function will return an error if the caller has bonded less than `amount`.
+	pub fn unbond<R>(runtime: &mut R, caller: T::AccountId, amount: T::Balance) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let staking: &mut Self = runtime.as_mut();
+		let new_bonded = staking
+			.bonded(&caller)
+			.checked_sub(&amount)
+			.ok_or("not enough balance bonded")?;
+
+		// Bonded balance is always reserved, so all of it is unreserved.
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.unreserve(&caller, amount);
+
+		let staking: &mut Self = runtime.as_mut();
+		staking.set_bonded(&caller, new_bonded);
+		staking.deposit_event(Event::Unbonded { who: caller, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Call;
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the staking pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		staking: super::Pallet<TestRuntime>,
+	}
+
+	struct ExistentialDeposit;
+	impl crate::support::Get<u128> for ExistentialDeposit {
+		fn get() -> u128 {
+			1
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ExistentialDeposit;
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.staking
+		}
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	#[test]
+	fn bond_and_unbond() {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), staking: super::Pallet::new() };
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::bond { amount: 60 }), Ok(()));
+		assert_eq!(runtime.staking.bonded(&alice), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 60);
+		assert_eq!(runtime.balances.balance(&alice), 40);
+
+		// Bonding more than the free balance fails, and nothing changes.
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::bond { amount: 50 }),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.staking.bonded(&alice), 60);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 60);
+
+		// Partially unbonding.
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::unbond { amount: 20 }), Ok(()));
+		assert_eq!(runtime.staking.bonded(&alice), 40);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 40);
+		assert_eq!(runtime.balances.balance(&alice), 60);
+
+		// Unbonding more than is bonded fails.
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::unbond { amount: 41 }),
+			Err("not enough balance bonded".into())
+		);
+
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::unbond { amount: 40 }), Ok(()));
+		assert_eq!(runtime.staking.bonded(&alice), 0);
+		assert_eq!(runtime.balances.reserved_balance(&alice), 0);
+		assert_eq!(runtime.balances.balance(&alice), 100);
+		assert_eq!(runtime.balances.total_issuance(), 100);
+		assert_eq!(runtime.staking.take_events().len(), 3);
+	}
+}
@@ -0,0 +1,291 @@
+use crate::support::{
+	write_snapshot_value, Dispatch, DispatchContext, DispatchResult, GetWeight, Hooks, Origin,
+	DEFAULT_WEIGHT,
+};
+
+/// The configuration trait for the Sudo Module.
+pub trait Config: crate::system::Config {
+	/// The overarching call type of the runtime, which the sudo key can dispatch.
+	type RuntimeCall;
+}
+
+/// The events which can be emitted by the Sudo Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// The sudo key was changed from `old` to `new`.
+	KeyChanged { old: Option<T::AccountId>, new: T::AccountId },
+	/// The sudo key dispatched a call, with the given `result`.
+	Sudid { result: DispatchResult },
+}
+
+/// This is the Sudo Module.
+/// It allows a single superuser account, the sudo key, to make privileged calls.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The account which is allowed to make sudo calls, if any.
+	key: Option<T::AccountId>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Sudo Module.
+	pub fn new() -> Self {
+		Self { key: None, events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Sudo Module to a snapshot of the state, one line per storage value
+	/// or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "sudo", "key", &self.key)
+	}
+
+	/// Get the current sudo key, if any.
+	pub fn key(&self) -> Option<&T::AccountId> {
+		self.key.as_ref()
+	}
+
+	/// Give the sudo key to `to`, if it is held by `from`. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		if self.key.as_ref() == Some(from) {
+			self.key = Some(to.clone());
+		}
+	}
+
+	/// Set the initial sudo key, when the runtime is first created.
+	pub fn initialize_key(&mut self, key: T::AccountId) {
+		self.key = Some(key);
+	}
+
+	/// Check that `caller` is the sudo key.
+	/// The runtime can use this to only allow the sudo key to make privileged calls.
+	pub fn ensure_sudo(&self, caller: &T::AccountId) -> DispatchResult {
+		if self.key.as_ref() != Some(caller) {
+			return Err("sender must be the sudo key".into());
+		}
+		Ok(())
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// The `sudo` call needs access to the whole runtime in order to dispatch the inner call, and its
+// weight depends on the weight of the inner call.
+#[macros::call(
+	R: Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	T::RuntimeCall: GetWeight,
+)]
+impl<T: Config> Pallet<T> {
+	/// Dispatch `call` with the root origin, in the block of the `context`. The result of the inner
+	/// call is reported with a `Sudid` event.
+	/// This function will return an error if the caller is not the sudo key, or if the inner call
+	/// fails.
+	#[weight(DEFAULT_WEIGHT.saturating_add(call.weight()))]
+	pub fn sudo<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		call: Box<T::RuntimeCall>,
+	) -> DispatchResult
+	where
+		R: AsMut<Self>
+			+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		let caller = origin.ensure_signed()?;
+		let sudo: &mut Self = runtime.as_mut();
+		sudo.ensure_sudo(&caller)?;
+
+		// The inner call is made by the root origin, in the same block.
+		let context = DispatchContext { origin: Origin::Root, block_number };
+		let result = runtime.dispatch(context, *call);
+		let sudo: &mut Self = runtime.as_mut();
+		sudo.deposit_event(Event::Sudid { result });
+		result
+	}
+
+	/// Change the sudo key to `new`.
+	/// This function will return an error if the caller is not the current sudo key.
+	pub fn set_key(&mut self, caller: T::AccountId, new: T::AccountId) -> DispatchResult {
+		self.ensure_sudo(&caller)?;
+		let old = self.key.replace(new.clone());
+		self.deposit_event(Event::KeyChanged { old, new });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::support::{
+		Dispatch, DispatchCall, DispatchContext, DispatchResult, GetWeight, Origin, Weight,
+	};
+
+	// A minimal runtime which only contains the sudo pallet, and can dispatch `TestCall`s.
+	struct TestRuntime {
+		sudo: super::Pallet<TestRuntime>,
+		// Every origin which successfully dispatched a `TestCall`.
+		dispatched: Vec<Origin<String>>,
+	}
+
+	enum TestCall {
+		Succeed,
+		Fail,
+	}
+
+	impl GetWeight for TestCall {
+		fn weight(&self) -> Weight {
+			10
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type RuntimeCall = TestCall;
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.sudo
+		}
+	}
+
+	impl Dispatch for TestRuntime {
+		type Caller = String;
+		type BlockNumber = u32;
+		type Call = TestCall;
+
+		fn dispatch(
+			&mut self,
+			context: DispatchContext<String, u32>,
+			call: TestCall,
+		) -> DispatchResult {
+			match call {
+				TestCall::Succeed => {
+					self.dispatched.push(context.origin);
+					Ok(())
+				},
+				TestCall::Fail => Err("test call failed".into()),
+			}
+		}
+	}
+
+	fn new_runtime() -> TestRuntime {
+		let mut runtime = TestRuntime { sudo: super::Pallet::new(), dispatched: Vec::new() };
+		runtime.sudo.initialize_key("alice".to_string());
+		runtime
+	}
+
+	fn sudo(call: TestCall) -> Call<TestRuntime> {
+		Call::sudo { call: Box::new(call) }
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	#[test]
+	fn sudo_call() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		// The inner call is made by the root origin, not by alice.
+		assert_eq!(runtime.dispatch_call(context(&alice), sudo(TestCall::Succeed)), Ok(()));
+		assert_eq!(runtime.dispatched, vec![Origin::Root]);
+		// The result of the inner call is returned.
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), sudo(TestCall::Fail)),
+			Err("test call failed".into())
+		);
+
+		let events = runtime.sudo.take_events();
+		assert!(matches!(
+			events[..],
+			[Event::Sudid { result: Ok(()) }, Event::Sudid { result: Err(_) }]
+		));
+	}
+
+	#[test]
+	fn unauthorized_sudo_call() {
+		let mut runtime = new_runtime();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), sudo(TestCall::Succeed)),
+			Err("sender must be the sudo key".into())
+		);
+		assert!(runtime.dispatched.is_empty());
+
+		// Sudo calls must be signed, even by the root origin.
+		let root = DispatchContext { origin: Origin::Root, block_number: 1 };
+		assert_eq!(
+			runtime.dispatch_call(root, sudo(TestCall::Succeed)),
+			Err("call must be signed".into())
+		);
+
+		// Without a sudo key, nobody can make sudo calls.
+		let mut runtime = TestRuntime { sudo: super::Pallet::new(), dispatched: Vec::new() };
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), sudo(TestCall::Succeed)),
+			Err("sender must be the sudo key".into())
+		);
+	}
+
+	#[test]
+	fn set_key() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::set_key { new: bob.clone() }),
+			Err("sender must be the sudo key".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::set_key { new: bob.clone() }),
+			Ok(())
+		);
+		assert_eq!(runtime.sudo.key(), Some(&bob));
+
+		// Only the new key can make sudo calls.
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), sudo(TestCall::Succeed)),
+			Err("sender must be the sudo key".into())
+		);
+		assert_eq!(runtime.dispatch_call(context(&bob), sudo(TestCall::Succeed)), Ok(()));
+		assert_eq!(runtime.dispatched, vec![Origin::Root]);
+
+		let events = runtime.sudo.take_events();
+		assert!(matches!(
+			&events[0],
+			Event::KeyChanged { old: Some(old), new } if *old == alice && *new == bob
+		));
+	}
+}
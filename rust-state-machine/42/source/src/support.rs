@@ -1,4 +1,10 @@
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
 /// The most primitive representation of a Blockchain block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Block<Header, Extrinsic> {
 	/// The block header contains metadata about the block.
 	pub header: Header,
@@ -6,37 +12,1012 @@ pub struct Block<Header, Extrinsic> {
 	pub extrinsics: Vec<Extrinsic>,
 }
 
-/// We are using an extremely simplified header which only contains the current block number.
+#[cfg(feature = "serde")]
+impl<Header: Serialize + DeserializeOwned, Extrinsic: Serialize + DeserializeOwned>
+	Block<Header, Extrinsic>
+{
+	/// Parse a block from its JSON representation.
+	#[allow(dead_code)]
+	pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+		serde_json::from_str(json)
+	}
+
+	/// Convert this block into its JSON representation.
+	#[allow(dead_code)]
+	pub fn to_json(&self) -> String {
+		serde_json::to_string(self).expect("blocks can always be serialized")
+	}
+}
+
+/// We are using an extremely simplified header which only contains the current block number, the
+/// hash of the parent block, and the state root after executing the block.
 /// On a real blockchain, you would expect to also find:
-/// - parent block hash
-/// - state root
 /// - extrinsics root
 /// - etc...
-pub struct Header<BlockNumber> {
+#[derive(Debug, Clone, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Header<BlockNumber, Hash> {
 	pub block_number: BlockNumber,
+	/// The hash of the header of the previous block, which links the blocks together into a chain.
+	/// The first block has the default "zero" hash as its parent.
+	pub parent_hash: Hash,
+	/// A commitment to all of the state of the runtime after this block has been executed. See
+	/// `state_root`.
+	pub state_root: StateRoot,
+}
+
+/// The reasons a header can be invalid for the block it is part of. Every field of the header is
+/// checked, and each of them has its own error. See `Runtime::validate_header`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+	/// The last executed block has the maximum block number, so there is no next block.
+	BlockNumberOverflow,
+	/// The block number is not the one after the last executed block.
+	BlockNumberMismatch,
+	/// The parent hash is not the hash of the last executed block.
+	ParentHashMismatch,
+	/// The state root is not the state root after executing the block.
+	StateRootMismatch,
+}
+
+impl HeaderError {
+	/// The message of this error, which is what `execute_block` returns when it rejects a block.
+	pub fn message(&self) -> &'static str {
+		match self {
+			Self::BlockNumberOverflow => "block number overflow",
+			Self::BlockNumberMismatch => "block number mismatch",
+			Self::ParentHashMismatch => "parent hash mismatch",
+			Self::StateRootMismatch => "state root mismatch",
+		}
+	}
+}
+
+impl From<HeaderError> for &'static str {
+	fn from(error: HeaderError) -> Self {
+		error.message()
+	}
+}
+
+impl core::fmt::Display for HeaderError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(self.message())
+	}
+}
+
+/// The reasons importing a file of blocks can fail. See `Runtime::import_blocks_from_file`.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportError {
+	/// The file could not be read.
+	Read,
+	/// The file is not a JSON array of blocks.
+	Parse,
+	/// The block at `index` in the file could not be executed, for the reason in `error`. The
+	/// blocks before it were executed.
+	Block { index: usize, error: &'static str },
+}
+
+#[cfg(feature = "serde")]
+impl core::fmt::Display for ImportError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		match self {
+			Self::Read => f.write_str("could not read the blocks file"),
+			Self::Parse => f.write_str("could not parse the blocks file"),
+			Self::Block { index, error } => write!(f, "block {index} is invalid: {error}"),
+		}
+	}
 }
 
 /// This is an "extrinsic": literally an external message from outside of the blockchain.
-/// This simplified version of an extrinsic tells us who is making the call, and which call they are
-/// making.
-pub struct Extrinsic<Caller, Call> {
+/// This simplified version of an extrinsic tells us who is making the call, which call they are
+/// making, and the nonce of the caller. The nonce must match the number of extrinsics the caller has
+/// already made, so the same extrinsic cannot be executed twice.
+///
+/// An extrinsic is "signed" when it carries a signature which matches the `caller`, proving that
+/// the caller really did make this call. Only signed extrinsics are executed by the runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Extrinsic<Caller, Call, Nonce> {
 	pub caller: Caller,
 	pub call: Call,
+	pub nonce: Nonce,
+	pub signature: Option<Signature>,
+}
+
+/// The signature of an extrinsic.
+///
+/// We use a mock signing scheme, where the signature is simply the `hash` of the caller, the call
+/// and the nonce. This means anyone can sign on behalf of anyone else, so it is NOT secure! On a
+/// real blockchain, the signature would be created with the private key of the caller, and
+/// verified with their public key.
+pub type Signature = u64;
+
+impl<Caller: Encode, Call: Encode, Nonce: Encode> Extrinsic<Caller, Call, Nonce> {
+	/// Create a new extrinsic, signed by the `caller`.
+	pub fn new_signed(caller: Caller, call: Call, nonce: Nonce) -> Self {
+		let signature = Some(sign(&caller, &call, &nonce));
+		Self { caller, call, nonce, signature }
+	}
+
+	/// Check that this extrinsic has a signature, and that the signature matches the caller, call
+	/// and nonce of this extrinsic.
+	pub fn verify_signed(&self) -> bool {
+		self.signature == Some(sign(&self.caller, &self.call, &self.nonce))
+	}
+}
+
+/// Create the mock signature of `caller` making `call` with `nonce`. See `Signature`.
+pub fn sign<Caller: Encode, Call: Encode, Nonce: Encode>(
+	caller: &Caller,
+	call: &Call,
+	nonce: &Nonce,
+) -> Signature {
+	hash(&(caller, call, nonce))
+}
+
+/// A builder for blocks, so tests do not need to write out every header field and extrinsic.
+///
+/// The header defaults to the first block, on top of the default "zero" parent hash, with an empty
+/// state root, and each setter overrides one of its fields. Every extrinsic is signed by its
+/// caller, using the nonces of the caller in order, starting from zero, so a block of a fresh
+/// account is always valid. Like other blocks, the state root is only known after executing the
+/// block, so it must be set by hand for `execute_block` to accept the block.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct BlockBuilder<BlockNumber, Hash, Caller, Call, Nonce> {
+	header: Header<BlockNumber, Hash>,
+	extrinsics: Vec<Extrinsic<Caller, Call, Nonce>>,
+	// The nonce of the next extrinsic of each caller.
+	nonces: BTreeMap<Caller, Nonce>,
+}
+
+#[cfg(test)]
+impl<BlockNumber, Hash, Caller, Call, Nonce> BlockBuilder<BlockNumber, Hash, Caller, Call, Nonce>
+where
+	BlockNumber: num::One,
+	Hash: Default,
+	Caller: Encode + Ord + Clone,
+	Call: Encode,
+	Nonce: Encode + num::Zero + num::One + Copy,
+{
+	/// Create a builder for an empty first block.
+	pub fn new() -> Self {
+		Self {
+			header: Header {
+				block_number: BlockNumber::one(),
+				parent_hash: Hash::default(),
+				state_root: [0; 32],
+			},
+			extrinsics: Vec::new(),
+			nonces: BTreeMap::new(),
+		}
+	}
+
+	/// Set the block number of the block.
+	pub fn block_number(mut self, block_number: BlockNumber) -> Self {
+		self.header.block_number = block_number;
+		self
+	}
+
+	/// Set the hash of the parent of the block.
+	pub fn parent_hash(mut self, parent_hash: Hash) -> Self {
+		self.header.parent_hash = parent_hash;
+		self
+	}
+
+	/// Set the state root after executing the block.
+	pub fn state_root(mut self, state_root: StateRoot) -> Self {
+		self.header.state_root = state_root;
+		self
+	}
+
+	/// Add an extrinsic of `caller` making `call` to the end of the block, signed with the next
+	/// nonce of the `caller` in this block.
+	pub fn push_extrinsic(mut self, caller: Caller, call: Call) -> Self {
+		let nonce = self.nonces.entry(caller.clone()).or_insert_with(Nonce::zero);
+		let extrinsic = Extrinsic::new_signed(caller, call, *nonce);
+		*nonce = *nonce + Nonce::one();
+		self.extrinsics.push(extrinsic);
+		self
+	}
+
+	/// Build the block, with its extrinsics in the order they were pushed.
+	pub fn build(self) -> Block<Header<BlockNumber, Hash>, Extrinsic<Caller, Call, Nonce>> {
+		Block { header: self.header, extrinsics: self.extrinsics }
+	}
 }
 
 /// The Result type for our runtime. When everything completes successfully, we return `Ok(())`,
-/// otherwise return a static error message.
-pub type DispatchResult = Result<(), &'static str>;
+/// otherwise return a `DispatchError`. Pallets usually fail with a static error message, which
+/// converts into `DispatchError::Other`, so they can simply write `Err("...".into())`.
+pub type DispatchResult = Result<(), DispatchError>;
+
+/// The error of a call which failed, or of an extrinsic which could not be applied by the runtime.
+/// Every pallet returns this same error type, usually by converting its own errors into it.
+///
+/// Most errors are recoverable: the extrinsic fails, but the rest of its block is still executed.
+/// Fatal errors mean that the extrinsic should never have been included in a block, so a block
+/// which includes it is invalid, and `execute_block` aborts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchError {
+	/// The extrinsic is not signed by its caller. This is fatal.
+	BadOrigin,
+	/// The extrinsic does not fit in the weight of its block. This is fatal.
+	Exhausted,
+	/// The extrinsic is identical to an earlier extrinsic in its block. This is fatal.
+	Duplicate,
+	/// The extrinsic does not fit in the number of extrinsics of its block. This is fatal.
+	Full,
+	/// The nonce of the extrinsic is not the next nonce of its caller, for example because it was
+	/// already executed. This is fatal.
+	BadNonce,
+	/// The caller of the extrinsic is frozen. This is fatal.
+	Frozen,
+	/// The caller of the extrinsic cannot pay its fee. This is fatal.
+	CannotPayFee,
+	/// Any other error, like a call which failed. This is recoverable.
+	Other(&'static str),
+}
+
+impl DispatchError {
+	/// Whether this error makes the whole block invalid, instead of only failing the extrinsic.
+	pub fn is_fatal(&self) -> bool {
+		!matches!(self, Self::Other(_))
+	}
+
+	/// The message of this error, which is what `execute_block` returns when it aborts a block.
+	pub fn message(&self) -> &'static str {
+		match self {
+			Self::BadOrigin => "Invalid signature.",
+			Self::Exhausted => "block weight exceeded",
+			Self::Duplicate => "duplicate extrinsic",
+			Self::Full => "too many extrinsics",
+			Self::BadNonce => "Invalid nonce.",
+			Self::Frozen => "account frozen",
+			Self::CannotPayFee => "cannot pay fee",
+			Self::Other(message) => message,
+		}
+	}
+}
+
+// Every error of a call is recoverable.
+impl From<&'static str> for DispatchError {
+	fn from(message: &'static str) -> Self {
+		Self::Other(message)
+	}
+}
+
+impl From<DispatchError> for &'static str {
+	fn from(error: DispatchError) -> Self {
+		error.message()
+	}
+}
+
+impl core::fmt::Display for DispatchError {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(self.message())
+	}
+}
+
+/// Where a call comes from.
+///
+/// Every extrinsic is dispatched with a `Signed` origin, since only signed extrinsics are executed.
+/// Other origins can only come from within the runtime: for example, the sudo pallet dispatches
+/// its inner call with the `Root` origin, which privileged calls usually require.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Origin<AccountId> {
+	/// The call is made by an account, which signed it.
+	Signed(AccountId),
+	/// The call is made by the runtime itself, with full privileges.
+	Root,
+	/// The call is made by nobody, like an inherent which the block author includes.
+	None,
+}
+
+impl<AccountId> Origin<AccountId> {
+	/// Return the account which signed the call, or an error if the call is not signed.
+	pub fn ensure_signed(self) -> Result<AccountId, DispatchError> {
+		match self {
+			Origin::Signed(who) => Ok(who),
+			_ => Err("call must be signed".into()),
+		}
+	}
+
+	/// Return an error if the call is not made with the `Root` origin.
+	pub fn ensure_root(&self) -> DispatchResult {
+		match self {
+			Origin::Root => Ok(()),
+			_ => Err("call must be made by root".into()),
+		}
+	}
+
+	/// Return an error if the call is not made with the `None` origin.
+	pub fn ensure_none(&self) -> DispatchResult {
+		match self {
+			Origin::None => Ok(()),
+			_ => Err("call must be unsigned".into()),
+		}
+	}
+}
+
+/// The context a call is dispatched in: the origin of the call, and the number of the block it is
+/// executed in.
+///
+/// The runtime builds this for every extrinsic, so pallets can know the current block number
+/// without access to the system pallet. Calls which dispatch other calls pass it on, so the inner
+/// calls are made by the same origin, in the same block, unless they say otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DispatchContext<Caller, BlockNumber> {
+	/// The origin of the call.
+	pub origin: Origin<Caller>,
+	/// The number of the block the call is executed in.
+	pub block_number: BlockNumber,
+}
+
+impl<Caller, BlockNumber> DispatchContext<Caller, BlockNumber> {
+	/// The context of a call signed by `caller`, executed in the block `block_number`.
+	pub fn signed(caller: Caller, block_number: BlockNumber) -> Self {
+		Self { origin: Origin::Signed(caller), block_number }
+	}
+}
 
 /// A trait which allows us to dispatch an incoming extrinsic to the appropriate state transition
 /// function call.
 pub trait Dispatch {
-	/// The type used to identify the caller of the function.
+	/// The type used to identify the accounts which can sign calls.
 	type Caller;
+	/// The type used to number the blocks the function is called in.
+	type BlockNumber;
 	/// The state transition function call the caller is trying to access.
 	type Call;
 
-	/// This function takes a `caller` and the `call` they want to make, and returns a `Result`
-	/// based on the outcome of that function call.
-	fn dispatch(&mut self, caller: Self::Caller, call: Self::Call) -> DispatchResult;
+	/// This function takes the `context` of the call, including its origin, and the `call` to
+	/// make, and returns a `Result` based on the outcome of that function call.
+	fn dispatch(
+		&mut self,
+		context: DispatchContext<Self::Caller, Self::BlockNumber>,
+		call: Self::Call,
+	) -> DispatchResult;
+}
+
+/// A trait which allows the runtime to dispatch the `Call` of a single pallet.
+///
+/// Most pallets only need access to their own storage, so the `macros::call` macro implements this
+/// for any runtime which implements `AsMut<Pallet<T>>` by simply calling `Dispatch` on the pallet.
+/// Pallets which need more than their own storage, like a pallet which dispatches other calls, pass
+/// whatever they require from the runtime to `macros::call`.
+#[diagnostic::on_unimplemented(
+	message = "the runtime `{Self}` cannot dispatch `{Call}`",
+	label = "the calls of this pallet cannot be dispatched by the runtime",
+	note = "the calls of a pallet may need access to other pallets, which must also be in the runtime"
+)]
+pub trait DispatchCall<Caller, BlockNumber, Call> {
+	/// Dispatch `call` in the given `context`, on behalf of its origin.
+	fn dispatch_call(
+		&mut self,
+		context: DispatchContext<Caller, BlockNumber>,
+		call: Call,
+	) -> DispatchResult;
+}
+
+/// A trait which allows the runtime to decide which origins can make privileged calls.
+///
+/// The calls of a pallet marked with `#[privileged]` in `macros::call` can only be dispatched by a
+/// runtime which implements this, and only if `ensure_privileged` succeeds for the origin. For
+/// example, a runtime with the sudo pallet usually allows only the `Root` origin, so the sudo key
+/// can make privileged calls with `sudo`.
+pub trait EnsurePrivileged<Caller> {
+	/// Return an error if `origin` is not allowed to make privileged calls.
+	fn ensure_privileged(&self, origin: &Origin<Caller>) -> DispatchResult;
+}
+
+/// The amount of resources, like execution time, used by executing a call. The total weight of the
+/// extrinsics in a block is limited, so a block can always be executed in a reasonable time.
+pub type Weight = u64;
+
+/// The weight of a call which does not declare its own weight.
+pub const DEFAULT_WEIGHT: Weight = 1;
+
+/// A trait for calls which declare the `Weight` used by executing them.
+///
+/// The `macros::call` macro implements this for every `Call` enum, using the `#[weight(..)]`
+/// attribute of each call, or `DEFAULT_WEIGHT`. The attribute can use the arguments of the call, so
+/// calls like `transfer_batch` can weigh more for a larger input. The weight is known before the
+/// call is executed, and does not depend on the state.
+pub trait GetWeight {
+	/// The weight of executing this call.
+	fn weight(&self) -> Weight;
+}
+
+/// A trait for calls which can name the function they call, without its arguments.
+///
+/// The `macros::call` macro implements this for every `Call` enum, using the name of each variant.
+/// The runtime uses it to log which call each extrinsic dispatched.
+pub trait GetCallName {
+	/// The name of the function this call dispatches to, like `"transfer"`.
+	fn call_name(&self) -> &'static str;
+}
+
+/// A trait which allows the runtime to charge a fee for executing an extrinsic.
+///
+/// The fee is charged before the call of the extrinsic is dispatched, and is not refunded if the
+/// call fails. The runtime decides how the fee is computed from the `Weight` of the call, and who
+/// pays it.
+pub trait ChargeFee<Caller> {
+	/// Charge `who` the fee for a call of the given `weight`.
+	/// This should return an error if `who` cannot pay the fee.
+	fn charge_fee(&mut self, who: &Caller, weight: Weight) -> DispatchResult;
+}
+
+/// A trait which allows the runtime to react to every extrinsic which is part of a block.
+///
+/// `apply_block` calls this after each extrinsic is applied, whether its call succeeded or failed,
+/// with the index of the extrinsic in the block. An extrinsic which fails with a fatal
+/// `DispatchError` is not part of the block, so this is not called for it.
+pub trait OnExtrinsic<Caller, BlockNumber> {
+	/// Called after the extrinsic of `caller` at `index` in the block `block_number` was applied.
+	fn on_extrinsic(&mut self, caller: &Caller, block_number: BlockNumber, index: u32);
+}
+
+/// A trait which allows the runtime to provide randomness to its pallets.
+///
+/// The runtime decides where the randomness comes from, like the recent block hashes with
+/// `system::Pallet::random`, which is NOT secure. Tests can use a fixed value instead, so they know
+/// the outcome.
+pub trait Randomness<Output> {
+	/// Get a random value for `subject`. Different subjects should get unrelated values.
+	fn random(&self, subject: &[u8]) -> Output;
+}
+
+/// A trait which allows the state of the runtime to be copied, and restored later, so a change
+/// which fails part way through can be undone.
+///
+/// The `macros::runtime` macro implements this by cloning every pallet, and `execute_block` uses it
+/// to restore the state when it rejects a block. Pallets can require it from the runtime to undo a
+/// group of calls, like `batch_all` in the utility pallet.
+pub trait CloneState {
+	/// Copy the state of every pallet in the runtime.
+	fn clone_state(&self) -> Self;
+	/// Restore the state of every pallet from a `snapshot` made with `clone_state`.
+	fn restore_state(&mut self, snapshot: Self);
+}
+
+/// A trait for types which provide a value of type `T`, usually a constant configured by the
+/// runtime. For example, a pallet might require `type ExistentialDeposit: Get<Self::Balance>`.
+pub trait Get<T> {
+	fn get() -> T;
+}
+
+// `()` provides the default value of any type, which is useful for tests which do not care about
+// the value.
+impl<T: Default> Get<T> for () {
+	fn get() -> T {
+		T::default()
+	}
+}
+
+/// Declare constants which implement `Get`, so they can be used to configure a pallet.
+///
+/// Each constant is declared like `pub ExistentialDeposit: Balance = 1;`, which declares a unit
+/// struct `ExistentialDeposit` whose `Get<Balance>` implementation returns `1`. The value can be
+/// any expression, which is evaluated every time `get` is called. Like any exported macro, it is
+/// used from the root of the crate, as `parameter_types!`.
+#[macro_export]
+macro_rules! parameter_types {
+	($($(#[$attr:meta])* $vis:vis $name:ident: $type:ty = $value:expr;)*) => {
+		$(
+			$(#[$attr])*
+			$vis struct $name;
+			impl $crate::support::Get<$type> for $name {
+				fn get() -> $type {
+					$value
+				}
+			}
+		)*
+	};
+}
+
+/// A storage map from keys of type `K` to values of type `V`, which pallets use to keep their state.
+///
+/// Pallets which are generic over their storage backend can use any type which implements this
+/// trait. `BTreeMap` is the default, which simply keeps everything in memory, but a persistent
+/// backend could be used instead. Iterating over the map should visit the keys in order, so the
+/// state of a pallet is always processed in the same order.
+pub trait StorageMap<K, V>: Default {
+	/// Get the value stored under `key`, if any.
+	fn get(&self, key: &K) -> Option<&V>;
+	/// Store `value` under `key`, returning the value which was stored there before, if any.
+	fn insert(&mut self, key: K, value: V) -> Option<V>;
+	/// Remove the value stored under `key`, returning it if there was one.
+	fn remove(&mut self, key: &K) -> Option<V>;
+	/// Iterate over all of the keys and their values, in the order of the keys.
+	fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+	where
+		K: 'a,
+		V: 'a;
+}
+
+impl<K: Ord, V> StorageMap<K, V> for BTreeMap<K, V> {
+	fn get(&self, key: &K) -> Option<&V> {
+		BTreeMap::get(self, key)
+	}
+
+	fn insert(&mut self, key: K, value: V) -> Option<V> {
+		BTreeMap::insert(self, key, value)
+	}
+
+	fn remove(&mut self, key: &K) -> Option<V> {
+		BTreeMap::remove(self, key)
+	}
+
+	fn iter<'a>(&'a self) -> impl Iterator<Item = (&'a K, &'a V)>
+	where
+		K: 'a,
+		V: 'a,
+	{
+		BTreeMap::iter(self)
+	}
+}
+
+/// A canonical binary encoding of a value, which is what the state root and block hashes are
+/// computed from, so every node computes the same hashes for the same data.
+///
+/// Integers are encoded in little endian with their full width, and lengths as a `u64`, so the
+/// encoding is the same on every platform, and unlike `std::hash::Hash`, it never changes between
+/// versions of Rust. A collection is encoded as its length followed by its items in order, which
+/// is the order of the keys for a `BTreeMap` or `BTreeSet`. Structs and enums can derive it with
+/// `macros::Encode`.
+pub trait Encode {
+	/// Append the encoding of this value to `dest`.
+	fn encode_to(&self, dest: &mut Vec<u8>);
+
+	/// The encoding of this value.
+	fn encode(&self) -> Vec<u8> {
+		let mut dest = Vec::new();
+		self.encode_to(&mut dest);
+		dest
+	}
+}
+
+// Every integer is encoded in little endian, and `usize` and `isize` always take 8 bytes.
+macro_rules! impl_encode_for_integer {
+	($( $int:ty => $as:ty ),* $(,)?) => {
+		$(
+			impl Encode for $int {
+				fn encode_to(&self, dest: &mut Vec<u8>) {
+					dest.extend_from_slice(&(*self as $as).to_le_bytes());
+				}
+			}
+		)*
+	};
+}
+
+impl_encode_for_integer!(
+	u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => u64,
+	i8 => i8, i16 => i16, i32 => i32, i64 => i64, i128 => i128, isize => i64,
+);
+
+// Tuples are encoded as each of their elements in order.
+macro_rules! impl_encode_for_tuple {
+	($( ($( $name:ident ),+) ),* $(,)?) => {
+		$(
+			impl<$( $name: Encode ),+> Encode for ($( $name, )+) {
+				#[allow(non_snake_case)]
+				fn encode_to(&self, dest: &mut Vec<u8>) {
+					let ($( $name, )+) = self;
+					$( $name.encode_to(dest); )+
+				}
+			}
+		)*
+	};
+}
+
+impl_encode_for_tuple!((A), (A, B), (A, B, C), (A, B, C, D));
+
+impl Encode for () {
+	fn encode_to(&self, _dest: &mut Vec<u8>) {}
+}
+
+impl Encode for bool {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		dest.push(*self as u8);
+	}
+}
+
+impl Encode for str {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.len().encode_to(dest);
+		dest.extend_from_slice(self.as_bytes());
+	}
+}
+
+impl Encode for String {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.as_str().encode_to(dest);
+	}
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		(**self).encode_to(dest);
+	}
+}
+
+impl<T: Encode + ?Sized> Encode for Box<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		(**self).encode_to(dest);
+	}
+}
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		match self {
+			None => dest.push(0),
+			Some(value) => {
+				dest.push(1);
+				value.encode_to(dest);
+			},
+		}
+	}
+}
+
+// An array always has the same length, so only its items are encoded.
+impl<T: Encode, const N: usize> Encode for [T; N] {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.iter().for_each(|item| item.encode_to(dest));
+	}
+}
+
+impl<T: Encode> Encode for [T] {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.len().encode_to(dest);
+		self.iter().for_each(|item| item.encode_to(dest));
+	}
+}
+
+impl<T: Encode> Encode for Vec<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.as_slice().encode_to(dest);
+	}
+}
+
+impl<T: Encode> Encode for VecDeque<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.len().encode_to(dest);
+		self.iter().for_each(|item| item.encode_to(dest));
+	}
+}
+
+impl<T: Encode> Encode for BTreeSet<T> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.len().encode_to(dest);
+		self.iter().for_each(|item| item.encode_to(dest));
+	}
+}
+
+impl<K: Encode, V: Encode> Encode for BTreeMap<K, V> {
+	fn encode_to(&self, dest: &mut Vec<u8>) {
+		self.len().encode_to(dest);
+		self.iter().for_each(|entry| entry.encode_to(dest));
+	}
+}
+
+/// Hash any `data` which implements `Encode`, for example a block header.
+/// This is the first 8 bytes of the blake2 hash of the encoding of `data`, so it is the same on
+/// every platform, but it is far too short to be secure like the hashes of a real blockchain.
+pub fn hash<T: Encode>(data: &T) -> u64 {
+	let digest = blake2_256(&data.encode());
+	u64::from_le_bytes(digest[..8].try_into().expect("digest is 32 bytes"))
+}
+
+/// The root of the state of the runtime: a single hash which commits to all of the storage of all
+/// of the pallets. If any storage changes, so does the state root.
+pub type StateRoot = [u8; 32];
+
+/// Compute the `StateRoot` of any `state` which implements `Encode`.
+///
+/// The encoding of the state is fed into the blake2 hash function. Since all of our storage is kept
+/// in `BTreeMap`s, and the encoding does not depend on the platform, every node computes the same
+/// state root for the same state. Unlike a real blockchain, we do not build a merkle trie, so this
+/// cannot be used to prove the value of a single storage item.
+pub fn state_root<T: Encode>(state: &T) -> StateRoot {
+	blake2_256(&state.encode())
+}
+
+// The 32 byte blake2 hash of `bytes`.
+fn blake2_256(bytes: &[u8]) -> [u8; 32] {
+	use blake2::{digest::consts::U32, Blake2b, Digest};
+	Blake2b::<U32>::digest(bytes).into()
+}
+
+/// Write the storage value `item` of `pallet` as a single line of a snapshot of the state, like
+/// `timestamp.now = 10`. Values are written with their `Debug` format.
+///
+/// Unlike the state root, a snapshot is human readable, and can be compared line by line. See
+/// `Runtime::snapshot`.
+pub fn write_snapshot_value(
+	f: &mut impl std::fmt::Write,
+	pallet: &str,
+	item: &str,
+	value: &impl std::fmt::Debug,
+) -> std::fmt::Result {
+	writeln!(f, "{pallet}.{item} = {value:?}")
+}
+
+/// Write every entry of the storage map `item` of `pallet` as a line of a snapshot of the state,
+/// like `balances.free["alice"] = 100`. The entries are sorted by key, so the snapshot does not
+/// depend on the order in which the storage backend iterates over them.
+pub fn write_snapshot_map<'a, K, V>(
+	f: &mut impl std::fmt::Write,
+	pallet: &str,
+	item: &str,
+	entries: impl IntoIterator<Item = (&'a K, &'a V)>,
+) -> std::fmt::Result
+where
+	K: std::fmt::Debug + Ord + 'a,
+	V: std::fmt::Debug + 'a,
+{
+	let mut entries = entries.into_iter().collect::<Vec<_>>();
+	entries.sort_by_key(|(key, _)| *key);
+	for (key, value) in entries {
+		writeln!(f, "{pallet}.{item}[{key:?}] = {value:?}")?;
+	}
+	Ok(())
+}
+
+/// The states of the runtime before each of the most recent blocks it executed, which allows those
+/// blocks to be reverted.
+///
+/// Only the last `N` states are kept, like a ring buffer, so at most `N` blocks can be reverted.
+/// The `macros::runtime` macro records the state in the field of the `Runtime` marked with
+/// `#[history]`, and uses it in `revert_last_block`.
+#[derive(Clone)]
+pub struct BlockHistory<State, const N: usize> {
+	states: VecDeque<State>,
+}
+
+impl<State, const N: usize> BlockHistory<State, N> {
+	/// Record the `state` before a block was executed, forgetting the oldest state if there are
+	/// already `N` of them.
+	pub fn push(&mut self, state: State) {
+		if N == 0 {
+			return;
+		}
+		if self.states.len() == N {
+			self.states.pop_front();
+		}
+		self.states.push_back(state);
+	}
+
+	/// Take the state before the most recent block, if any.
+	#[allow(dead_code)]
+	pub fn pop(&mut self) -> Option<State> {
+		self.states.pop_back()
+	}
+
+	/// The number of blocks which can be reverted.
+	#[cfg(test)]
+	pub fn len(&self) -> usize {
+		self.states.len()
+	}
+
+	/// Check if there are no blocks which can be reverted.
+	#[cfg(test)]
+	pub fn is_empty(&self) -> bool {
+		self.states.is_empty()
+	}
+}
+
+impl<State, const N: usize> Default for BlockHistory<State, N> {
+	fn default() -> Self {
+		Self { states: VecDeque::with_capacity(N) }
+	}
+}
+
+// Each state is a whole copy of the runtime, so we only show how many there are.
+impl<State, const N: usize> core::fmt::Debug for BlockHistory<State, N> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("BlockHistory").field("len", &self.states.len()).finish()
+	}
+}
+
+/// A trait which allows pallets to execute logic at the start and end of each block.
+/// Both functions do nothing by default, so pallets only need to implement the hooks they use.
+pub trait Hooks<BlockNumber> {
+	/// Called at the start of each block, before any extrinsics are executed.
+	fn on_initialize(&mut self, _block_number: BlockNumber) {}
+	/// Called at the end of each block, after all extrinsics are executed.
+	fn on_finalize(&mut self, _block_number: BlockNumber) {}
+}
+
+/// A trait which allows the runtime to run the hooks of a single pallet.
+///
+/// Like `DispatchCall`, the `macros::call` macro implements this for any runtime which implements
+/// `AsMut<Pallet>`, by simply calling the `Hooks` of the pallet. Pallets whose hooks need more than
+/// their own storage implement this by hand instead.
+pub trait PalletHooks<Pallet, BlockNumber> {
+	/// Called at the start of each block, before any extrinsics are executed.
+	fn on_initialize(&mut self, _block_number: BlockNumber) {}
+	/// Called at the end of each block, after all extrinsics are executed.
+	fn on_finalize(&mut self, _block_number: BlockNumber) {}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		write_snapshot_map, write_snapshot_value, BlockBuilder, BlockHistory, DispatchError,
+		Encode, Extrinsic, Get, Header, Hooks, Origin,
+	};
+	use std::collections::BTreeMap;
+
+	// A simple module which counts the number of blocks it has seen.
+	struct BlockCounter {
+		blocks_seen: u32,
+	}
+
+	impl Hooks<u32> for BlockCounter {
+		fn on_finalize(&mut self, _block_number: u32) {
+			self.blocks_seen += 1;
+		}
+	}
+
+	#[test]
+	fn hooks() {
+		let mut counter = BlockCounter { blocks_seen: 0 };
+		for block_number in 1..=3 {
+			// `on_initialize` uses the default implementation, which does nothing.
+			counter.on_initialize(block_number);
+			assert_eq!(counter.blocks_seen, block_number - 1);
+			counter.on_finalize(block_number);
+		}
+		assert_eq!(counter.blocks_seen, 3);
+	}
+
+	parameter_types! {
+		// A constant used to configure `TestConfig`.
+		MaxLength: u32 = 8;
+		// The value can be any expression, not only a literal.
+		Owner: String = "alice".to_string();
+	}
+
+	// A configuration trait like those of our pallets, which needs a constant.
+	trait Config {
+		type MaxLength: Get<u32>;
+	}
+
+	struct TestConfig;
+	impl Config for TestConfig {
+		type MaxLength = MaxLength;
+	}
+
+	#[test]
+	fn parameter_types() {
+		assert_eq!(<TestConfig as Config>::MaxLength::get(), 8);
+		assert_eq!(Owner::get(), "alice");
+		// `()` can be used instead, when the default value is enough.
+		assert_eq!(<() as Get<u32>>::get(), 0);
+	}
+
+	#[test]
+	fn dispatch_errors() {
+		assert!(DispatchError::BadOrigin.is_fatal());
+		assert!(DispatchError::Exhausted.is_fatal());
+		assert!(DispatchError::Duplicate.is_fatal());
+		assert!(DispatchError::Full.is_fatal());
+		assert!(DispatchError::BadNonce.is_fatal());
+		assert!(DispatchError::Frozen.is_fatal());
+		assert!(DispatchError::CannotPayFee.is_fatal());
+		assert!(!DispatchError::from("Not enough funds.").is_fatal());
+		assert_eq!(<&str>::from(DispatchError::Exhausted), "block weight exceeded");
+		assert_eq!(DispatchError::Other("Not enough funds.").to_string(), "Not enough funds.");
+	}
+
+	#[test]
+	fn encoding() {
+		// Integers are little endian with their full width, and lengths always take 8 bytes.
+		assert_eq!(0x0102u16.encode(), vec![2, 1]);
+		assert_eq!(1usize.encode(), vec![1, 0, 0, 0, 0, 0, 0, 0]);
+		assert_eq!("ab".encode(), vec![2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']);
+		assert_eq!((true, None::<u8>, Some(7u8)).encode(), vec![1, 0, 1, 7]);
+
+		// Maps are encoded in the order of their keys, however they were built.
+		let map = BTreeMap::from([(2u8, 20u8), (1, 10)]);
+		assert_eq!(map.encode(), vec![2, 0, 0, 0, 0, 0, 0, 0, 1, 10, 2, 20]);
+
+		// A derived enum starts with the index of its variant.
+		assert_eq!(Origin::Signed(5u8).encode(), vec![0, 5]);
+		assert_eq!(Origin::<u8>::Root.encode(), vec![1]);
+		let header = Header { block_number: 1u32, parent_hash: 2u64, state_root: [3; 32] };
+		assert_eq!(header.encode()[..12], [1, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+		assert_eq!(header.encode().len(), 44);
+	}
+
+	#[test]
+	fn signatures() {
+		let extrinsic = Extrinsic::new_signed("alice", "transfer", 0u32);
+		assert!(extrinsic.verify_signed());
+
+		// Unsigned extrinsics are not verified.
+		let unsigned = Extrinsic { signature: None, ..extrinsic };
+		assert!(!unsigned.verify_signed());
+
+		// Reusing a signature with a different caller, call or nonce is detected.
+		let signature = Extrinsic::new_signed("alice", "transfer", 0u32).signature;
+		let forged = Extrinsic { caller: "bob", call: "transfer", nonce: 0u32, signature };
+		assert!(!forged.verify_signed());
+		let forged = Extrinsic { caller: "alice", call: "burn", nonce: 0u32, signature };
+		assert!(!forged.verify_signed());
+		let forged = Extrinsic { caller: "alice", call: "transfer", nonce: 1u32, signature };
+		assert!(!forged.verify_signed());
+	}
+
+	#[test]
+	fn block_builder() {
+		let block = BlockBuilder::<u32, u64, &str, &str, u32>::new().build();
+		assert_eq!(block.header, Header { block_number: 1, parent_hash: 0, state_root: [0; 32] });
+		assert!(block.extrinsics.is_empty());
+
+		// Each caller uses its own nonces, in the order its extrinsics were pushed.
+		let block = BlockBuilder::<u32, u64, _, _, u32>::new()
+			.block_number(2)
+			.parent_hash(7)
+			.state_root([1; 32])
+			.push_extrinsic("alice", "transfer")
+			.push_extrinsic("bob", "transfer")
+			.push_extrinsic("alice", "burn")
+			.build();
+		assert_eq!(block.header, Header { block_number: 2, parent_hash: 7, state_root: [1; 32] });
+		assert_eq!(
+			block.extrinsics,
+			vec![
+				Extrinsic::new_signed("alice", "transfer", 0),
+				Extrinsic::new_signed("bob", "transfer", 0),
+				Extrinsic::new_signed("alice", "burn", 1),
+			]
+		);
+	}
+
+	#[test]
+	fn block_history() {
+		let mut history = BlockHistory::<u32, 2>::default();
+		assert!(history.is_empty());
+		history.push(1);
+		history.push(2);
+		history.push(3);
+		// Only the two most recent states are kept.
+		assert_eq!(history.len(), 2);
+		assert_eq!(history.pop(), Some(3));
+		assert_eq!(history.pop(), Some(2));
+		assert_eq!(history.pop(), None);
+	}
+
+	#[test]
+	fn snapshot_lines() {
+		let mut snapshot = String::new();
+		write_snapshot_value(&mut snapshot, "test", "value", &Some(1)).unwrap();
+		// Map entries are sorted by key, whatever order they are given in.
+		let entries = [("bob".to_string(), 2), ("alice".to_string(), 1)];
+		let entries = entries.iter().map(|(key, value)| (key, value));
+		write_snapshot_map(&mut snapshot, "test", "map", entries).unwrap();
+		assert_eq!(
+			snapshot,
+			"test.value = Some(1)\ntest.map[\"alice\"] = 1\ntest.map[\"bob\"] = 2\n"
+		);
+	}
+
+	#[test]
+	fn origins() {
+		assert_eq!(Origin::Signed("alice").ensure_signed(), Ok("alice"));
+		assert_eq!(Origin::<&str>::Root.ensure_signed(), Err("call must be signed".into()));
+		assert_eq!(Origin::<&str>::None.ensure_signed(), Err("call must be signed".into()));
+
+		assert_eq!(Origin::<&str>::Root.ensure_root(), Ok(()));
+		assert_eq!(Origin::Signed("alice").ensure_root(), Err("call must be made by root".into()));
+		assert_eq!(Origin::<&str>::None.ensure_root(), Err("call must be made by root".into()));
+
+		assert_eq!(Origin::<&str>::None.ensure_none(), Ok(()));
+		assert_eq!(Origin::Signed("alice").ensure_none(), Err("call must be unsigned".into()));
+		assert_eq!(Origin::<&str>::Root.ensure_none(), Err("call must be unsigned".into()));
+	}
 }
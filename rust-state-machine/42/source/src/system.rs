@@ -1,6 +1,7 @@
+use crate::support::{write_snapshot_map, write_snapshot_value, Encode, Get, Hooks, Weight};
 use core::ops::AddAssign;
-use num::traits::{One, Zero};
-use std::collections::BTreeMap;
+use num::traits::{CheckedAdd, One, Zero};
+use std::collections::{BTreeMap, BTreeSet};
 
 /// The configuration trait for the System Pallet.
 /// This controls the common types used throughout our state machine.
@@ -10,20 +11,71 @@ pub trait Config {
 	type AccountId: Ord + Clone;
 	/// A type which can be used to represent the current block number.
 	/// Usually a basic unsigned integer.
-	type BlockNumber: Zero + One + AddAssign + Copy;
+	type BlockNumber: Zero + One + AddAssign + CheckedAdd + Copy + Ord;
 	/// A type which can be used to keep track of the number of transactions from each account.
 	/// Usually a basic unsigned integer.
-	type Nonce: Zero + One + Copy;
+	type Nonce: Zero + One + Copy + PartialEq;
+	/// A type which can represent the hash of a block.
+	/// The default value is used as the parent hash of the first block.
+	type Hash: Copy + Default;
+	/// The overarching event type of the runtime, which accumulates the events of all pallets.
+	type RuntimeEvent;
+	/// The maximum total weight of the extrinsics in a single block.
+	type MaxBlockWeight: Get<Weight>;
+	/// The number of past blocks whose events are kept, so they can be queried with `events_at`.
+	type EventHistoryDepth: Get<u32>;
+	/// The maximum number of extrinsics in a single block, however little they weigh.
+	type MaxExtrinsicsPerBlock: Get<u32>;
+}
+
+/// The part of a block in which an event was deposited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Phase {
+	/// While running the `on_initialize` hooks, before any extrinsic is applied.
+	Initialization,
+	/// While applying the extrinsic at this index of the block.
+	ApplyExtrinsic(u32),
+	/// While running the `on_finalize` hooks, after all extrinsics are applied.
+	Finalization,
+}
+
+/// An event deposited in the system pallet, along with the `phase` of the block in which it was
+/// deposited, so we know which extrinsic caused it.
+#[derive(Debug, Clone)]
+pub struct EventRecord<Event> {
+	/// The part of the block in which the event was deposited.
+	pub phase: Phase,
+	/// The event itself.
+	pub event: Event,
 }
 
 /// This is the System Pallet.
 /// It handles low level state needed for your blockchain.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[macros::storage]
 pub struct Pallet<T: Config> {
 	/// The current block number.
 	block_number: T::BlockNumber,
 	/// A map from an account to their nonce.
 	nonce: BTreeMap<T::AccountId, T::Nonce>,
+	/// A map from a block number to the hash of that block. The hashes are derived from block
+	/// headers which already contain a state root, so they are not part of it.
+	#[not_in_state_root]
+	block_hash: BTreeMap<T::BlockNumber, T::Hash>,
+	/// The accounts which are frozen, so the runtime rejects all of their extrinsics.
+	frozen: BTreeSet<T::AccountId>,
+	/// The part of the current block which is being executed, which is not part of the state root,
+	/// since it only changes during a block.
+	#[not_in_state_root]
+	phase: Phase,
+	/// The events deposited during the current block.
+	#[transient]
+	events: Vec<EventRecord<T::RuntimeEvent>>,
+	/// A map from a past block number to the events deposited during that block, for only the last
+	/// `EventHistoryDepth` blocks.
+	#[transient]
+	event_history: BTreeMap<T::BlockNumber, Vec<EventRecord<T::RuntimeEvent>>>,
 }
 
 /// The System Pallet is a low level system which is not really meant to be exposed to the outside
@@ -31,7 +83,21 @@ pub struct Pallet<T: Config> {
 impl<T: Config> Pallet<T> {
 	/// Create a new instance of the System Pallet.
 	pub fn new() -> Self {
-		Self { block_number: T::BlockNumber::zero(), nonce: BTreeMap::new() }
+		Self::new_at(T::BlockNumber::zero())
+	}
+
+	/// Create a new instance of the System Pallet, for a chain whose genesis is at block number
+	/// `start`. The first block executed after it must have the block number after `start`.
+	pub fn new_at(start: T::BlockNumber) -> Self {
+		Self {
+			block_number: start,
+			nonce: BTreeMap::new(),
+			block_hash: BTreeMap::new(),
+			frozen: BTreeSet::new(),
+			phase: Phase::Initialization,
+			events: Vec::new(),
+			event_history: BTreeMap::new(),
+		}
 	}
 
 	/// Get the current block number.
@@ -39,38 +105,391 @@ impl<T: Config> Pallet<T> {
 		self.block_number
 	}
 
+	/// Get the current nonce of an account `who`.
+	/// If the account has not made any transactions, we return zero.
+	pub fn nonce(&self, who: &T::AccountId) -> T::Nonce {
+		*self.nonce.get(who).unwrap_or(&T::Nonce::zero())
+	}
+
+	/// Freeze the account `who`, so the runtime rejects all of its extrinsics with "account frozen"
+	/// before they are dispatched, until it is thawed. Freezing a frozen account does nothing.
+	pub fn freeze(&mut self, who: T::AccountId) {
+		self.frozen.insert(who);
+	}
+
+	/// Thaw the account `who`, so its extrinsics can be dispatched again. Thawing an account which
+	/// is not frozen does nothing.
+	pub fn thaw(&mut self, who: &T::AccountId) {
+		self.frozen.remove(who);
+	}
+
+	/// Check if the account `who` is frozen.
+	pub fn is_frozen(&self, who: &T::AccountId) -> bool {
+		self.frozen.contains(who)
+	}
+
+	/// Get the hash of the block with block number `number`, if we have executed that block.
+	pub fn block_hash(&self, number: T::BlockNumber) -> Option<T::Hash> {
+		self.block_hash.get(&number).copied()
+	}
+
+	/// Get the hash of the most recently executed block, which must be the parent of the next block.
+	/// Before any block has been executed, this is the default "zero" hash.
+	pub fn parent_hash(&self) -> T::Hash {
+		self.block_hash(self.block_number).unwrap_or_default()
+	}
+
+	/// Get a pseudo-random value for `subject`, by mixing the bytes of the subject with the hash of
+	/// the most recently recorded block. Different subjects get different values, and the values
+	/// change with every block. Before any block hash is recorded, the default hash is used.
+	///
+	/// This is NOT secure randomness: anyone who knows the block hash can compute the value, and
+	/// the author of a block can choose its contents to get a value they like. It is only useful
+	/// for demos, or for games where nothing of value depends on the outcome.
+	pub fn random(&self, subject: &[u8]) -> T::Hash
+	where
+		T::Hash: Encode + From<u64>,
+	{
+		let latest_hash = self.block_hash.values().next_back().copied().unwrap_or_default();
+		T::Hash::from(crate::support::hash(&(latest_hash, subject)))
+	}
+
+	// Record the `hash` of the block with block number `number`.
+	pub fn set_block_hash(&mut self, number: T::BlockNumber, hash: T::Hash) {
+		self.block_hash.insert(number, hash);
+	}
+
+	/// Get the part of the current block which is being executed.
+	pub fn phase(&self) -> Phase {
+		self.phase
+	}
+
+	/// Get the index of the extrinsic which is being applied, if any.
+	/// This is `None` while the hooks at the start or end of a block are running.
+	pub fn extrinsic_index(&self) -> Option<u32> {
+		match self.phase {
+			Phase::ApplyExtrinsic(index) => Some(index),
+			Phase::Initialization | Phase::Finalization => None,
+		}
+	}
+
+	// Set the part of the current block which is being executed. The runtime sets this before
+	// running the hooks, and before applying each extrinsic.
+	pub fn set_phase(&mut self, phase: Phase) {
+		self.phase = phase;
+	}
+
+	/// Get all of the events deposited during the current block, along with their phase.
+	pub fn events(&self) -> &[EventRecord<T::RuntimeEvent>] {
+		&self.events
+	}
+
+	// Deposit an `event` which happened during the current phase of the block.
+	pub fn deposit_event(&mut self, event: T::RuntimeEvent) {
+		self.events.push(EventRecord { phase: self.phase, event });
+	}
+
+	/// Get all of the events deposited during the block with block number `number`, along with
+	/// their phase. Only the events of the current block, and of the last `EventHistoryDepth` blocks
+	/// before it, can be queried.
+	pub fn events_at(&self, number: T::BlockNumber) -> Option<&Vec<EventRecord<T::RuntimeEvent>>> {
+		match number == self.block_number {
+			true => Some(&self.events),
+			false => self.event_history.get(&number),
+		}
+	}
+
+	// Move the events deposited so far into the event history, under the current block number.
+	// Used at the start of each block, before the block number is incremented.
+	pub fn reset_events(&mut self) {
+		let events = core::mem::take(&mut self.events);
+		self.event_history.insert(self.block_number, events);
+	}
+
+	/// Write the storage of the System Pallet to a snapshot of the state, one line per storage
+	/// value or map entry. Like the state root, this does not include the events or the block
+	/// hashes. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+		T::Nonce: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "system", "block_number", &self.block_number)?;
+		write_snapshot_map(f, "system", "nonce", &self.nonce)?;
+		write_snapshot_map(f, "system", "frozen", self.frozen.iter().map(|who| (who, &true)))
+	}
+
+	/// Move the nonce of `from` to `to`, along with whether it is frozen, so the account can carry
+	/// on under its new id. Any nonce of `to` is overwritten. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		if let Some(nonce) = self.nonce.remove(from) {
+			self.nonce.insert(to.clone(), nonce);
+		}
+		if self.frozen.remove(from) {
+			self.frozen.insert(to.clone());
+		}
+	}
+
 	// This function can be used to increment the block number.
-	// Increases the block number by one.
-	pub fn inc_block_number(&mut self) {
-		self.block_number += T::BlockNumber::one();
+	// Increases the block number by one, or returns an error if that overflows, in which case the
+	// block number is unchanged.
+	pub fn inc_block_number(&mut self) -> crate::support::DispatchResult {
+		self.block_number = self
+			.block_number
+			.checked_add(&T::BlockNumber::one())
+			.ok_or("block number overflow")?;
+		Ok(())
 	}
 
 	// Increment the nonce of an account. This helps us keep track of how many transactions each
 	// account has made.
 	pub fn inc_nonce(&mut self, who: &T::AccountId) {
-		let nonce = *self.nonce.get(&who).unwrap_or(&T::Nonce::zero());
-		let new_nonce = nonce + T::Nonce::one();
+		let new_nonce = self.nonce(who) + T::Nonce::one();
 		self.nonce.insert(who.clone(), new_nonce);
 	}
+
+	// Check that `expected` is the current nonce of an account, without incrementing it.
+	pub fn check_nonce(
+		&self,
+		who: &T::AccountId,
+		expected: T::Nonce,
+	) -> crate::support::DispatchResult {
+		if self.nonce(who) != expected {
+			return Err(crate::support::DispatchError::BadNonce);
+		}
+		Ok(())
+	}
+
+	// Check that `expected` is the current nonce of an account, and then increment it.
+	// This protects against the same transaction being executed more than once.
+	pub fn check_and_increment_nonce(
+		&mut self,
+		who: &T::AccountId,
+		expected: T::Nonce,
+	) -> crate::support::DispatchResult {
+		self.check_nonce(who, expected)?;
+		self.inc_nonce(who);
+		Ok(())
+	}
+}
+
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+	// At the start of each block, the events of the blocks which are now too old are pruned from the
+	// event history, so it keeps at most `EventHistoryDepth` blocks.
+	fn on_initialize(&mut self, _block_number: T::BlockNumber) {
+		let depth = T::EventHistoryDepth::get() as usize;
+		while self.event_history.len() > depth {
+			self.event_history.pop_first();
+		}
+	}
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_to_owned)]
 mod test {
 	struct TestConfig;
 	impl super::Config for TestConfig {
 		type AccountId = String;
 		type BlockNumber = u32;
 		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
 	}
 
 	#[test]
 	fn init_system() {
 		let mut system = super::Pallet::<TestConfig>::new();
-		system.inc_block_number();
+		system.inc_block_number().unwrap();
 		system.inc_nonce(&"alice".to_string());
 
 		assert_eq!(system.block_number(), 1);
 		assert_eq!(system.nonce.get(&"alice".to_string()), Some(&1));
 		assert_eq!(system.nonce.get(&"bob".to_string()), None);
 	}
+
+	#[test]
+	fn new_at() {
+		let mut system = super::Pallet::<TestConfig>::new_at(99);
+		assert_eq!(system.block_number(), 99);
+		// There is no block before the genesis, so it has the default parent hash.
+		assert_eq!(system.parent_hash(), 0);
+		system.inc_block_number().unwrap();
+		assert_eq!(system.block_number(), 100);
+	}
+
+	#[test]
+	fn block_number_overflow() {
+		let mut system = super::Pallet::<TestConfig>::new_at(u32::MAX);
+		assert_eq!(system.inc_block_number(), Err("block number overflow".into()));
+		// The block number does not wrap around to zero.
+		assert_eq!(system.block_number(), u32::MAX);
+	}
+
+	#[test]
+	fn check_and_increment_nonce() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		assert_eq!(
+			system.check_and_increment_nonce(&alice, 1),
+			Err(crate::support::DispatchError::BadNonce)
+		);
+		assert_eq!(system.check_and_increment_nonce(&alice, 0), Ok(()));
+		// The same nonce cannot be used twice.
+		assert_eq!(
+			system.check_and_increment_nonce(&alice, 0),
+			Err(crate::support::DispatchError::BadNonce)
+		);
+		assert_eq!(system.check_and_increment_nonce(&alice, 1), Ok(()));
+		assert_eq!(system.nonce.get("alice"), Some(&2));
+	}
+
+	#[test]
+	fn nonce() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		system.inc_nonce(&alice);
+		system.inc_nonce(&alice);
+		system.inc_nonce(&alice);
+		assert_eq!(system.nonce(&alice), 3);
+		assert_eq!(system.nonce(&"bob".to_string()), 0);
+	}
+
+	#[test]
+	fn block_hash() {
+		let mut system = super::Pallet::<TestConfig>::new();
+
+		system.set_block_hash(1, 111);
+		system.set_block_hash(2, 222);
+		assert_eq!(system.block_hash(1), Some(111));
+		assert_eq!(system.block_hash(2), Some(222));
+		assert_eq!(system.block_hash(3), None);
+	}
+
+	#[test]
+	fn parent_hash() {
+		let mut system = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(system.parent_hash(), 0);
+		system.inc_block_number().unwrap();
+		system.set_block_hash(1, 111);
+		assert_eq!(system.parent_hash(), 111);
+		system.inc_block_number().unwrap();
+		system.set_block_hash(2, 222);
+		assert_eq!(system.parent_hash(), 222);
+	}
+
+	#[test]
+	fn freeze_and_thaw() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+
+		assert!(!system.is_frozen(&alice));
+		system.freeze(alice.clone());
+		system.freeze(alice.clone());
+		assert!(system.is_frozen(&alice));
+		assert!(!system.is_frozen(&"bob".to_string()));
+		system.thaw(&alice);
+		assert!(!system.is_frozen(&alice));
+	}
+
+	#[test]
+	fn random() {
+		let mut system = super::Pallet::<TestConfig>::new();
+		system.set_block_hash(1, 111);
+
+		// The same subject and block hash always give the same value, but other subjects do not.
+		let value = system.random(b"lottery");
+		assert_eq!(system.random(b"lottery"), value);
+		assert_ne!(system.random(b"raffle"), value);
+
+		// The value changes with the most recent block hash.
+		system.set_block_hash(2, 222);
+		assert_ne!(system.random(b"lottery"), value);
+		let mut other = super::Pallet::<TestConfig>::new();
+		other.set_block_hash(1, 111);
+		assert_eq!(other.random(b"lottery"), value);
+	}
+
+	#[test]
+	fn events() {
+		let mut system = super::Pallet::<TestConfig>::new();
+
+		system.deposit_event(());
+		system.deposit_event(());
+		assert_eq!(system.events().len(), 2);
+		system.reset_events();
+		assert!(system.events().is_empty());
+	}
+
+	#[test]
+	fn events_at() {
+		use crate::support::Hooks;
+
+		// A system pallet which keeps the events of the last two blocks.
+		struct HistoryConfig;
+		struct EventHistoryDepth;
+		impl crate::support::Get<u32> for EventHistoryDepth {
+			fn get() -> u32 {
+				2
+			}
+		}
+		impl super::Config for HistoryConfig {
+			type AccountId = String;
+			type BlockNumber = u32;
+			type Nonce = u32;
+			type Hash = u64;
+			type RuntimeEvent = u32;
+			type MaxBlockWeight = ();
+			type EventHistoryDepth = EventHistoryDepth;
+			type MaxExtrinsicsPerBlock = ();
+		}
+
+		// Execute blocks 1 to 4, each depositing its block number as an event, like the runtime.
+		let mut system = super::Pallet::<HistoryConfig>::new();
+		for block_number in 1..=4 {
+			system.reset_events();
+			system.inc_block_number().unwrap();
+			system.on_initialize(block_number);
+			system.deposit_event(block_number);
+
+			let events = system.events_at(block_number).unwrap();
+			assert_eq!(
+				events.iter().map(|record| record.event).collect::<Vec<_>>(),
+				vec![block_number]
+			);
+		}
+
+		// The events of the last two blocks before the current one are kept, and older ones pruned.
+		assert!(system.events_at(4).is_some());
+		assert_eq!(system.events_at(3).unwrap()[0].event, 3);
+		assert_eq!(system.events_at(2).unwrap()[0].event, 2);
+		assert!(system.events_at(1).is_none());
+		assert!(system.events_at(5).is_none());
+	}
+
+	#[test]
+	fn event_phase() {
+		use super::Phase;
+
+		let mut system = super::Pallet::<TestConfig>::new();
+		system.deposit_event(());
+		system.set_phase(Phase::ApplyExtrinsic(0));
+		assert_eq!(system.extrinsic_index(), Some(0));
+		system.deposit_event(());
+		system.set_phase(Phase::Finalization);
+		assert_eq!(system.extrinsic_index(), None);
+		system.deposit_event(());
+
+		let phases = system.events().iter().map(|record| record.phase).collect::<Vec<_>>();
+		assert_eq!(
+			phases,
+			vec![Phase::Initialization, Phase::ApplyExtrinsic(0), Phase::Finalization]
+		);
+	}
 }
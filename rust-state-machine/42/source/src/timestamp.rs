@@ -0,0 +1,172 @@
+use crate::support::{write_snapshot_value, DispatchContext, DispatchResult, Hooks};
+use num::traits::Zero;
+
+/// The configuration trait for the Timestamp Module.
+pub trait Config: crate::system::Config {
+	/// A type which can represent a moment in time.
+	/// Usually an unsigned integer, like the number of milliseconds since the unix epoch.
+	type Moment: Zero + Copy + PartialOrd;
+}
+
+/// The events which can be emitted by the Timestamp Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// The timestamp of the current block was set to `now`.
+	Set { now: T::Moment },
+}
+
+/// This is the Timestamp Module.
+/// It keeps track of the current time, which is set once in every block.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The timestamp of the current block.
+	now: T::Moment,
+	/// Whether the timestamp has already been set in the current block.
+	did_update: bool,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Timestamp Module.
+	pub fn new() -> Self {
+		Self { now: T::Moment::zero(), did_update: false, events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Timestamp Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::Moment: core::fmt::Debug,
+	{
+		write_snapshot_value(f, "timestamp", "now", &self.now)?;
+		write_snapshot_value(f, "timestamp", "did_update", &self.did_update)
+	}
+
+	/// Get the timestamp of the current block.
+	pub fn now(&self) -> T::Moment {
+		self.now
+	}
+}
+
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
+	// Every block gets to set the timestamp once.
+	fn on_initialize(&mut self, _block_number: T::BlockNumber) {
+		self.did_update = false;
+	}
+}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Set the timestamp of the current block to `moment`.
+	/// The timestamp is set by the block author, so the call must be unsigned: no account can set
+	/// the time.
+	/// This function will return an error if the origin of the `context` is not `None`, if the
+	/// timestamp has already been set in this block, or if `moment` is not later than the timestamp
+	/// of the previous block.
+	pub fn set(
+		&mut self,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		moment: T::Moment,
+	) -> DispatchResult {
+		context.origin.ensure_none()?;
+		if self.did_update {
+			return Err("timestamp must be set only once per block".into());
+		}
+		if moment <= self.now {
+			return Err("timestamp must increase".into());
+		}
+		self.now = moment;
+		self.did_update = true;
+		self.deposit_event(Event::Set { now: moment });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::support::{DispatchContext, Hooks, Origin};
+
+	struct TestConfig;
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {
+		type Moment = u64;
+	}
+
+	// The context of an unsigned call in block 1.
+	fn unsigned() -> DispatchContext<String, u32> {
+		DispatchContext { origin: Origin::None, block_number: 1 }
+	}
+
+	#[test]
+	fn timestamp_must_increase() {
+		let mut timestamp = super::Pallet::<TestConfig>::new();
+
+		assert_eq!(timestamp.now(), 0);
+		timestamp.on_initialize(1);
+		assert_eq!(timestamp.set(unsigned(), 0), Err("timestamp must increase".into()));
+		assert_eq!(timestamp.set(unsigned(), 1000), Ok(()));
+		assert_eq!(timestamp.now(), 1000);
+
+		timestamp.on_initialize(2);
+		assert_eq!(timestamp.set(unsigned(), 999), Err("timestamp must increase".into()));
+		assert_eq!(timestamp.set(unsigned(), 1000), Err("timestamp must increase".into()));
+		assert_eq!(timestamp.set(unsigned(), 2000), Ok(()));
+		assert_eq!(timestamp.now(), 2000);
+		assert_eq!(timestamp.take_events().len(), 2);
+	}
+
+	#[test]
+	fn timestamp_set_once_per_block() {
+		let mut timestamp = super::Pallet::<TestConfig>::new();
+
+		timestamp.on_initialize(1);
+		assert_eq!(timestamp.set(unsigned(), 1000), Ok(()));
+		assert_eq!(
+			timestamp.set(unsigned(), 2000),
+			Err("timestamp must be set only once per block".into())
+		);
+		assert_eq!(timestamp.now(), 1000);
+
+		// The next block can set the timestamp again.
+		timestamp.on_initialize(2);
+		assert_eq!(timestamp.set(unsigned(), 2000), Ok(()));
+		assert_eq!(timestamp.now(), 2000);
+	}
+
+	#[test]
+	fn signed_origin_cannot_set_timestamp() {
+		let mut timestamp = super::Pallet::<TestConfig>::new();
+		let alice = DispatchContext::signed("alice".to_string(), 1);
+
+		timestamp.on_initialize(1);
+		assert_eq!(timestamp.set(alice, 1000), Err("call must be unsigned".into()));
+		assert_eq!(timestamp.now(), 0);
+		// The failed call does not use up the update of this block.
+		assert_eq!(timestamp.set(unsigned(), 1000), Ok(()));
+	}
+}
@@ -0,0 +1,217 @@
+use crate::{
+	balances,
+	support::{write_snapshot_map, DispatchResult, Hooks},
+};
+use num::traits::Zero;
+use std::collections::BTreeMap;
+
+/// The configuration trait for the Tips Module.
+pub trait Config: balances::Config {}
+
+/// The events which can be emitted by the Tips Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `from` tipped `amount` to `to`.
+	Tipped { from: T::AccountId, to: T::AccountId, amount: T::Balance },
+}
+
+/// This is the Tips Module.
+/// It allows any account to tip another account, which immediately transfers the tip in the
+/// Balances Module. We keep track of how much each account has been tipped in total, so it can be
+/// shown on a leaderboard.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an account to the total amount it has been tipped.
+	received: BTreeMap<T::AccountId, T::Balance>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Tips Module.
+	pub fn new() -> Self {
+		Self { received: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Tips Module to a snapshot of the state, one line per storage value
+	/// or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "tips", "received", &self.received)
+	}
+
+	/// Get the total amount `who` has been tipped, across all of their tips.
+	/// If the account was never tipped, we return zero.
+	pub fn total_tipped(&self, who: &T::AccountId) -> T::Balance {
+		*self.received.get(who).unwrap_or(&T::Balance::zero())
+	}
+
+	/// Move the total amount `from` has been tipped to `to`, so it keeps its place on the
+	/// leaderboard. Any total of `to` is overwritten. See `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		if let Some(received) = self.received.remove(from) {
+			self.received.insert(to.clone(), received);
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Tips are transferred with the Balances Module, so our calls need access to it.
+#[macros::call(R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Tip `amount` from the `caller` to `beneficiary`, which is transferred straight away.
+	/// This function will return an error if the caller tips themselves, since that would inflate
+	/// their total for free, or if the transfer fails. See `balances::Pallet::transfer`.
+	pub fn tip<R>(
+		runtime: &mut R,
+		caller: T::AccountId,
+		beneficiary: T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		if caller == beneficiary {
+			return Err("cannot tip yourself".into());
+		}
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.transfer(caller.clone(), beneficiary.clone(), amount)?;
+
+		// The same balance can be tipped back and forth forever, so the total received by an
+		// account is not bounded by the total issuance, and saturates instead of overflowing.
+		let tips: &mut Self = runtime.as_mut();
+		let total = tips.received.entry(beneficiary.clone()).or_insert_with(T::Balance::zero);
+		*total = balances::saturating_add_balance(*total, amount);
+		tips.deposit_event(Event::Tipped { from: caller, to: beneficiary, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the tips pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		tips: super::Pallet<TestRuntime>,
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ();
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.tips
+		}
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	fn tip(beneficiary: &str, amount: u128) -> Call<TestRuntime> {
+		Call::tip { beneficiary: beneficiary.to_string(), amount }
+	}
+
+	#[test]
+	fn tips_accumulate() {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), tips: super::Pallet::new() };
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		let charlie = "charlie".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		runtime.balances.set_balance(&charlie, 100);
+		assert_eq!(runtime.tips.total_tipped(&bob), 0);
+
+		// Tips move balance straight away, and add up across tippers.
+		assert_eq!(runtime.dispatch_call(context("alice"), tip("bob", 10)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("charlie"), tip("bob", 20)), Ok(()));
+		assert_eq!(runtime.dispatch_call(context("alice"), tip("bob", 5)), Ok(()));
+		assert_eq!(runtime.balances.balance(&alice), 85);
+		assert_eq!(runtime.balances.balance(&charlie), 80);
+		assert_eq!(runtime.balances.balance(&bob), 35);
+		assert_eq!(runtime.tips.total_tipped(&bob), 35);
+		assert_eq!(runtime.tips.total_tipped(&alice), 0);
+
+		// Tipping balance back does not reduce the total which was received.
+		assert_eq!(runtime.dispatch_call(context("bob"), tip("alice", 35)), Ok(()));
+		assert_eq!(runtime.tips.total_tipped(&bob), 35);
+		assert_eq!(runtime.tips.total_tipped(&alice), 35);
+		let events = runtime.tips.take_events();
+		assert_eq!(events.len(), 4);
+		assert!(matches!(&events[0], Event::Tipped { amount: 10, .. }));
+	}
+
+	#[test]
+	fn failed_tips_are_not_counted() {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), tips: super::Pallet::new() };
+		runtime.balances.set_balance(&"alice".to_string(), 10);
+
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), tip("bob", 11)),
+			Err("Not enough funds.".into())
+		);
+		assert_eq!(runtime.tips.total_tipped(&"bob".to_string()), 0);
+
+		// Tipping yourself is rejected, even though the transfer would succeed.
+		assert_eq!(
+			runtime.dispatch_call(context("alice"), tip("alice", 5)),
+			Err("cannot tip yourself".into())
+		);
+		assert_eq!(runtime.tips.total_tipped(&"alice".to_string()), 0);
+		assert!(runtime.tips.take_events().is_empty());
+	}
+}
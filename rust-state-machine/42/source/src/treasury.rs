@@ -0,0 +1,207 @@
+use crate::{
+	balances,
+	support::{DispatchResult, Get, Hooks},
+};
+
+/// The configuration trait for the Treasury Module.
+///
+/// The treasury account is the `Treasury` account of the Balances Module, which also collects the
+/// fees paid for extrinsics, unless the runtime burns them. See `balances::FeeDestination`.
+pub trait Config: balances::Config {}
+
+/// The events which can be emitted by the Treasury Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `amount` was spent from the pot, and given to `to`.
+	Spent { to: T::AccountId, amount: T::Balance },
+}
+
+/// This is the Treasury Module.
+/// It allows a privileged origin, like the sudo key, to spend the pot: the funds held by the
+/// treasury account in the Balances Module, like the fees paid for extrinsics and the dust of
+/// reaped accounts.
+#[derive(Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+// We cannot derive `Debug`, since that would only require `T` to implement it, and not our events.
+impl<T: Config> core::fmt::Debug for Pallet<T>
+where
+	Event<T>: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("Pallet").field("events", &self.events).finish()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Treasury Module.
+	pub fn new() -> Self {
+		Self { events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Get the well known account which holds the funds of the treasury.
+	pub fn account_id() -> T::AccountId {
+		T::Treasury::get()
+	}
+
+	/// Get the amount of balance which is available to spend, which is the balance of the
+	/// treasury account in `balances`.
+	pub fn pot(balances: &balances::Pallet<T>) -> T::Balance {
+		balances.balance(&Self::account_id())
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Spending moves funds out of the treasury account in the Balances Module, so our calls need
+// access to it.
+#[macros::call(R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Spend `amount` from the pot, by transferring it from the treasury account to `to`.
+	/// This is a privileged call, which the runtime only allows privileged origins, like the root
+	/// origin of the sudo key, to make. This function will return an error if the pot has less
+	/// than `amount`.
+	#[privileged]
+	pub fn spend<R>(runtime: &mut R, to: T::AccountId, amount: T::Balance) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		if Self::pot(balances) < amount {
+			return Err("not enough funds in the pot".into());
+		}
+		balances.transfer(Self::account_id(), to.clone(), amount)?;
+
+		let treasury: &mut Self = runtime.as_mut();
+		treasury.deposit_event(Event::Spent { to, amount });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext, DispatchResult, EnsurePrivileged, Origin},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the treasury pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		treasury: super::Pallet<TestRuntime>,
+	}
+
+	struct Treasury;
+	impl crate::support::Get<String> for Treasury {
+		fn get() -> String {
+			"treasury".to_string()
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ();
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = Treasury;
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.treasury
+		}
+	}
+
+	// Only the root origin can make privileged calls.
+	impl EnsurePrivileged<String> for TestRuntime {
+		fn ensure_privileged(&self, origin: &Origin<String>) -> DispatchResult {
+			origin.ensure_root()
+		}
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	// The context of a call made by the root origin in the first block.
+	fn root() -> DispatchContext<String, u32> {
+		DispatchContext { origin: Origin::Root, block_number: 1 }
+	}
+
+	#[test]
+	fn fees_are_spent() {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), treasury: super::Pallet::new() };
+		let bob = "bob".to_string();
+		let treasury = "treasury".to_string();
+		assert_eq!(super::Pallet::<TestRuntime>::account_id(), treasury);
+
+		// A fee is paid into the treasury account, which is the pot.
+		let pot = |runtime: &TestRuntime| super::Pallet::<TestRuntime>::pot(&runtime.balances);
+		runtime.balances.set_balance(&bob, 100);
+		runtime.balances.withdraw_fee(&bob, 30).unwrap();
+		assert_eq!(pot(&runtime), 30);
+
+		let spend = |amount| Call::spend { to: bob.clone(), amount };
+		assert_eq!(runtime.dispatch_call(root(), spend(10)), Ok(()));
+		assert_eq!(pot(&runtime), 20);
+		assert_eq!(runtime.balances.balance(&treasury), 20);
+		assert_eq!(runtime.balances.balance(&bob), 80);
+
+		// Only the root origin can spend, and not more than the pot.
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), spend(10)),
+			Err("call must be made by root".into())
+		);
+		assert_eq!(
+			runtime.dispatch_call(root(), spend(21)),
+			Err("not enough funds in the pot".into())
+		);
+		assert_eq!(pot(&runtime), 20);
+		assert_eq!(runtime.balances.balance(&treasury), 20);
+
+		let events = runtime.treasury.take_events();
+		assert!(matches!(&events[..], [Event::Spent { amount: 10, .. }]));
+	}
+}
@@ -0,0 +1,269 @@
+use crate::support::{write_snapshot_map, write_snapshot_value, DispatchResult};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The configuration trait for the Uniques Module.
+pub trait Config: crate::system::Config {
+	/// A type which uniquely identifies a non-fungible item.
+	/// Usually an unsigned integer.
+	type ItemId: Ord + Clone;
+}
+
+/// The events which can be emitted by the Uniques Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// `owner` minted the new item `item`.
+	Minted { item: T::ItemId, owner: T::AccountId },
+	/// The ownership of `item` was transferred from `from` to `to`.
+	Transferred { item: T::ItemId, from: T::AccountId, to: T::AccountId },
+	/// `owner` burned their item `item`, which no longer exists.
+	Burned { item: T::ItemId, owner: T::AccountId },
+}
+
+/// This is the Uniques Module.
+/// It keeps track of non-fungible items: unlike balance, each item is unique, and is owned by
+/// exactly one account.
+///
+/// Other pallets can lock an item, like the Auction Module does while it is being sold, so its
+/// owner cannot transfer or burn it until it is unlocked again.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an item to the account which owns it.
+	owners: BTreeMap<T::ItemId, T::AccountId>,
+	/// The items which are locked by another pallet.
+	locked: BTreeSet<T::ItemId>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Uniques Module.
+	pub fn new() -> Self {
+		Self { owners: BTreeMap::new(), locked: BTreeSet::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Uniques Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::ItemId: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "uniques", "owners", &self.owners)?;
+		write_snapshot_value(f, "uniques", "locked", &self.locked)
+	}
+
+	/// Get the owner of `item`, if it exists.
+	pub fn owner_of(&self, item: &T::ItemId) -> Option<&T::AccountId> {
+		self.owners.get(item)
+	}
+
+	/// Get every item owned by `who`, in order.
+	pub fn items_of<'a>(&'a self, who: &'a T::AccountId) -> impl Iterator<Item = &'a T::ItemId> {
+		self.owners.iter().filter(move |(_, owner)| *owner == who).map(|(item, _)| item)
+	}
+
+	/// Check if `item` is locked, so its owner cannot transfer or burn it.
+	pub fn is_locked(&self, item: &T::ItemId) -> bool {
+		self.locked.contains(item)
+	}
+
+	// Check that `item` exists, is owned by `who`, and is not locked.
+	fn ensure_owner(&self, who: &T::AccountId, item: &T::ItemId) -> DispatchResult {
+		let owner = self.owner_of(item).ok_or("item does not exist")?;
+		if owner != who {
+			return Err("caller does not own the item".into());
+		}
+		if self.is_locked(item) {
+			return Err("item is locked".into());
+		}
+		Ok(())
+	}
+
+	/// Lock `item`, which must be owned by `who`, so it cannot be transferred or burned until it
+	/// is unlocked.
+	/// This function will return an error if the item does not exist, if `who` does not own it, or
+	/// if it is already locked.
+	pub fn lock(&mut self, who: &T::AccountId, item: &T::ItemId) -> DispatchResult {
+		self.ensure_owner(who, item)?;
+		self.locked.insert(item.clone());
+		Ok(())
+	}
+
+	/// Unlock `item`, if it is locked.
+	pub fn unlock(&mut self, item: &T::ItemId) {
+		self.locked.remove(item);
+	}
+
+	/// Give all of the items owned by `from` to `to`, including the items which are locked. See
+	/// `Runtime::migrate_account`.
+	pub fn migrate_account(&mut self, from: &T::AccountId, to: &T::AccountId) {
+		for owner in self.owners.values_mut().filter(|owner| *owner == from) {
+			*owner = to.clone();
+		}
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+#[macros::call]
+impl<T: Config> Pallet<T> {
+	/// Mint a new item with the id `item`, owned by the `caller`.
+	/// This function will return an error if an item with that id already exists.
+	pub fn mint(&mut self, caller: T::AccountId, item: T::ItemId) -> DispatchResult {
+		if self.owners.contains_key(&item) {
+			return Err("item already exists".into());
+		}
+		self.owners.insert(item.clone(), caller.clone());
+		self.deposit_event(Event::Minted { item, owner: caller });
+		Ok(())
+	}
+
+	/// Transfer the ownership of `item` from the `caller` to `to`.
+	/// This function will return an error if the item does not exist, if the caller does not own
+	/// it, or if it is locked.
+	pub fn transfer(
+		&mut self,
+		caller: T::AccountId,
+		item: T::ItemId,
+		to: T::AccountId,
+	) -> DispatchResult {
+		self.ensure_owner(&caller, &item)?;
+		self.owners.insert(item.clone(), to.clone());
+		self.deposit_event(Event::Transferred { item, from: caller, to });
+		Ok(())
+	}
+
+	/// Burn `item`, so it no longer exists, and its id can be minted again.
+	/// This function will return an error if the item does not exist, if the caller does not own
+	/// it, or if it is locked.
+	pub fn burn(&mut self, caller: T::AccountId, item: T::ItemId) -> DispatchResult {
+		self.ensure_owner(&caller, &item)?;
+		self.owners.remove(&item);
+		self.deposit_event(Event::Burned { item, owner: caller });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	struct TestConfig;
+
+	impl crate::system::Config for TestConfig {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestConfig {
+		type ItemId = u32;
+	}
+
+	#[test]
+	fn mint() {
+		let mut uniques = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(uniques.owner_of(&0), None);
+		assert_eq!(uniques.mint(alice.clone(), 0), Ok(()));
+		assert_eq!(uniques.owner_of(&0), Some(&alice));
+
+		// An item can only be minted once, even by someone else.
+		assert_eq!(uniques.mint(alice.clone(), 0), Err("item already exists".into()));
+		assert_eq!(uniques.mint(bob.clone(), 0), Err("item already exists".into()));
+		assert_eq!(uniques.mint(bob.clone(), 1), Ok(()));
+		assert_eq!(uniques.owner_of(&1), Some(&bob));
+		assert_eq!(uniques.take_events().len(), 2);
+	}
+
+	#[test]
+	fn transfer() {
+		let mut uniques = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(
+			uniques.transfer(alice.clone(), 0, bob.clone()),
+			Err("item does not exist".into())
+		);
+		assert_eq!(uniques.mint(alice.clone(), 0), Ok(()));
+
+		// Only the owner can transfer an item.
+		assert_eq!(
+			uniques.transfer(bob.clone(), 0, bob.clone()),
+			Err("caller does not own the item".into())
+		);
+		assert_eq!(uniques.transfer(alice.clone(), 0, bob.clone()), Ok(()));
+		assert_eq!(uniques.owner_of(&0), Some(&bob));
+		assert_eq!(
+			uniques.transfer(alice.clone(), 0, alice.clone()),
+			Err("caller does not own the item".into())
+		);
+		assert_eq!(uniques.transfer(bob.clone(), 0, alice.clone()), Ok(()));
+		assert_eq!(uniques.owner_of(&0), Some(&alice));
+	}
+
+	#[test]
+	fn burn() {
+		let mut uniques = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(uniques.burn(alice.clone(), 0), Err("item does not exist".into()));
+		assert_eq!(uniques.mint(alice.clone(), 0), Ok(()));
+		assert_eq!(uniques.burn(bob.clone(), 0), Err("caller does not own the item".into()));
+		assert_eq!(uniques.burn(alice.clone(), 0), Ok(()));
+		assert_eq!(uniques.owner_of(&0), None);
+
+		// A burned item can no longer be transferred, but its id can be minted again.
+		assert_eq!(
+			uniques.transfer(alice.clone(), 0, bob.clone()),
+			Err("item does not exist".into())
+		);
+		assert_eq!(uniques.mint(bob.clone(), 0), Ok(()));
+		assert_eq!(uniques.owner_of(&0), Some(&bob));
+	}
+
+	#[test]
+	fn lock() {
+		let mut uniques = super::Pallet::<TestConfig>::new();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		assert_eq!(uniques.lock(&alice, &0), Err("item does not exist".into()));
+		assert_eq!(uniques.mint(alice.clone(), 0), Ok(()));
+		assert_eq!(uniques.lock(&bob, &0), Err("caller does not own the item".into()));
+		assert_eq!(uniques.lock(&alice, &0), Ok(()));
+		assert!(uniques.is_locked(&0));
+		assert_eq!(uniques.lock(&alice, &0), Err("item is locked".into()));
+
+		// A locked item cannot be transferred or burned by its owner.
+		assert_eq!(uniques.transfer(alice.clone(), 0, bob.clone()), Err("item is locked".into()));
+		assert_eq!(uniques.burn(alice.clone(), 0), Err("item is locked".into()));
+		assert_eq!(uniques.owner_of(&0), Some(&alice));
+
+		uniques.unlock(&0);
+		assert!(!uniques.is_locked(&0));
+		assert_eq!(uniques.transfer(alice.clone(), 0, bob.clone()), Ok(()));
+		assert_eq!(uniques.owner_of(&0), Some(&bob));
+	}
+}
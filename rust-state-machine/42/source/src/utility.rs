@@ -0,0 +1,289 @@
+use crate::support::{
+	CloneState, Dispatch, DispatchContext, DispatchError, DispatchResult, GetWeight, Hooks, Origin,
+	DEFAULT_WEIGHT,
+};
+
+/// The configuration trait for the Utility Module.
+pub trait Config: crate::system::Config {
+	/// The overarching call type of the runtime, which can be dispatched in a batch.
+	type RuntimeCall;
+}
+
+/// The events which can be emitted by the Utility Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// All of the calls in a batch made by `origin` were dispatched successfully.
+	BatchCompleted { origin: Origin<T::AccountId> },
+	/// The call at `index` in a batch made by `origin` failed with `error`, so the calls after it
+	/// were not dispatched.
+	BatchInterrupted { origin: Origin<T::AccountId>, index: u32, error: DispatchError },
+}
+
+/// This is the Utility Module.
+/// It allows an account to dispatch many calls at once, in a single extrinsic.
+#[derive(Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+// We cannot derive `Debug`, since that would only require `T` to implement it, and not our events.
+impl<T: Config> core::fmt::Debug for Pallet<T>
+where
+	Event<T>: core::fmt::Debug,
+{
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.debug_struct("Pallet").field("events", &self.events).finish()
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Utility Module.
+	pub fn new() -> Self {
+		Self { events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	// Dispatch each of the `calls` in the `context` of the batch in order, stopping at the first
+	// one which fails. Returns the index and the error of the call which failed, if any.
+	fn dispatch_batch<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		calls: Vec<T::RuntimeCall>,
+	) -> Result<(), (u32, DispatchError)>
+	where
+		R: AsMut<Self>
+			+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	{
+		for (index, call) in calls.into_iter().enumerate() {
+			if let Err(error) = runtime.dispatch(context.clone(), call) {
+				let index = index as u32;
+				let origin = context.origin;
+				runtime.as_mut().deposit_event(Event::BatchInterrupted { origin, index, error });
+				return Err((index, error));
+			}
+		}
+		runtime.as_mut().deposit_event(Event::BatchCompleted { origin: context.origin });
+		Ok(())
+	}
+}
+
+// This pallet does not need to do anything at the start or end of a block.
+impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {}
+
+// Our calls need access to the whole runtime in order to dispatch the inner calls, and to undo them
+// when `batch_all` fails. A batch is only a little heavier than all of the calls it dispatches.
+#[macros::call(
+	R: CloneState
+		+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	T::RuntimeCall: GetWeight,
+)]
+impl<T: Config> Pallet<T> {
+	/// Dispatch each of the `calls` on behalf of the origin in `context` in order, stopping at the
+	/// first one which fails. The calls before it are still executed, so the batch itself always
+	/// succeeds, and the index of the call which failed is reported with a `BatchInterrupted`
+	/// event.
+	#[weight(calls.iter().fold(DEFAULT_WEIGHT, |total, call| total.saturating_add(call.weight())))]
+	pub fn batch<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		calls: Vec<T::RuntimeCall>,
+	) -> DispatchResult
+	where
+		R: AsMut<Self>
+			+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	{
+		// The failure is reported in the events, and does not fail the batch.
+		let _ = Self::dispatch_batch(runtime, context, calls);
+		Ok(())
+	}
+
+	/// Dispatch each of the `calls` on behalf of the origin in `context` in order, requiring all of
+	/// them to succeed. This function will return the error of the first call which fails, and the
+	/// calls before it are undone, by restoring the state of the runtime from before the batch. An
+	/// empty batch is rejected, since it is always a mistake.
+	#[weight(calls.iter().fold(DEFAULT_WEIGHT, |total, call| total.saturating_add(call.weight())))]
+	pub fn batch_all<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		calls: Vec<T::RuntimeCall>,
+	) -> DispatchResult
+	where
+		R: AsMut<Self>
+			+ CloneState
+			+ Dispatch<Caller = T::AccountId, BlockNumber = T::BlockNumber, Call = T::RuntimeCall>,
+	{
+		if calls.is_empty() {
+			return Err("batch must contain at least one call".into());
+		}
+		let snapshot = runtime.clone_state();
+		if let Err((_, error)) = Self::dispatch_batch(runtime, context, calls) {
+			runtime.restore_state(snapshot);
+			return Err(error);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, Event};
+	use crate::support::{
+		CloneState, Dispatch, DispatchCall, DispatchContext, DispatchError, DispatchResult,
+		GetWeight, Weight,
+	};
+
+	// A minimal runtime which only contains the utility pallet, and can dispatch `TestCall`s.
+	#[derive(Clone)]
+	struct TestRuntime {
+		utility: super::Pallet<TestRuntime>,
+		// Every `TestCall::Succeed` which was dispatched, by its id.
+		dispatched: Vec<u32>,
+	}
+
+	#[derive(Clone)]
+	enum TestCall {
+		Succeed(u32),
+		Fail,
+	}
+
+	impl CloneState for TestRuntime {
+		fn clone_state(&self) -> Self {
+			self.clone()
+		}
+
+		fn restore_state(&mut self, snapshot: Self) {
+			*self = snapshot;
+		}
+	}
+
+	impl GetWeight for TestCall {
+		fn weight(&self) -> Weight {
+			10
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl super::Config for TestRuntime {
+		type RuntimeCall = TestCall;
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.utility
+		}
+	}
+
+	impl Dispatch for TestRuntime {
+		type Caller = String;
+		type BlockNumber = u32;
+		type Call = TestCall;
+
+		fn dispatch(
+			&mut self,
+			_context: DispatchContext<String, u32>,
+			call: TestCall,
+		) -> DispatchResult {
+			match call {
+				TestCall::Succeed(id) => {
+					self.dispatched.push(id);
+					Ok(())
+				},
+				TestCall::Fail => Err("test call failed".into()),
+			}
+		}
+	}
+
+	fn new_runtime() -> TestRuntime {
+		TestRuntime { utility: super::Pallet::new(), dispatched: Vec::new() }
+	}
+
+	// The context of a call made by `caller` in the first block.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 1)
+	}
+
+	#[test]
+	fn batch() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		let calls = vec![TestCall::Succeed(0), TestCall::Succeed(1)];
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::batch { calls }), Ok(()));
+		assert_eq!(runtime.dispatched, vec![0, 1]);
+
+		// The batch stops at the failed call, but still succeeds, and reports the failed index.
+		let calls = vec![TestCall::Succeed(2), TestCall::Fail, TestCall::Succeed(3)];
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::batch { calls }), Ok(()));
+		assert_eq!(runtime.dispatched, vec![0, 1, 2]);
+
+		let events = runtime.utility.take_events();
+		assert!(matches!(
+			events[..],
+			[
+				Event::BatchCompleted { .. },
+				Event::BatchInterrupted {
+					index: 1,
+					error: DispatchError::Other("test call failed"),
+					..
+				}
+			]
+		));
+	}
+
+	#[test]
+	fn batch_all() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		let calls = vec![TestCall::Succeed(0), TestCall::Succeed(1)];
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::batch_all { calls }), Ok(()));
+		assert_eq!(runtime.dispatched, vec![0, 1]);
+
+		// The batch fails with the error of the failed call, and the calls before it are undone.
+		let calls = vec![TestCall::Succeed(2), TestCall::Fail, TestCall::Succeed(3)];
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::batch_all { calls }),
+			Err("test call failed".into())
+		);
+		assert_eq!(runtime.dispatched, vec![0, 1]);
+
+		assert_eq!(
+			runtime.dispatch_call(context(&alice), Call::batch_all { calls: vec![] }),
+			Err("batch must contain at least one call".into())
+		);
+
+		// Only the batch which succeeded is left in the events.
+		let events = runtime.utility.take_events();
+		assert!(matches!(events[..], [Event::BatchCompleted { .. }]));
+	}
+
+	#[test]
+	fn batch_weight() {
+		let calls = vec![TestCall::Succeed(0), TestCall::Fail];
+		assert_eq!(Call::<TestRuntime>::batch { calls }.weight(), 21);
+		assert_eq!(Call::<TestRuntime>::batch_all { calls: vec![] }.weight(), 1);
+	}
+}
@@ -0,0 +1,369 @@
+use crate::{
+	balances::{self, LockId},
+	support::{write_snapshot_map, DispatchContext, DispatchResult, PalletHooks},
+};
+use num::traits::{CheckedMul, CheckedSub, Zero};
+use std::collections::BTreeMap;
+
+/// The id of the lock which the Vesting Module places on the balance of vesting accounts.
+pub const VESTING_ID: LockId = *b"vesting ";
+
+/// The configuration trait for the Vesting Module.
+///
+/// Vesting schedules unlock some balance per block, so we need to be able to convert a number of
+/// blocks into a balance.
+pub trait Config:
+	balances::Config<Balance: From<<Self as crate::system::Config>::BlockNumber> + CheckedMul>
+{
+}
+
+/// A linear vesting schedule: `locked` balance which unlocks `per_block` after `starting_block`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, macros::Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VestingInfo<Balance, BlockNumber> {
+	/// The amount of balance locked when the schedule was created.
+	pub locked: Balance,
+	/// The amount of balance which unlocks every block.
+	pub per_block: Balance,
+	/// The block after which balance starts to unlock.
+	pub starting_block: BlockNumber,
+}
+
+/// The events which can be emitted by the Vesting Module.
+#[derive(Debug, Clone)]
+pub enum Event<T: Config> {
+	/// The balance of `who` which is still locked by vesting changed to `unvested`.
+	VestingUpdated { who: T::AccountId, unvested: T::Balance },
+	/// All of the balance of `who` has vested, and their schedule was removed.
+	VestingCompleted { who: T::AccountId },
+}
+
+/// This is the Vesting Module.
+/// It keeps track of vesting schedules, and locks the balance of each account which has not yet
+/// vested, using the locks of the Balances Module.
+#[derive(Debug, Clone)]
+#[macros::storage]
+pub struct Pallet<T: Config> {
+	/// A storage map from an account to its vesting schedule.
+	schedules: BTreeMap<T::AccountId, VestingInfo<T::Balance, T::BlockNumber>>,
+	/// The events emitted by this pallet, which have not yet been collected by the runtime.
+	#[transient]
+	events: Vec<Event<T>>,
+}
+
+impl<T: Config> Pallet<T> {
+	/// Create a new instance of the Vesting Module.
+	pub fn new() -> Self {
+		Self { schedules: BTreeMap::new(), events: Vec::new() }
+	}
+
+	// Emit an `event` from this pallet.
+	fn deposit_event(&mut self, event: Event<T>) {
+		self.events.push(event);
+	}
+
+	/// Take all of the events emitted by this pallet since the last time this was called.
+	/// The runtime uses this to collect our events into the system pallet.
+	pub fn take_events(&mut self) -> Vec<Event<T>> {
+		core::mem::take(&mut self.events)
+	}
+
+	/// Write the storage of the Vesting Module to a snapshot of the state, one line per storage
+	/// value or map entry. See `Runtime::snapshot`.
+	pub fn write_snapshot(&self, f: &mut impl core::fmt::Write) -> core::fmt::Result
+	where
+		T::AccountId: core::fmt::Debug,
+		T::Balance: core::fmt::Debug,
+		T::BlockNumber: core::fmt::Debug,
+	{
+		write_snapshot_map(f, "vesting", "schedules", &self.schedules)
+	}
+
+	/// Get the vesting schedule of `who`, if any.
+	pub fn vesting(&self, who: &T::AccountId) -> Option<&VestingInfo<T::Balance, T::BlockNumber>> {
+		self.schedules.get(who)
+	}
+
+	/// Get the amount of balance of `who` which has vested by block `now`.
+	/// Nothing vests until after the starting block, and at most `locked` can vest.
+	pub fn vested_balance(&self, who: &T::AccountId, now: T::BlockNumber) -> T::Balance {
+		let Some(schedule) = self.schedules.get(who) else { return T::Balance::zero() };
+		let elapsed = match T::Balance::from(now).checked_sub(&schedule.starting_block.into()) {
+			Some(elapsed) => elapsed,
+			None => return T::Balance::zero(),
+		};
+		match schedule.per_block.checked_mul(&elapsed) {
+			Some(vested) if vested < schedule.locked => vested,
+			_ => schedule.locked,
+		}
+	}
+
+	/// Get the amount of balance of `who` which is still locked by vesting at block `now`.
+	pub fn unvested_balance(&self, who: &T::AccountId, now: T::BlockNumber) -> T::Balance {
+		match self.schedules.get(who) {
+			// The vested balance is never more than the locked balance.
+			Some(schedule) => schedule
+				.locked
+				.checked_sub(&self.vested_balance(who, now))
+				.unwrap_or_else(T::Balance::zero),
+			None => T::Balance::zero(),
+		}
+	}
+
+	/// Give `who` a vesting schedule, when the runtime is first created. The `locked` balance must
+	/// already be in the free balance of `who`, and is locked until it vests.
+	/// This function will return an error if `who` already has a vesting schedule.
+	pub fn add_vesting_schedule<R>(
+		runtime: &mut R,
+		who: &T::AccountId,
+		schedule: VestingInfo<T::Balance, T::BlockNumber>,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let vesting: &mut Self = runtime.as_mut();
+		if vesting.schedules.contains_key(who) {
+			return Err("account already has a vesting schedule".into());
+		}
+		vesting.schedules.insert(who.clone(), schedule);
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		balances.set_lock(VESTING_ID, who, schedule.locked);
+		Ok(())
+	}
+
+	// Reduce the lock on the balance of `who` to the balance which has not yet vested in the block
+	// `now`, removing the lock and the schedule once everything has vested.
+	fn update_lock<R>(runtime: &mut R, who: T::AccountId, now: T::BlockNumber)
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let vesting: &mut Self = runtime.as_mut();
+		let unvested = vesting.unvested_balance(&who, now);
+
+		let balances: &mut balances::Pallet<T> = runtime.as_mut();
+		if unvested.is_zero() {
+			balances.remove_lock(VESTING_ID, &who);
+		} else {
+			balances.set_lock(VESTING_ID, &who, unvested);
+		}
+
+		let vesting: &mut Self = runtime.as_mut();
+		if unvested.is_zero() {
+			vesting.schedules.remove(&who);
+			vesting.deposit_event(Event::VestingCompleted { who });
+		} else {
+			vesting.deposit_event(Event::VestingUpdated { who, unvested });
+		}
+	}
+}
+
+// Vesting unlocks balance in the Balances Module, so both our calls and our hooks need access to it.
+// Our hooks are implemented by hand below.
+#[macros::call(custom_hooks, R: AsMut<balances::Pallet<T>>)]
+impl<T: Config> Pallet<T> {
+	/// Unlock the balance of `who` which has vested by the block of the `context`. Any signed
+	/// caller may do this for any account, since it can only ever reduce the lock to what the
+	/// schedule allows.
+	/// Locks are also updated at the start of every block, so this is only needed after a schedule
+	/// was added in the middle of a block.
+	/// This function will return an error if `who` does not have a vesting schedule.
+	pub fn vest<R>(
+		runtime: &mut R,
+		context: DispatchContext<T::AccountId, T::BlockNumber>,
+		who: T::AccountId,
+	) -> DispatchResult
+	where
+		R: AsMut<Self> + AsMut<balances::Pallet<T>>,
+	{
+		let DispatchContext { origin, block_number } = context;
+		origin.ensure_signed()?;
+		let vesting: &mut Self = runtime.as_mut();
+		if !vesting.schedules.contains_key(&who) {
+			return Err("account has no vesting schedule".into());
+		}
+		Self::update_lock(runtime, who, block_number);
+		Ok(())
+	}
+}
+
+// At the start of each block, we reduce the lock on each vesting account to the balance which has
+// not yet vested. This needs access to the Balances Module, so we implement `PalletHooks` by hand,
+// instead of implementing `Hooks` for the pallet.
+impl<T: Config, R> PalletHooks<Pallet<T>, T::BlockNumber> for R
+where
+	R: AsMut<Pallet<T>> + AsMut<balances::Pallet<T>>,
+{
+	fn on_initialize(&mut self, block_number: T::BlockNumber) {
+		let vesting: &mut Pallet<T> = self.as_mut();
+		let accounts = vesting.schedules.keys().cloned().collect::<Vec<_>>();
+		for who in accounts {
+			Pallet::update_lock(self, who, block_number);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Call, VestingInfo};
+	use crate::{
+		balances,
+		support::{DispatchCall, DispatchContext, PalletHooks},
+	};
+
+	// A minimal runtime which only contains the pallets needed by the vesting pallet.
+	struct TestRuntime {
+		balances: balances::Pallet<TestRuntime>,
+		vesting: super::Pallet<TestRuntime>,
+	}
+
+	struct ExistentialDeposit;
+	impl crate::support::Get<u128> for ExistentialDeposit {
+		fn get() -> u128 {
+			1
+		}
+	}
+
+	impl crate::system::Config for TestRuntime {
+		type AccountId = String;
+		type BlockNumber = u32;
+		type Nonce = u32;
+		type Hash = u64;
+		type RuntimeEvent = ();
+		type MaxBlockWeight = ();
+		type EventHistoryDepth = ();
+		type MaxExtrinsicsPerBlock = ();
+	}
+
+	impl balances::Config for TestRuntime {
+		type Balance = u128;
+		type ExistentialDeposit = ExistentialDeposit;
+		type MinimumTransfer = ();
+		type FeeMultiplier = ();
+		type Treasury = ();
+		type FeeDestination = ();
+		type DustDestination = ();
+		type OnTransfer = ();
+	}
+
+	impl super::Config for TestRuntime {}
+
+	impl AsMut<balances::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut balances::Pallet<TestRuntime> {
+			&mut self.balances
+		}
+	}
+
+	impl AsMut<super::Pallet<TestRuntime>> for TestRuntime {
+		fn as_mut(&mut self) -> &mut super::Pallet<TestRuntime> {
+			&mut self.vesting
+		}
+	}
+
+	// Run the hooks of the vesting pallet at the start of `block_number`.
+	fn on_initialize(runtime: &mut TestRuntime, block_number: u32) {
+		PalletHooks::<super::Pallet<TestRuntime>, _>::on_initialize(runtime, block_number);
+	}
+
+	// A runtime where alice has 100 balance, of which 50 vests at 10 per block after block 5.
+	fn new_runtime() -> TestRuntime {
+		let mut runtime =
+			TestRuntime { balances: balances::Pallet::new(), vesting: super::Pallet::new() };
+		let alice = "alice".to_string();
+		runtime.balances.set_balance(&alice, 100);
+		let schedule = VestingInfo { locked: 50, per_block: 10, starting_block: 5 };
+		assert_eq!(super::Pallet::add_vesting_schedule(&mut runtime, &alice, schedule), Ok(()));
+		runtime
+	}
+
+	// The context of a call made by `caller` in block 7.
+	fn context(caller: &str) -> DispatchContext<String, u32> {
+		DispatchContext::signed(caller.to_string(), 7)
+	}
+
+	#[test]
+	fn unlock_curve() {
+		let runtime = new_runtime();
+		let alice = "alice".to_string();
+
+		// Nothing vests before, or at, the starting block.
+		assert_eq!(runtime.vesting.vested_balance(&alice, 0), 0);
+		assert_eq!(runtime.vesting.vested_balance(&alice, 5), 0);
+		assert_eq!(runtime.vesting.unvested_balance(&alice, 5), 50);
+		// Then balance vests linearly.
+		assert_eq!(runtime.vesting.vested_balance(&alice, 6), 10);
+		assert_eq!(runtime.vesting.vested_balance(&alice, 8), 30);
+		assert_eq!(runtime.vesting.unvested_balance(&alice, 8), 20);
+		// Until everything has vested.
+		assert_eq!(runtime.vesting.vested_balance(&alice, 10), 50);
+		assert_eq!(runtime.vesting.vested_balance(&alice, 1000), 50);
+		assert_eq!(runtime.vesting.unvested_balance(&alice, 1000), 0);
+		// Accounts without a schedule have nothing vesting.
+		assert_eq!(runtime.vesting.vested_balance(&"bob".to_string(), 10), 0);
+	}
+
+	#[test]
+	fn vesting_updates_lock() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+		assert_eq!(runtime.balances.frozen_balance(&alice), 50);
+		assert_eq!(
+			super::Pallet::add_vesting_schedule(
+				&mut runtime,
+				&alice,
+				VestingInfo { locked: 10, per_block: 1, starting_block: 0 }
+			),
+			Err("account already has a vesting schedule".into())
+		);
+
+		// Before the schedule starts, only the unlocked balance can be transferred.
+		on_initialize(&mut runtime, 1);
+		assert_eq!(
+			runtime.balances.transfer(alice.clone(), bob.clone(), 51),
+			Err(balances::BalancesError::LiquidityRestrictions)
+		);
+
+		// Two blocks after the start, 20 more can be transferred.
+		on_initialize(&mut runtime, 7);
+		assert_eq!(runtime.balances.frozen_balance(&alice), 30);
+		assert_eq!(runtime.balances.transfer(alice.clone(), bob.clone(), 70), Ok(()));
+		assert_eq!(
+			runtime.balances.transfer(alice.clone(), bob.clone(), 1),
+			Err(balances::BalancesError::LiquidityRestrictions)
+		);
+
+		// Once fully vested, the lock and schedule are removed.
+		on_initialize(&mut runtime, 10);
+		assert_eq!(runtime.balances.frozen_balance(&alice), 0);
+		assert_eq!(runtime.vesting.vesting(&alice), None);
+		assert_eq!(runtime.balances.transfer(alice.clone(), bob.clone(), 30), Ok(()));
+		assert_eq!(runtime.vesting.take_events().len(), 3);
+	}
+
+	#[test]
+	fn vest_call() {
+		let mut runtime = new_runtime();
+		let alice = "alice".to_string();
+		let bob = "bob".to_string();
+
+		// A schedule added in the middle of block 7 locks everything until it is vested.
+		on_initialize(&mut runtime, 7);
+		runtime.balances.set_balance(&bob, 100);
+		let schedule = VestingInfo { locked: 50, per_block: 10, starting_block: 5 };
+		assert_eq!(super::Pallet::add_vesting_schedule(&mut runtime, &bob, schedule), Ok(()));
+		assert_eq!(runtime.balances.frozen_balance(&bob), 50);
+
+		// Anyone can unlock the balance which has vested so far.
+		assert_eq!(runtime.dispatch_call(context(&alice), Call::vest { who: bob.clone() }), Ok(()));
+		assert_eq!(runtime.balances.frozen_balance(&bob), 30);
+		// The lock follows the block the call is made in, not the last block the hooks ran in.
+		let later = DispatchContext::signed(alice.clone(), 8);
+		assert_eq!(runtime.dispatch_call(later, Call::vest { who: bob.clone() }), Ok(()));
+		assert_eq!(runtime.balances.frozen_balance(&bob), 20);
+		assert_eq!(
+			runtime.dispatch_call(context(&bob), Call::vest { who: "charlie".to_string() }),
+			Err("account has no vesting schedule".into())
+		);
+	}
+}
@@ -0,0 +1,7 @@
+// These tests check the code generated by our macros, by compiling small runtimes which use them.
+#[test]
+fn ui() {
+	let t = trybuild::TestCases::new();
+	t.pass("tests/ui/pass/*.rs");
+	t.compile_fail("tests/ui/fail/*.rs");
+}
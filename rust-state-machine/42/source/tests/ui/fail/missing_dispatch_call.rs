@@ -0,0 +1,77 @@
+// The staking pallet needs access to the balances pallet to dispatch its calls, so a runtime
+// which does not include the balances pallet cannot dispatch them.
+#![allow(dead_code)]
+
+#[path = "../../../src/balances.rs"]
+mod balances;
+#[path = "../../../src/staking.rs"]
+mod staking;
+#[path = "../../../src/support.rs"]
+mod support;
+#[path = "../../../src/system.rs"]
+mod system;
+
+mod types {
+	// A primitive type, so the error below is printed the same with or without the `serde` feature.
+	pub type AccountId = u64;
+	pub type Balance = u128;
+	pub type BlockNumber = u32;
+	pub type Nonce = u32;
+	pub type Hash = u64;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall, Nonce>;
+	pub type Header = crate::support::Header<BlockNumber, Hash>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[derive(Clone, Debug)]
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	staking: staking::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = types::Nonce;
+	type Hash = types::Hash;
+	type RuntimeEvent = RuntimeEvent;
+	type MaxBlockWeight = ();
+	type EventHistoryDepth = ();
+	type MaxExtrinsicsPerBlock = ();
+}
+
+impl balances::Config for Runtime {
+	type Balance = types::Balance;
+	type ExistentialDeposit = ();
+	type MinimumTransfer = ();
+	type FeeMultiplier = ();
+	type Treasury = ();
+	type FeeDestination = ();
+	type DustDestination = ();
+	type OnTransfer = ();
+}
+
+impl staking::Config for Runtime {}
+
+impl support::ChargeFee<types::AccountId> for Runtime {
+	fn charge_fee(
+		&mut self,
+		_who: &types::AccountId,
+		_weight: support::Weight,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+impl support::OnExtrinsic<types::AccountId, types::BlockNumber> for Runtime {
+	fn on_extrinsic(
+		&mut self,
+		_caller: &types::AccountId,
+		_block_number: types::BlockNumber,
+		_index: u32,
+	) {
+	}
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+// The name of each pallet in the runtime must match the name of its module.
+#![allow(dead_code)]
+
+#[path = "../../../src/balances.rs"]
+mod balances;
+#[path = "../../../src/support.rs"]
+mod support;
+#[path = "../../../src/system.rs"]
+mod system;
+
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	coins: balances::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = String;
+	type BlockNumber = u32;
+	type Nonce = u32;
+	type Hash = u64;
+	type RuntimeEvent = ();
+	type MaxBlockWeight = ();
+	type EventHistoryDepth = ();
+	type MaxExtrinsicsPerBlock = ();
+}
+
+impl balances::Config for Runtime {
+	type Balance = u128;
+	type ExistentialDeposit = ();
+	type MinimumTransfer = ();
+	type FeeMultiplier = ();
+	type Treasury = ();
+	type FeeDestination = ();
+	type DustDestination = ();
+	type OnTransfer = ();
+}
+
+fn main() {}
@@ -0,0 +1,214 @@
+// A minimal runtime with a pallet whose calls take arguments of many different shapes, which checks
+// that `#[macros::call]` and `#[macros::runtime]` can forward all of them.
+#![allow(dead_code)]
+
+#[path = "../../../src/support.rs"]
+mod support;
+#[path = "../../../src/system.rs"]
+mod system;
+
+mod shapes {
+	use crate::support::{DispatchContext, DispatchResult};
+
+	pub trait Config: crate::system::Config {
+		type Content: Clone;
+		type Balance: Copy;
+	}
+
+	#[derive(Debug, Clone)]
+	pub enum Event<T: Config> {
+		Called { who: T::AccountId, name: &'static str },
+		CalledByRoot { name: &'static str },
+	}
+
+	#[derive(Clone)]
+	pub struct Pallet<T: Config> {
+		events: Vec<Event<T>>,
+	}
+
+	impl<T: Config> core::fmt::Debug for Pallet<T> {
+		fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+			f.debug_struct("Pallet").finish_non_exhaustive()
+		}
+	}
+
+	impl<T: Config> crate::support::Encode for Pallet<T> {
+		fn encode_to(&self, _dest: &mut Vec<u8>) {}
+	}
+
+	impl<T: Config> Pallet<T> {
+		pub fn new() -> Self {
+			Self { events: Vec::new() }
+		}
+
+		pub fn take_events(&mut self) -> Vec<Event<T>> {
+			core::mem::take(&mut self.events)
+		}
+	}
+
+	impl<T: Config> crate::support::Hooks<T::BlockNumber> for Pallet<T> {}
+
+	#[macros::call]
+	impl<T: Config> Pallet<T> {
+		pub fn content(&mut self, caller: T::AccountId, _content: T::Content) -> DispatchResult {
+			self.events.push(Event::Called { who: caller, name: "content" });
+			Ok(())
+		}
+
+		pub fn account(&mut self, caller: T::AccountId, _who: T::AccountId) -> DispatchResult {
+			self.events.push(Event::Called { who: caller, name: "account" });
+			Ok(())
+		}
+
+		#[weight(3)]
+		pub fn pairs(
+			&mut self,
+			caller: T::AccountId,
+			pairs: Vec<(T::AccountId, T::Balance)>,
+		) -> DispatchResult {
+			if pairs.is_empty() {
+				return Err("no pairs".into());
+			}
+			self.events.push(Event::Called { who: caller, name: "pairs" });
+			Ok(())
+		}
+
+		pub fn optional(
+			&mut self,
+			caller: T::AccountId,
+			_maybe: Option<Box<T::Content>>,
+			_flag: bool,
+		) -> DispatchResult {
+			self.events.push(Event::Called { who: caller, name: "optional" });
+			Ok(())
+		}
+
+		pub fn no_arguments(&mut self, caller: T::AccountId) -> DispatchResult {
+			self.events.push(Event::Called { who: caller, name: "no_arguments" });
+			Ok(())
+		}
+
+		// A call can take the whole context instead of only the caller.
+		pub fn with_context(
+			&mut self,
+			context: DispatchContext<T::AccountId, T::BlockNumber>,
+			_content: T::Content,
+		) -> DispatchResult {
+			let who = context.origin.ensure_signed()?;
+			self.events.push(Event::Called { who, name: "with_context" });
+			Ok(())
+		}
+
+		// A privileged call takes no caller, since its origin may not be signed.
+		#[privileged]
+		pub fn privileged(&mut self, _content: T::Content) -> DispatchResult {
+			self.events.push(Event::CalledByRoot { name: "privileged" });
+			Ok(())
+		}
+	}
+}
+
+mod types {
+	pub type AccountId = String;
+	pub type BlockNumber = u32;
+	pub type Nonce = u32;
+	pub type Hash = u64;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall, Nonce>;
+	pub type Header = crate::support::Header<BlockNumber, Hash>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[derive(Clone, Debug)]
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	shapes: shapes::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = types::Nonce;
+	type Hash = types::Hash;
+	type RuntimeEvent = RuntimeEvent;
+	type MaxBlockWeight = ();
+	type EventHistoryDepth = ();
+	type MaxExtrinsicsPerBlock = ();
+}
+
+impl shapes::Config for Runtime {
+	type Content = String;
+	type Balance = u128;
+}
+
+// Only the root origin can make privileged calls.
+impl support::EnsurePrivileged<types::AccountId> for Runtime {
+	fn ensure_privileged(
+		&self,
+		origin: &support::Origin<types::AccountId>,
+	) -> support::DispatchResult {
+		origin.ensure_root()
+	}
+}
+
+impl support::ChargeFee<types::AccountId> for Runtime {
+	fn charge_fee(
+		&mut self,
+		_who: &types::AccountId,
+		_weight: support::Weight,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+impl support::OnExtrinsic<types::AccountId, types::BlockNumber> for Runtime {
+	fn on_extrinsic(
+		&mut self,
+		_caller: &types::AccountId,
+		_block_number: types::BlockNumber,
+		_index: u32,
+	) {
+	}
+}
+
+fn main() {
+	use support::{Dispatch, GetWeight};
+
+	let mut runtime = Runtime::new();
+	let alice = "alice".to_string();
+	let context = || support::DispatchContext::signed(alice.clone(), 1);
+	let root = || support::DispatchContext { origin: support::Origin::Root, block_number: 1 };
+	let none = || support::DispatchContext { origin: support::Origin::None, block_number: 1 };
+
+	let calls = vec![
+		shapes::Call::content { _content: "hello".to_string() },
+		shapes::Call::account { _who: "bob".to_string() },
+		shapes::Call::pairs { pairs: vec![("bob".to_string(), 10), ("charlie".to_string(), 20)] },
+		shapes::Call::optional { _maybe: Some(Box::new("hello".to_string())), _flag: true },
+		shapes::Call::no_arguments {},
+		shapes::Call::with_context { _content: "hello".to_string() },
+	];
+	for call in calls {
+		// Every call can be cloned, compared and hashed, as needed to sign it.
+		let call = RuntimeCall::shapes(call);
+		assert_eq!(call.clone(), call);
+		assert_eq!(support::hash(&call.clone()), support::hash(&call));
+		assert_eq!(runtime.dispatch(context(), call), Ok(()));
+	}
+	assert_eq!(runtime.shapes.take_events().len(), 6);
+
+	let call = RuntimeCall::shapes(shapes::Call::pairs { pairs: vec![] });
+	assert_eq!(call.weight(), 3);
+	assert_eq!(runtime.dispatch(context(), call), Err("no pairs".into()));
+
+	// Calls which take a caller must be signed.
+	let call = RuntimeCall::shapes(shapes::Call::no_arguments {});
+	assert_eq!(runtime.dispatch(root(), call.clone()), Err("call must be signed".into()));
+	assert_eq!(runtime.dispatch(none(), call), Err("call must be signed".into()));
+
+	// A privileged call rejects a signed origin, and is dispatched for the root origin.
+	let call = RuntimeCall::shapes(shapes::Call::privileged { _content: "hello".to_string() });
+	assert_eq!(runtime.dispatch(context(), call.clone()), Err("call must be made by root".into()));
+	assert_eq!(runtime.dispatch(root(), call), Ok(()));
+	assert_eq!(runtime.shapes.take_events().len(), 1);
+}
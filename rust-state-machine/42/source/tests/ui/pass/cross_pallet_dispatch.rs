@@ -0,0 +1,99 @@
+// A minimal runtime with the system, balances and staking pallets, which checks that
+// `#[macros::runtime]` can dispatch the calls of a pallet which needs access to another pallet.
+#![allow(dead_code)]
+
+#[path = "../../../src/balances.rs"]
+mod balances;
+#[path = "../../../src/staking.rs"]
+mod staking;
+#[path = "../../../src/support.rs"]
+mod support;
+#[path = "../../../src/system.rs"]
+mod system;
+
+mod types {
+	pub type AccountId = String;
+	pub type Balance = u128;
+	pub type BlockNumber = u32;
+	pub type Nonce = u32;
+	pub type Hash = u64;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall, Nonce>;
+	pub type Header = crate::support::Header<BlockNumber, Hash>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[derive(Clone, Debug)]
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	balances: balances::Pallet<Self>,
+	staking: staking::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = types::Nonce;
+	type Hash = types::Hash;
+	type RuntimeEvent = RuntimeEvent;
+	type MaxBlockWeight = ();
+	type EventHistoryDepth = ();
+	type MaxExtrinsicsPerBlock = ();
+}
+
+impl balances::Config for Runtime {
+	type Balance = types::Balance;
+	type ExistentialDeposit = ();
+	type MinimumTransfer = ();
+	type FeeMultiplier = ();
+	type Treasury = ();
+	type FeeDestination = ();
+	type DustDestination = ();
+	type OnTransfer = ();
+}
+
+impl staking::Config for Runtime {}
+
+impl support::ChargeFee<types::AccountId> for Runtime {
+	fn charge_fee(
+		&mut self,
+		_who: &types::AccountId,
+		_weight: support::Weight,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+impl support::OnExtrinsic<types::AccountId, types::BlockNumber> for Runtime {
+	fn on_extrinsic(
+		&mut self,
+		_caller: &types::AccountId,
+		_block_number: types::BlockNumber,
+		_index: u32,
+	) {
+	}
+}
+
+// No origin can make privileged calls.
+impl support::EnsurePrivileged<types::AccountId> for Runtime {
+	fn ensure_privileged(
+		&self,
+		_origin: &support::Origin<types::AccountId>,
+	) -> support::DispatchResult {
+		Err("no privileged accounts".into())
+	}
+}
+
+fn main() {
+	use support::Dispatch;
+
+	let mut runtime = Runtime::new();
+	let alice = "alice".to_string();
+	runtime.balances.set_balance(&alice, 100);
+
+	let call = RuntimeCall::staking(staking::Call::bond { amount: 60 });
+	let context = support::DispatchContext::signed(alice.clone(), 1);
+	assert_eq!(runtime.dispatch(context, call), Ok(()));
+	assert_eq!(runtime.staking.bonded(&alice), 60);
+	assert_eq!(runtime.balances.balance(&alice), 40);
+}
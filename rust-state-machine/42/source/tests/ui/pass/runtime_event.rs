@@ -0,0 +1,93 @@
+// A minimal runtime with the system and balances pallets, which checks that `#[macros::runtime]`
+// generates a `RuntimeEvent` enum which the events of every pallet convert into.
+#![allow(dead_code)]
+
+#[path = "../../../src/balances.rs"]
+mod balances;
+#[path = "../../../src/support.rs"]
+mod support;
+#[path = "../../../src/system.rs"]
+mod system;
+
+mod types {
+	pub type AccountId = String;
+	pub type Balance = u128;
+	pub type BlockNumber = u32;
+	pub type Nonce = u32;
+	pub type Hash = u64;
+	pub type Extrinsic = crate::support::Extrinsic<AccountId, crate::RuntimeCall, Nonce>;
+	pub type Header = crate::support::Header<BlockNumber, Hash>;
+	pub type Block = crate::support::Block<Header, Extrinsic>;
+}
+
+#[derive(Clone, Debug)]
+#[macros::runtime]
+pub struct Runtime {
+	system: system::Pallet<Self>,
+	balances: balances::Pallet<Self>,
+}
+
+impl system::Config for Runtime {
+	type AccountId = types::AccountId;
+	type BlockNumber = types::BlockNumber;
+	type Nonce = types::Nonce;
+	type Hash = types::Hash;
+	type RuntimeEvent = RuntimeEvent;
+	type MaxBlockWeight = ();
+	type EventHistoryDepth = ();
+	type MaxExtrinsicsPerBlock = ();
+}
+
+impl balances::Config for Runtime {
+	type Balance = types::Balance;
+	type ExistentialDeposit = ();
+	type MinimumTransfer = ();
+	type FeeMultiplier = ();
+	type Treasury = ();
+	type FeeDestination = ();
+	type DustDestination = ();
+	type OnTransfer = ();
+}
+
+impl support::ChargeFee<types::AccountId> for Runtime {
+	fn charge_fee(
+		&mut self,
+		_who: &types::AccountId,
+		_weight: support::Weight,
+	) -> support::DispatchResult {
+		Ok(())
+	}
+}
+
+impl support::OnExtrinsic<types::AccountId, types::BlockNumber> for Runtime {
+	fn on_extrinsic(
+		&mut self,
+		_caller: &types::AccountId,
+		_block_number: types::BlockNumber,
+		_index: u32,
+	) {
+	}
+}
+
+// No origin can make privileged calls.
+impl support::EnsurePrivileged<types::AccountId> for Runtime {
+	fn ensure_privileged(
+		&self,
+		_origin: &support::Origin<types::AccountId>,
+	) -> support::DispatchResult {
+		Err("no privileged accounts".into())
+	}
+}
+
+fn main() {
+	let event: RuntimeEvent = balances::Event::<Runtime>::Transferred {
+		from: "alice".to_string(),
+		to: "bob".to_string(),
+		amount: 10,
+	}
+	.into();
+	assert!(matches!(
+		event,
+		RuntimeEvent::balances(balances::Event::Transferred { amount: 10, .. })
+	));
+}